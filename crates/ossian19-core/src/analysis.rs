@@ -0,0 +1,90 @@
+//! Spectrum snapshots for editor previews (Goertzel per bin, no FFT dependency)
+
+use std::f32::consts::PI;
+
+/// Lowest bin frequency in Hz
+const MIN_FREQ: f32 = 20.0;
+/// Highest bin frequency in Hz (or just below Nyquist, whichever is lower)
+const MAX_FREQ: f32 = 20000.0;
+
+/// Center frequency of `bin` out of `bins`, spaced logarithmically from 20 Hz to 20 kHz
+pub fn bin_frequency(bin: usize, bins: usize, sample_rate: f32) -> f32 {
+    let top = MAX_FREQ.min(sample_rate * 0.49);
+    if bins < 2 {
+        return MIN_FREQ;
+    }
+    MIN_FREQ * (top / MIN_FREQ).powf(bin as f32 / (bins - 1) as f32)
+}
+
+/// Magnitude spectrum of `samples` at `bins` log-spaced frequencies (see `bin_frequency`).
+/// The input is Hann-windowed; a full-scale sine on a bin reads about 1.0.
+pub fn spectrum_of(samples: &[f32], sample_rate: f32, bins: usize) -> Vec<f32> {
+    let len = samples.len();
+    if len == 0 {
+        return vec![0.0; bins];
+    }
+    let window: Vec<f32> = (0..len)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / len as f32).cos())
+        .collect();
+    let window_sum: f32 = window.iter().sum();
+
+    (0..bins)
+        .map(|bin| {
+            let coeff = 2.0 * (2.0 * PI * bin_frequency(bin, bins, sample_rate) / sample_rate).cos();
+            let (mut s1, mut s2) = (0.0f32, 0.0f32);
+            for (sample, w) in samples.iter().zip(&window) {
+                let s0 = sample * w + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+            let power = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0);
+            2.0 * power.sqrt() / window_sum
+        })
+        .collect()
+}
+
+/// Share of a periodic signal's power that is not at harmonics of `freq`
+/// (`freq` and the buffer length chosen so harmonics land on exact DFT bins)
+#[cfg(test)]
+pub(crate) fn alias_ratio(buffer: &[f32], freq: f64, sample_rate: f64) -> f64 {
+    let n = buffer.len() as f64;
+    let total: f64 = buffer.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / n;
+    let mut harmonic = 0.0;
+    let mut h = 1.0;
+    while freq * h < sample_rate / 2.0 {
+        let w = 2.0 * std::f64::consts::PI * freq * h / sample_rate;
+        let (re, im) = buffer.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, &s)| {
+            (re + s as f64 * (w * i as f64).cos(), im - s as f64 * (w * i as f64).sin())
+        });
+        harmonic += 2.0 * (re * re + im * im) / (n * n);
+        h += 1.0;
+    }
+    (total - harmonic).max(0.0) / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_has_one_dominant_bin() {
+        let sample_rate = 44100.0;
+        let bins = 64;
+        // Put the sine exactly on a bin
+        let freq = bin_frequency(40, bins, sample_rate);
+        let samples: Vec<f32> = (0..4096)
+            .map(|i| (2.0 * PI * freq * i as f32 / sample_rate).sin())
+            .collect();
+
+        let spectrum = spectrum_of(&samples, sample_rate, bins);
+        assert_eq!(spectrum.len(), bins);
+        let peak = (0..bins).max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b])).unwrap();
+        assert_eq!(peak, 40);
+        assert!((spectrum[peak] - 1.0).abs() < 0.05, "{}", spectrum[peak]);
+        for (bin, magnitude) in spectrum.iter().enumerate() {
+            if bin != peak {
+                assert!(*magnitude < 0.05, "bin {} at {}", bin, magnitude);
+            }
+        }
+    }
+}
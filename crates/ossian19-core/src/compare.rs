@@ -0,0 +1,122 @@
+//! A/B compare of two stored parameter sets
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Normalized (0.0 - 1.0) parameter values keyed by parameter id
+pub type ParamSnapshot = BTreeMap<String, f32>;
+
+/// Compare slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompareSlot {
+    #[default]
+    A,
+    B,
+}
+
+impl CompareSlot {
+    pub fn other(&self) -> Self {
+        match self {
+            Self::A => Self::B,
+            Self::B => Self::A,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::A => "A",
+            Self::B => "B",
+        }
+    }
+}
+
+/// Two parameter sets, one of which is live; toggling stores the live values
+/// in the current slot and hands back the other slot's values to apply.
+/// `T` can bundle state that isn't a parameter (e.g. a full patch) with the values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbCompare<T = ParamSnapshot> {
+    a: Option<T>,
+    b: Option<T>,
+    active: CompareSlot,
+}
+
+impl<T> Default for AbCompare<T> {
+    fn default() -> Self {
+        Self { a: None, b: None, active: CompareSlot::A }
+    }
+}
+
+impl<T: Clone> AbCompare<T> {
+    /// Slot the live parameters belong to
+    pub fn active(&self) -> CompareSlot {
+        self.active
+    }
+
+    pub fn slot(&self, slot: CompareSlot) -> Option<&T> {
+        match slot {
+            CompareSlot::A => self.a.as_ref(),
+            CompareSlot::B => self.b.as_ref(),
+        }
+    }
+
+    fn slot_mut(&mut self, slot: CompareSlot) -> &mut Option<T> {
+        match slot {
+            CompareSlot::A => &mut self.a,
+            CompareSlot::B => &mut self.b,
+        }
+    }
+
+    /// Save `current` into the active slot, switch slots and return the values
+    /// to apply. An empty slot starts as a copy of `current`.
+    pub fn toggle(&mut self, current: T) -> T {
+        let next = self.active.other();
+        let target = self.slot(next).cloned().unwrap_or_else(|| current.clone());
+        *self.slot_mut(self.active) = Some(current);
+        *self.slot_mut(next) = Some(target.clone());
+        self.active = next;
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(cutoff: f32, resonance: f32) -> ParamSnapshot {
+        [("cutoff".to_string(), cutoff), ("reso".to_string(), resonance)].into_iter().collect()
+    }
+
+    #[test]
+    fn test_ab_toggle_round_trip() {
+        let original = snapshot(0.8, 0.2);
+        let mut compare = AbCompare::default();
+        assert_eq!(compare.active(), CompareSlot::A);
+
+        // B starts as a copy of A
+        let b = compare.toggle(original.clone());
+        assert_eq!(b, original);
+        assert_eq!(compare.active(), CompareSlot::B);
+
+        // Edit B, flip back to A, then to B and A again
+        let edited = snapshot(0.3, 0.9);
+        assert_eq!(compare.toggle(edited.clone()), original);
+        assert_eq!(compare.toggle(original.clone()), edited);
+        assert_eq!(compare.toggle(edited.clone()), original);
+        assert_eq!(compare.active(), CompareSlot::A);
+
+        let json = serde_json::to_string(&compare).unwrap();
+        assert_eq!(serde_json::from_str::<AbCompare>(&json).unwrap(), compare);
+    }
+
+    #[test]
+    fn test_ab_toggle_carries_extra_state() {
+        let mut compare: AbCompare<(ParamSnapshot, u32)> = AbCompare::default();
+        compare.toggle((snapshot(0.8, 0.2), 1));
+        assert_eq!(compare.toggle((snapshot(0.3, 0.9), 2)), (snapshot(0.8, 0.2), 1));
+        assert_eq!(compare.toggle((snapshot(0.8, 0.2), 1)), (snapshot(0.3, 0.9), 2));
+
+        let json = serde_json::to_string(&compare).unwrap();
+        assert_eq!(serde_json::from_str::<AbCompare<(ParamSnapshot, u32)>>(&json).unwrap(), compare);
+    }
+}
@@ -0,0 +1,374 @@
+use std::f32::consts::PI;
+
+use crate::filter::OnePoleHighPass;
+
+/// Feed-forward peak compressor for the summed output (auto-level)
+#[derive(Debug, Clone)]
+pub struct Compressor {
+    /// Level above which gain reduction starts, in dB
+    pub threshold_db: f32,
+    /// Compression ratio (4.0 = 4:1)
+    pub ratio: f32,
+
+    attack_ms: f32,
+    release_ms: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+    sample_rate: f32,
+}
+
+impl Compressor {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut comp = Self {
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 5.0,
+            release_ms: 150.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+            sample_rate,
+        };
+        comp.update_coefficients();
+        comp
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    /// Set attack time in milliseconds
+    pub fn set_attack_ms(&mut self, ms: f32) {
+        self.attack_ms = ms.max(0.0);
+        self.update_coefficients();
+    }
+
+    /// Set release time in milliseconds
+    pub fn set_release_ms(&mut self, ms: f32) {
+        self.release_ms = ms.max(0.0);
+        self.update_coefficients();
+    }
+
+    /// Set threshold (dB), ratio, attack and release (ms) together
+    pub fn set_params(&mut self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.threshold_db = threshold_db.clamp(-60.0, 0.0);
+        self.ratio = ratio.clamp(1.0, 20.0);
+        self.attack_ms = attack_ms.max(0.0);
+        self.release_ms = release_ms.max(0.0);
+        self.update_coefficients();
+    }
+
+    fn update_coefficients(&mut self) {
+        let coeff = |ms: f32| {
+            if ms <= 0.0 {
+                0.0
+            } else {
+                (-1.0 / (ms * 0.001 * self.sample_rate)).exp()
+            }
+        };
+        self.attack_coeff = coeff(self.attack_ms);
+        self.release_coeff = coeff(self.release_ms);
+    }
+
+    /// Process one sample
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> f32 {
+        input * self.gain(input.abs())
+    }
+
+    /// Process a stereo pair with linked gain, detected on the louder channel
+    #[inline]
+    pub fn tick_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if left == right {
+            let out = self.tick(left);
+            return (out, out);
+        }
+        let gain = self.gain(left.abs().max(right.abs()));
+        (left * gain, right * gain)
+    }
+
+    /// Follow `level` and return the linear gain to apply
+    #[inline]
+    fn gain(&mut self, level: f32) -> f32 {
+        let coeff = if level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope = level + coeff * (self.envelope - level);
+
+        let env_db = 20.0 * self.envelope.max(1e-6).log10();
+        let over = env_db - self.threshold_db;
+        if over <= 0.0 {
+            return 1.0;
+        }
+        let reduction_db = over * (1.0 - 1.0 / self.ratio.max(1.0));
+        10.0_f32.powf(-reduction_db / 20.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+/// Bit crusher and sample-rate reducer for the stereo output
+#[derive(Debug, Clone)]
+pub struct LoFi {
+    /// Resolution in bits (1 - 24)
+    pub bit_depth: u32,
+    /// Hold each input sample for this many output samples (1 = off)
+    pub downsample: usize,
+    /// Dry/wet (0.0 = bypass)
+    pub mix: f32,
+
+    held: (f32, f32),
+    hold_counter: usize,
+}
+
+impl Default for LoFi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoFi {
+    pub fn new() -> Self {
+        Self {
+            bit_depth: 16,
+            downsample: 1,
+            mix: 0.0,
+            held: (0.0, 0.0),
+            hold_counter: 0,
+        }
+    }
+
+    pub fn set_bit_depth(&mut self, bits: u32) {
+        self.bit_depth = bits.clamp(1, 24);
+    }
+
+    pub fn set_downsample(&mut self, factor: usize) {
+        self.downsample = factor.clamp(1, 64);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Process one stereo sample
+    #[inline]
+    pub fn tick(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.mix <= 0.0 {
+            return (left, right);
+        }
+
+        // Zero-order hold: take a new input every `downsample` samples
+        if self.hold_counter == 0 {
+            let step = 2.0 / (1u32 << self.bit_depth.clamp(1, 24)) as f32;
+            let crush = |x: f32| (x / step).round() * step;
+            self.held = (crush(left), crush(right));
+        }
+        self.hold_counter = (self.hold_counter + 1) % self.downsample.max(1);
+
+        let (wet_left, wet_right) = self.held;
+        let dry = 1.0 - self.mix;
+        (left * dry + wet_left * self.mix, right * dry + wet_right * self.mix)
+    }
+
+    pub fn reset(&mut self) {
+        self.held = (0.0, 0.0);
+        self.hold_counter = 0;
+    }
+}
+
+/// Corner of the output DC blocker in Hz (well below anything audible)
+const DC_BLOCKER_HZ: f32 = 5.0;
+
+/// High-pass that strips DC offset from the stereo output (AC coupling)
+#[derive(Debug, Clone)]
+pub struct DcBlocker {
+    left: OnePoleHighPass,
+    right: OnePoleHighPass,
+}
+
+impl DcBlocker {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut blocker = Self {
+            left: OnePoleHighPass::new(sample_rate),
+            right: OnePoleHighPass::new(sample_rate),
+        };
+        blocker.set_sample_rate(sample_rate);
+        blocker
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        for filter in [&mut self.left, &mut self.right] {
+            filter.set_sample_rate(sample_rate);
+            filter.set_cutoff(DC_BLOCKER_HZ);
+        }
+    }
+
+    /// Process one stereo sample
+    #[inline]
+    pub fn tick(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (self.left.tick(left), self.right.tick(right))
+    }
+
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+}
+
+/// Allpass corner at the lightest dispersion setting in Hz
+const DISPERSION_MAX_HZ: f32 = 8000.0;
+/// Allpass corner at full dispersion in Hz
+const DISPERSION_MIN_HZ: f32 = 400.0;
+
+/// Two first-order allpass stages per channel: smears phase to soften sharp
+/// transients while leaving the magnitude spectrum flat
+#[derive(Debug, Clone)]
+pub struct Dispersion {
+    /// 0.0 = bypass, 1.0 = lowest allpass corner
+    amount: f32,
+    coefficient: f32,
+    sample_rate: f32,
+    /// Last (input, output) of each stage, left channel first
+    state: [[(f32, f32); 2]; 2],
+}
+
+impl Dispersion {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            amount: 0.0,
+            coefficient: 0.0,
+            sample_rate,
+            state: [[(0.0, 0.0); 2]; 2],
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficient();
+    }
+
+    /// 0.0 = off; higher amounts move the allpass corner down from 8 kHz to 400 Hz
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount.clamp(0.0, 1.0);
+        self.update_coefficient();
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    fn update_coefficient(&mut self) {
+        let corner = DISPERSION_MAX_HZ * (DISPERSION_MIN_HZ / DISPERSION_MAX_HZ).powf(self.amount);
+        let t = (PI * corner.min(self.sample_rate * 0.45) / self.sample_rate).tan();
+        self.coefficient = (t - 1.0) / (t + 1.0);
+    }
+
+    /// Process one stereo sample
+    #[inline]
+    pub fn tick(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.amount <= 0.0 {
+            return (left, right);
+        }
+        let a = self.coefficient;
+        let mut out = [left, right];
+        for (sample, stages) in out.iter_mut().zip(self.state.iter_mut()) {
+            for (last_in, last_out) in stages.iter_mut() {
+                let y = a * *sample + *last_in - a * *last_out;
+                *last_in = *sample;
+                *last_out = y;
+                *sample = y;
+            }
+        }
+        (out[0], out[1])
+    }
+
+    pub fn reset(&mut self) {
+        self.state = [[(0.0, 0.0); 2]; 2];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressor_reduces_loud_signals() {
+        let mut comp = Compressor::new(44100.0);
+        // Quiet signals pass unchanged
+        for _ in 0..1000 {
+            assert_eq!(comp.tick(0.05), 0.05);
+        }
+
+        comp.reset();
+        let mut out = 0.0;
+        for _ in 0..44100 {
+            out = comp.tick(0.9);
+        }
+        // -18 dB threshold, 4:1: 0.9 (-0.9 dB) settles around -14.7 dB
+        assert!(out < 0.25 && out > 0.1, "settled at {}", out);
+    }
+
+    /// Share of power that is not at the first harmonic (`cycles` periods in the buffer)
+    fn distortion(buffer: &[f32], cycles: usize) -> f64 {
+        let n = buffer.len() as f64;
+        let total: f64 = buffer.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / n;
+        let w = 2.0 * std::f64::consts::PI * cycles as f64 / n;
+        let (re, im) = buffer.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, &s)| {
+            (re + s as f64 * (w * i as f64).cos(), im - s as f64 * (w * i as f64).sin())
+        });
+        (total - 2.0 * (re * re + im * im) / (n * n)) / total
+    }
+
+    #[test]
+    fn test_lofi() {
+        // 10 cycles of a 0.8 sine over 4410 samples
+        let sine: Vec<f32> = (0..4410)
+            .map(|i| 0.8 * (2.0 * std::f32::consts::PI * 10.0 * i as f32 / 4410.0).sin())
+            .collect();
+        let run = |lofi: &mut LoFi| sine.iter().map(|&s| lofi.tick(s, s).0).collect::<Vec<f32>>();
+
+        // Bypass is bit-identical
+        let mut lofi = LoFi::new();
+        lofi.set_bit_depth(4);
+        lofi.set_downsample(8);
+        assert_eq!(run(&mut lofi), sine);
+
+        // Fewer bits, more quantization harmonics
+        let mut lofi = LoFi::new();
+        lofi.set_mix(1.0);
+        lofi.set_bit_depth(12);
+        let fine = distortion(&run(&mut lofi), 10);
+        lofi.set_bit_depth(4);
+        let coarse = distortion(&run(&mut lofi), 10);
+        assert!(coarse > fine * 100.0, "{} vs {}", coarse, fine);
+
+        // Holding every 5th sample of 1 kHz (8820 Hz effective rate) mirrors
+        // an image to 8820 - 1000 = 7820 Hz, about sinc(7820 / 8820) = 0.13 of the tone
+        let tone: Vec<f32> = (0..4410)
+            .map(|i| 0.8 * (2.0 * std::f32::consts::PI * 100.0 * i as f32 / 4410.0).sin())
+            .collect();
+        let mut lofi = LoFi::new();
+        lofi.set_mix(1.0);
+        lofi.set_bit_depth(24);
+        lofi.set_downsample(5);
+        let held: Vec<f32> = tone.iter().map(|&s| lofi.tick(s, s).0).collect();
+        for block in held.chunks(5) {
+            assert!(block.iter().all(|&s| s == block[0]));
+        }
+        let bin = |cycles: f64| {
+            let w = 2.0 * std::f64::consts::PI * cycles / held.len() as f64;
+            let (re, im) = held.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, &s)| {
+                (re + s as f64 * (w * i as f64).cos(), im - s as f64 * (w * i as f64).sin())
+            });
+            (re * re + im * im).sqrt()
+        };
+        let image = bin(782.0) / bin(100.0);
+        assert!(image > 0.08 && image < 0.2, "{}", image);
+    }
+}
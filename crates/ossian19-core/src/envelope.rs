@@ -1,18 +1,52 @@
 use serde::{Deserialize, Serialize};
 
+/// Time constants an exponential segment takes to (nearly) reach its target
+const EXP_TIME_CONSTANTS: f32 = 5.0;
+/// Level (-80 dB) at which a release is considered finished
+const RELEASE_FLOOR: f32 = 0.0001;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum EnvelopeStage {
     #[default]
     Idle,
+    /// Holding before the attack starts
+    Delay,
     Attack,
     Decay,
     Sustain,
     Release,
 }
 
-/// ADSR Envelope Generator
+/// Shape of the attack, decay and release segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum EnvelopeCurve {
+    /// Straight-line segments
+    #[default]
+    Linear = 0,
+    /// RC-style segments: fast at first, easing into the target. Attack and decay
+    /// arrive after their set time (5 time constants); release reaches -80 dB
+    /// after its set time, like the linear release.
+    Exponential = 1,
+}
+
+/// What the envelope does when the decay reaches the sustain level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum EnvelopeLoop {
+    /// Hold at sustain until release
+    #[default]
+    Off = 0,
+    /// Retrigger the attack, cycling attack/decay until release
+    AttackDecay = 1,
+}
+
+/// DADSR Envelope Generator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Envelope {
+    /// Delay in seconds between trigger and the attack
+    #[serde(default)]
+    pub delay: f32,
     /// Attack time in seconds
     pub attack: f32,
     /// Decay time in seconds
@@ -21,6 +55,18 @@ pub struct Envelope {
     pub sustain: f32,
     /// Release time in seconds
     pub release: f32,
+    /// Attack overshoot above 1.0 before decaying to sustain (0.0 - 1.0)
+    #[serde(default)]
+    pub punch: f32,
+    /// Longest a release may take in seconds, whatever `release` is set to
+    #[serde(default = "default_max_release")]
+    pub max_release: f32,
+    /// Segment shape
+    #[serde(default)]
+    pub curve: EnvelopeCurve,
+    /// Attack/decay looping
+    #[serde(default)]
+    pub loop_mode: EnvelopeLoop,
 
     #[serde(skip)]
     stage: EnvelopeStage,
@@ -30,19 +76,31 @@ pub struct Envelope {
     sample_rate: f32,
     #[serde(skip)]
     release_level: f32,
+    #[serde(skip)]
+    delay_remaining: u32,
+    /// Multiplier on the attack, decay and release times (keyboard rate scaling)
+    #[serde(skip, default = "unity")]
+    time_scale: f32,
 }
 
 impl Default for Envelope {
     fn default() -> Self {
         Self {
+            delay: 0.0,
             attack: 0.01,
             decay: 0.1,
             sustain: 0.7,
             release: 0.3,
+            punch: 0.0,
+            max_release: default_max_release(),
+            curve: EnvelopeCurve::Linear,
+            loop_mode: EnvelopeLoop::Off,
             stage: EnvelopeStage::Idle,
             level: 0.0,
             sample_rate: 44100.0,
             release_level: 0.0,
+            delay_remaining: 0,
+            time_scale: 1.0,
         }
     }
 }
@@ -55,14 +113,89 @@ impl Envelope {
         }
     }
 
+    /// Create an envelope from ADSR values at the default sample rate
+    pub fn adsr(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+            ..Default::default()
+        }
+    }
+
+    /// Set delay time in seconds (builder style)
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set attack time in seconds (builder style)
+    pub fn with_attack(mut self, attack: f32) -> Self {
+        self.attack = attack;
+        self
+    }
+
+    /// Set decay time in seconds (builder style)
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Set sustain level (builder style)
+    pub fn with_sustain(mut self, sustain: f32) -> Self {
+        self.sustain = sustain;
+        self
+    }
+
+    /// Set release time in seconds (builder style)
+    pub fn with_release(mut self, release: f32) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Set the release time cap in seconds (builder style)
+    pub fn with_max_release(mut self, max_release: f32) -> Self {
+        self.max_release = max_release;
+        self
+    }
+
+    /// Set attack overshoot (builder style)
+    pub fn with_punch(mut self, punch: f32) -> Self {
+        self.punch = punch;
+        self
+    }
+
+    /// Set the segment shape (builder style)
+    pub fn with_curve(mut self, curve: EnvelopeCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Set the loop mode (builder style)
+    pub fn with_loop(mut self, loop_mode: EnvelopeLoop) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
     }
 
+    /// Scale the attack, decay and release times without changing the stored values
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    }
+
     /// Trigger the envelope (note on)
     pub fn trigger(&mut self) {
-        self.stage = EnvelopeStage::Attack;
         // Don't reset level - allows retriggering from current position
+        self.delay_remaining = (self.delay.max(0.0) * self.sample_rate) as u32;
+        self.stage = if self.delay_remaining > 0 {
+            EnvelopeStage::Delay
+        } else {
+            EnvelopeStage::Attack
+        };
     }
 
     /// Release the envelope (note off)
@@ -90,15 +223,26 @@ impl Envelope {
 
     /// Generate next envelope value
     pub fn tick(&mut self) -> f32 {
+        match self.curve {
+            EnvelopeCurve::Linear => self.tick_linear(),
+            EnvelopeCurve::Exponential => self.tick_exponential(),
+        }
+        self.level
+    }
+
+    fn tick_linear(&mut self) {
         match self.stage {
             EnvelopeStage::Idle => {
                 self.level = 0.0;
             }
+            EnvelopeStage::Delay => self.tick_delay(),
             EnvelopeStage::Attack => {
+                // Attack time stays the same with punch; the ramp is just steeper
+                let peak = self.peak();
                 let rate = self.calculate_rate(self.attack);
-                self.level += rate;
-                if self.level >= 1.0 {
-                    self.level = 1.0;
+                self.level += rate * peak;
+                if self.level >= peak {
+                    self.level = peak;
                     self.stage = EnvelopeStage::Decay;
                 }
             }
@@ -107,32 +251,101 @@ impl Envelope {
                 self.level -= rate;
                 if self.level <= self.sustain {
                     self.level = self.sustain;
-                    self.stage = EnvelopeStage::Sustain;
+                    self.end_decay();
                 }
             }
             EnvelopeStage::Sustain => {
                 self.level = self.sustain;
             }
             EnvelopeStage::Release => {
-                let rate = self.calculate_rate(self.release);
+                let rate = self.calculate_rate(self.release.min(self.max_release));
                 self.level -= rate * self.release_level;
                 // Use threshold to avoid denormals and long tails
-                if self.level <= 0.0001 {
+                if self.level <= RELEASE_FLOOR {
                     self.level = 0.0;
                     self.stage = EnvelopeStage::Idle;
                 }
             }
         }
+    }
 
-        self.level
+    fn tick_exponential(&mut self) {
+        // Segments snap to their target once within this fraction of the distance
+        let end = (-EXP_TIME_CONSTANTS).exp();
+        match self.stage {
+            EnvelopeStage::Idle => {
+                self.level = 0.0;
+            }
+            EnvelopeStage::Delay => self.tick_delay(),
+            EnvelopeStage::Attack => {
+                let peak = self.peak();
+                self.level += (peak - self.level) * self.exp_coefficient(self.attack, EXP_TIME_CONSTANTS);
+                if peak - self.level <= peak * end {
+                    self.level = peak;
+                    self.stage = EnvelopeStage::Decay;
+                }
+            }
+            EnvelopeStage::Decay => {
+                self.level += (self.sustain - self.level) * self.exp_coefficient(self.decay, EXP_TIME_CONSTANTS);
+                if self.level - self.sustain <= (self.peak() - self.sustain) * end {
+                    self.level = self.sustain;
+                    self.end_decay();
+                }
+            }
+            EnvelopeStage::Sustain => {
+                self.level = self.sustain;
+            }
+            EnvelopeStage::Release => {
+                // As many time constants as it takes to fall from the release level to the floor
+                let time_constants = (self.release_level / RELEASE_FLOOR).ln().max(0.0);
+                self.level -= self.level * self.exp_coefficient(self.release.min(self.max_release), time_constants);
+                if self.level <= RELEASE_FLOOR {
+                    self.level = 0.0;
+                    self.stage = EnvelopeStage::Idle;
+                }
+            }
+        }
+    }
+
+    /// Hold the level until the delay has run out
+    fn tick_delay(&mut self) {
+        self.delay_remaining = self.delay_remaining.saturating_sub(1);
+        if self.delay_remaining == 0 {
+            self.stage = EnvelopeStage::Attack;
+        }
+    }
+
+    /// Enter sustain, or start the next attack when looping
+    fn end_decay(&mut self) {
+        self.stage = match self.loop_mode {
+            EnvelopeLoop::Off => EnvelopeStage::Sustain,
+            EnvelopeLoop::AttackDecay => EnvelopeStage::Attack,
+        };
+    }
+
+    /// Attack target, above 1.0 with punch
+    fn peak(&self) -> f32 {
+        1.0 + self.punch.clamp(0.0, 1.0)
+    }
+
+    /// Per-sample step towards the target for an exponential segment covering
+    /// `time_constants` in `time`
+    fn exp_coefficient(&self, time: f32, time_constants: f32) -> f32 {
+        let samples = time * self.time_scale * self.sample_rate;
+        if samples <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-time_constants / samples).exp()
+        }
     }
 
     /// Calculate rate for linear envelope segments
     fn calculate_rate(&self, time: f32) -> f32 {
-        if time <= 0.0 {
+        let samples = time * self.time_scale * self.sample_rate;
+        if samples <= 0.0 {
             1.0 // Instant
         } else {
-            1.0 / (time * self.sample_rate)
+            1.0 / samples
         }
     }
 
@@ -141,9 +354,19 @@ impl Envelope {
         self.stage = EnvelopeStage::Idle;
         self.level = 0.0;
         self.release_level = 0.0;
+        self.delay_remaining = 0;
     }
 }
 
+fn default_max_release() -> f32 {
+    10.0
+}
+
+/// Serde default for scale factors that start at 1.0
+pub(crate) fn unity() -> f32 {
+    1.0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,4 +408,153 @@ mod tests {
         }
         assert!(env.is_idle());
     }
+
+    #[test]
+    fn test_builder_matches_fields() {
+        let mut direct = Envelope::new(1000.0);
+        direct.attack = 0.02;
+        direct.decay = 0.03;
+        direct.sustain = 0.4;
+        direct.release = 0.05;
+
+        let mut built = Envelope::new(1000.0)
+            .with_attack(0.02)
+            .with_decay(0.03)
+            .with_sustain(0.4)
+            .with_release(0.05);
+
+        let mut shorthand = Envelope::adsr(0.02, 0.03, 0.4, 0.05);
+        shorthand.set_sample_rate(1000.0);
+
+        for env in [&mut direct, &mut built, &mut shorthand] {
+            env.trigger();
+        }
+        for i in 0..150 {
+            if i == 100 {
+                direct.release();
+                built.release();
+                shorthand.release();
+            }
+            let expected = direct.tick();
+            assert_eq!(built.tick(), expected);
+            assert_eq!(shorthand.tick(), expected);
+        }
+        assert!(built.is_idle() && shorthand.is_idle());
+    }
+
+    #[test]
+    fn test_punch_overshoots_then_settles() {
+        let mut env = Envelope::new(1000.0).with_attack(0.01).with_decay(0.05).with_sustain(0.5).with_punch(0.3);
+        env.trigger();
+
+        let mut peak: f32 = 0.0;
+        for _ in 0..200 {
+            peak = peak.max(env.tick());
+        }
+        assert!((peak - 1.3).abs() < 1e-5, "peak {}", peak);
+        assert_eq!(env.stage(), EnvelopeStage::Sustain);
+        assert_eq!(env.level(), 0.5);
+    }
+
+    #[test]
+    fn test_max_release_caps_long_release() {
+        for curve in [EnvelopeCurve::Linear, EnvelopeCurve::Exponential] {
+            let mut env = Envelope::new(1000.0)
+                .with_attack(0.0)
+                .with_sustain(1.0)
+                .with_release(10.0)
+                .with_max_release(3.0)
+                .with_curve(curve);
+            env.trigger();
+            for _ in 0..10 {
+                env.tick();
+            }
+            env.release();
+
+            let mut samples = 0;
+            while !env.is_idle() {
+                env.tick();
+                samples += 1;
+            }
+            assert!((2990..=3010).contains(&samples), "{:?}: {} samples", curve, samples);
+        }
+    }
+
+    #[test]
+    fn test_exponential_attack_time_constant() {
+        // 100 ms attack at 1 kHz: the exponential curve's time constant is 20 samples
+        let samples_to = |curve: EnvelopeCurve, target: f32| {
+            let mut env = Envelope::new(1000.0).with_attack(0.1).with_sustain(1.0).with_curve(curve);
+            env.trigger();
+            let mut samples = 0;
+            while env.tick() < target {
+                samples += 1;
+            }
+            samples + 1
+        };
+
+        let exponential = samples_to(EnvelopeCurve::Exponential, 0.632);
+        assert!((19..=21).contains(&exponential), "{}", exponential);
+        let linear = samples_to(EnvelopeCurve::Linear, 0.632);
+        assert!((63..=64).contains(&linear), "{}", linear);
+        // Both still complete the attack in the set time
+        assert!((99..=101).contains(&samples_to(EnvelopeCurve::Exponential, 1.0)));
+        assert!((99..=101).contains(&samples_to(EnvelopeCurve::Linear, 1.0)));
+    }
+
+    #[test]
+    fn test_delay_holds_before_attack() {
+        let mut env = Envelope::new(1000.0).with_delay(0.05).with_attack(0.01).with_sustain(1.0);
+        env.trigger();
+        assert_eq!(env.stage(), EnvelopeStage::Delay);
+
+        for _ in 0..50 {
+            assert_eq!(env.tick(), 0.0);
+        }
+        assert_eq!(env.stage(), EnvelopeStage::Attack);
+        assert!(env.tick() > 0.0);
+
+        // Releasing during the delay never sounds
+        env.reset();
+        env.trigger();
+        env.tick();
+        env.release();
+        assert_eq!(env.tick(), 0.0);
+        assert!(env.is_idle());
+    }
+
+    #[test]
+    fn test_attack_decay_loop() {
+        for curve in [EnvelopeCurve::Linear, EnvelopeCurve::Exponential] {
+            let mut env = Envelope::new(1000.0)
+                .with_attack(0.02)
+                .with_decay(0.02)
+                .with_sustain(0.2)
+                .with_curve(curve)
+                .with_loop(EnvelopeLoop::AttackDecay);
+            env.trigger();
+
+            // Several cycles of ~40 samples without a note-off
+            let (mut highs, mut lows, mut above) = (0, 0, false);
+            for _ in 0..400 {
+                let level = env.tick();
+                if !above && level > 0.9 {
+                    highs += 1;
+                    above = true;
+                } else if above && level < 0.3 {
+                    lows += 1;
+                    above = false;
+                }
+                assert_ne!(env.stage(), EnvelopeStage::Sustain);
+            }
+            assert!(highs >= 5 && lows >= 5, "{:?}: {} highs, {} lows", curve, highs, lows);
+
+            // Note-off still leaves the loop
+            env.release();
+            for _ in 0..2000 {
+                env.tick();
+            }
+            assert!(env.is_idle());
+        }
+    }
 }
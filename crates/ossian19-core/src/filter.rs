@@ -57,6 +57,40 @@ impl FilterSlope {
     }
 }
 
+/// How the voice's two ladder filters are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum FilterRouting {
+    /// Only the first filter
+    #[default]
+    Single = 0,
+    /// Filter 1 into filter 2 (e.g. LP then HP for a band-pass)
+    Series = 1,
+    /// Both filters fed the same input, outputs summed
+    Parallel = 2,
+}
+
+impl FilterRouting {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Series,
+            2 => Self::Parallel,
+            _ => Self::Single,
+        }
+    }
+}
+
+/// Ladder filter character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum LadderMode {
+    /// Soft-clipped input and feedback (classic Moog coloration)
+    #[default]
+    Vintage = 0,
+    /// Linear, transparent response without saturation
+    Clean = 1,
+}
+
 /// Moog-style ladder filter with selectable slope
 /// Based on the Stilson/Smith model
 #[derive(Debug, Clone)]
@@ -66,6 +100,9 @@ pub struct LadderFilter {
     pub cutoff: f32,      // Hz
     pub resonance: f32,   // 0.0 - 1.0 (self-oscillation at ~1.0)
     pub drive: f32,       // Input drive/saturation
+    pub mode: LadderMode, // Vintage (saturating) or Clean
+    /// Internal oversampling factor (1 = off)
+    pub oversample: usize,
 
     sample_rate: f32,
 
@@ -82,6 +119,8 @@ impl LadderFilter {
             cutoff: 10000.0,
             resonance: 0.0,
             drive: 1.0,
+            mode: LadderMode::default(),
+            oversample: 1,
             sample_rate,
             stage: [0.0; 4],
             delay: [0.0; 4],
@@ -105,6 +144,10 @@ impl LadderFilter {
         self.slope = slope;
     }
 
+    pub fn set_mode(&mut self, mode: LadderMode) {
+        self.mode = mode;
+    }
+
     pub fn reset(&mut self) {
         self.stage = [0.0; 4];
         self.delay = [0.0; 4];
@@ -118,8 +161,18 @@ impl LadderFilter {
 
     /// Process a single sample
     pub fn tick(&mut self, input: f32) -> f32 {
+        // Oversampled: run the ladder several times per sample on the held input
+        let factor = self.oversample.max(1);
+        let mut output = 0.0;
+        for _ in 0..factor {
+            output = self.tick_at_rate(input, self.sample_rate * factor as f32);
+        }
+        output
+    }
+
+    fn tick_at_rate(&mut self, input: f32, sample_rate: f32) -> f32 {
         // Calculate filter coefficient using bilinear transform approximation
-        let fc = (self.cutoff / self.sample_rate).clamp(0.0, 0.45);
+        let fc = (self.cutoff / sample_rate).clamp(0.0, 0.45);
         let g = (PI * fc).tan();
         let g1 = g / (1.0 + g);
 
@@ -134,12 +187,12 @@ impl LadderFilter {
             FilterSlope::Pole4 => 3.0,
         };
 
-        // Apply input drive (soft clipping)
-        let driven_input = self.soft_clip(input * self.drive);
+        // Apply input drive (soft clipping in Vintage mode)
+        let driven_input = self.saturate(input * self.drive);
 
         // Feedback from the last active stage
         let feedback_stage = poles.saturating_sub(1).min(3);
-        let feedback = self.soft_clip(k * self.stage[feedback_stage]);
+        let feedback = self.saturate(k * self.stage[feedback_stage]);
 
         // Input with feedback
         let x = driven_input - feedback;
@@ -186,6 +239,15 @@ impl LadderFilter {
         }
     }
 
+    /// Mode-dependent nonlinearity
+    #[inline]
+    fn saturate(&self, x: f32) -> f32 {
+        match self.mode {
+            LadderMode::Vintage => self.soft_clip(x),
+            LadderMode::Clean => x,
+        }
+    }
+
     /// Soft clipping for analog-style saturation
     fn soft_clip(&self, x: f32) -> f32 {
         // tanh-style soft clipper
@@ -199,6 +261,62 @@ impl LadderFilter {
     }
 }
 
+/// One-pole (6 dB/oct) high-pass; a cutoff of 0 bypasses it
+#[derive(Debug, Clone)]
+pub struct OnePoleHighPass {
+    cutoff: f32,
+    coeff: f32,
+    sample_rate: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl OnePoleHighPass {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            cutoff: 0.0,
+            coeff: 1.0,
+            sample_rate,
+            last_input: 0.0,
+            last_output: 0.0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_cutoff(self.cutoff);
+    }
+
+    /// Set cutoff in Hz (0 = off)
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff.clamp(0.0, self.sample_rate * 0.45);
+        self.coeff = 1.0 / (1.0 + 2.0 * PI * self.cutoff / self.sample_rate);
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.cutoff > 0.0
+    }
+
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> f32 {
+        if !self.is_enabled() {
+            return input;
+        }
+        self.last_output = self.coeff * (self.last_output + input - self.last_input);
+        self.last_input = input;
+        self.last_output
+    }
+
+    pub fn reset(&mut self) {
+        self.last_input = 0.0;
+        self.last_output = 0.0;
+    }
+}
+
 /// State Variable Filter (alternative, more flexible)
 /// 12dB/octave, simultaneous LP/HP/BP outputs
 #[derive(Debug, Clone)]
@@ -283,4 +401,30 @@ mod tests {
             assert!(output.is_finite());
         }
     }
+
+    #[test]
+    fn test_clean_mode_less_distortion() {
+        // Third-harmonic level relative to the fundamental (single-bin DFT)
+        let thd = |mode: LadderMode| {
+            let sample_rate = 44100.0;
+            let mut filter = LadderFilter::new(sample_rate);
+            filter.set_cutoff(8000.0);
+            filter.set_mode(mode);
+            let freq = 220.5;
+            let output: Vec<f32> = (0..8820)
+                .map(|i| filter.tick(0.9 * (2.0 * PI * freq * i as f32 / sample_rate).sin()))
+                .collect();
+            let bin = |f: f32| {
+                let (re, im) = output[4410..].iter().enumerate().fold((0.0, 0.0), |(re, im), (i, x)| {
+                    let phase = 2.0 * PI * f * i as f32 / sample_rate;
+                    (re + x * phase.cos(), im + x * phase.sin())
+                });
+                (re * re + im * im).sqrt()
+            };
+            bin(3.0 * freq) / bin(freq)
+        };
+        let vintage = thd(LadderMode::Vintage);
+        let clean = thd(LadderMode::Clean);
+        assert!(clean < vintage * 0.1, "clean {} vintage {}", clean, vintage);
+    }
 }
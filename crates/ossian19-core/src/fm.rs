@@ -1,20 +1,57 @@
 // FM (Frequency Modulation) Synthesis Engine
 // Based on Yamaha DX-style FM synthesis with 4 operators
 
-use std::f32::consts::PI;
+use std::cmp::Reverse;
+use std::f32::consts::{LN_2, PI};
 use serde::{Deserialize, Serialize};
-use crate::envelope::Envelope;
-use crate::filter::LadderFilter;
-use crate::lfo::Lfo;
+use crate::effects::{Compressor, Dispersion, LoFi};
+use crate::envelope::{unity, Envelope, EnvelopeCurve};
+use crate::filter::{FilterSlope, FilterType, LadderFilter, OnePoleHighPass};
+use crate::lfo::{Lfo, LfoWaveform};
+use crate::meter::OutputMeter;
+use crate::oscillator::Quality;
+use crate::smoother::{PanicFade, Smoother};
+use crate::tempo::NoteDivision;
+use crate::tuning::Tuning;
+use crate::voice::{db_to_gain, freq_to_midi, key_pan, offset_seed, pan_gains, NoiseGen, VoiceState};
+
+pub mod dx7_sysex;
+
+use dx7_sysex::Dx7VoiceData;
 
 const TWO_PI: f32 = 2.0 * PI;
+/// Quantization steps per unit for vintage mode (12-bit signed DAC)
+const VINTAGE_DAC_STEPS: f32 = 2048.0;
+/// Fade time when an operator is enabled or disabled mid-note
+const OP_ENABLE_FADE_MS: f32 = 5.0;
+/// Notes above this are affected by high-frequency damping (C4)
+const HF_DAMPING_REFERENCE_NOTE: u8 = 60;
+/// Default ramp time for vibrato depth and rate changes
+const VIBRATO_SMOOTHING_MS: f32 = 20.0;
+/// Ramp time for master volume changes
+const VOLUME_SMOOTHING_MS: f32 = 10.0;
+/// Fade-out time of a voice cut off by a new note in choke mode
+const CHOKE_FADE_MS: f32 = 5.0;
+/// Note at which operator rate scaling leaves envelope times unchanged
+const RATE_SCALE_REFERENCE_NOTE: u8 = 60;
+/// How often operator drift picks a new random target (seconds)
+const DRIFT_STEP_SECONDS: f32 = 0.5;
+/// Attenuation at level 0+ in Db curve mode (DX7: about 0.75 dB per step over 0-99)
+const LEVEL_CURVE_RANGE_DB: f32 = 74.25;
+/// Share of the global AM depth each amplitude modulation sensitivity (0-3) applies
+const AM_SENS_DEPTH: [f32; 4] = [0.0, 0.26, 0.43, 1.0];
 
 /// Simple sine oscillator for FM operators
 #[derive(Debug, Clone)]
 pub struct FmOscillator {
     phase: f32,
     phase_increment: f32,
+    /// Base frequency; pitch modulation never overwrites it
     frequency: f32,
+    /// Transient pitch multiplier (vibrato) applied on top of `frequency`
+    pitch_mod: f32,
+    /// Slow random pitch multiplier (operator drift)
+    drift: f32,
     sample_rate: f32,
 }
 
@@ -24,6 +61,8 @@ impl FmOscillator {
             phase: 0.0,
             phase_increment: 0.0,
             frequency: 440.0,
+            pitch_mod: 1.0,
+            drift: 1.0,
             sample_rate,
         }
     }
@@ -38,8 +77,29 @@ impl FmOscillator {
         self.update_phase_increment();
     }
 
+    /// Set the pitch multiplier relative to the base frequency
+    pub fn set_pitch_mod(&mut self, multiplier: f32) {
+        if self.pitch_mod != multiplier {
+            self.pitch_mod = multiplier;
+            self.update_phase_increment();
+        }
+    }
+
+    /// Set the drift multiplier relative to the base frequency
+    pub fn set_drift(&mut self, multiplier: f32) {
+        if self.drift != multiplier {
+            self.drift = multiplier;
+            self.update_phase_increment();
+        }
+    }
+
     fn update_phase_increment(&mut self) {
-        self.phase_increment = self.frequency / self.sample_rate;
+        self.phase_increment = self.frequency * self.pitch_mod * self.drift / self.sample_rate;
+    }
+
+    /// Jump the phase forward by `seconds` at the current frequency
+    pub fn skip(&mut self, seconds: f32) {
+        self.phase = (self.phase + self.frequency * self.pitch_mod * self.drift * seconds).fract();
     }
 
     /// Generate sample with phase modulation input (in radians)
@@ -56,11 +116,123 @@ impl FmOscillator {
         output
     }
 
+    /// Advance phase without computing a sample
+    #[inline]
+    pub fn advance(&mut self) {
+        self.phase += self.phase_increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+    }
+
     pub fn reset(&mut self) {
         self.phase = 0.0;
     }
 }
 
+/// How an operator's detune relates to its ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum DetuneMode {
+    /// Fixed cent offset from the harmonic: note * ratio * 2^(cents/1200)
+    #[default]
+    Absolute = 0,
+    /// Offset measured at ratio 1 and added to the ratio: note * (ratio + 2^(cents/1200) - 1),
+    /// so beating against the harmonic stays the same whatever the ratio
+    Proportional = 1,
+}
+
+impl DetuneMode {
+    /// Operator frequency for a note, ratio and detune in cents
+    pub fn frequency(&self, note_freq: f32, ratio: f32, detune: f32) -> f32 {
+        let detune_mult = (2.0_f32).powf(detune / 1200.0);
+        let ratio = match self {
+            DetuneMode::Absolute => ratio * detune_mult,
+            DetuneMode::Proportional => ratio + detune_mult - 1.0,
+        };
+        note_freq * ratio
+    }
+}
+
+/// How an operator's 0-1 `level` maps to amplitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum LevelCurve {
+    /// Amplitude equals level
+    #[default]
+    Linear = 0,
+    /// Level is a position on a dB scale, like the DX7's 0-99 output level (0 stays silent)
+    Db = 1,
+}
+
+impl LevelCurve {
+    /// Amplitude for a 0-1 level
+    pub fn gain(&self, level: f32) -> f32 {
+        match self {
+            LevelCurve::Linear => level,
+            LevelCurve::Db if level <= 0.0 => 0.0,
+            LevelCurve::Db => 10.0_f32.powf((level.min(1.0) - 1.0) * LEVEL_CURVE_RANGE_DB / 20.0),
+        }
+    }
+}
+
+/// How algorithms with several carriers keep their summed output in range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum AdditiveMode {
+    /// Divide the carrier sum by the carrier count (never clips, quieter)
+    #[default]
+    Normalize = 0,
+    /// Sum the carriers at full level into a tanh soft clip (louder, bounded)
+    Clamp = 1,
+}
+
+/// Slow random pitch wander: glides towards a new random target every
+/// `DRIFT_STEP_SECONDS`, staying within +/-`cents`
+#[derive(Debug, Clone)]
+struct Drift {
+    /// Depth in cents (0 = off)
+    cents: f32,
+    rng: NoiseGen,
+    target: f32,
+    value: f32,
+    countdown: u32,
+}
+
+impl Drift {
+    fn new() -> Self {
+        Self {
+            cents: 0.0,
+            rng: NoiseGen::new(),
+            target: 0.0,
+            value: 0.0,
+            countdown: 0,
+        }
+    }
+
+    /// Advance one sample; returns the frequency multiplier
+    #[inline]
+    fn tick(&mut self, sample_rate: f32) -> f32 {
+        let step = (DRIFT_STEP_SECONDS * sample_rate).max(1.0);
+        if self.countdown == 0 {
+            self.countdown = step as u32;
+            self.target = self.rng.tick();
+        }
+        self.countdown -= 1;
+        self.value += (self.target - self.value) / step;
+        // 2^(c/1200) is linear to well under 0.01 cents at these depths
+        1.0 + self.value * self.cents * (LN_2 / 1200.0)
+    }
+
+    /// Restart from the seed with no offset
+    fn reset(&mut self) {
+        self.rng.reseed();
+        self.target = 0.0;
+        self.value = 0.0;
+        self.countdown = 0;
+    }
+}
+
 /// A single FM Operator with its own envelope
 #[derive(Debug, Clone)]
 pub struct FmOperator {
@@ -70,16 +242,59 @@ pub struct FmOperator {
     pub ratio: f32,
     /// Fine detune in cents (-100 to +100)
     pub detune: f32,
+    /// Whether `detune` is applied in cents or as a ratio offset
+    pub detune_mode: DetuneMode,
     /// Output level (0.0 - 1.0)
     pub level: f32,
+    /// Whether `level` is linear amplitude or a dB position
+    pub level_curve: LevelCurve,
     /// Velocity sensitivity (0.0 - 1.0)
     pub velocity_sens: f32,
     /// Feedback amount (only used on certain operators in certain algorithms)
     pub feedback: f32,
+    /// How much velocity scales the envelope depth (0.0 - 1.0)
+    pub env_velocity: f32,
+    /// Fixed frequency in Hz (Some = ignore the played note, ratio and detune;
+    /// the envelope and level still apply)
+    pub fixed_freq: Option<f32>,
+    /// DX7 fixed-mode coarse decade (1, 10, 100 or 1000 Hz)
+    pub fixed_coarse: f32,
+    /// DX7 fixed-mode fine multiplier (1.0 - 9.99)
+    pub fixed_fine: f32,
+    /// Operator on/off (changes fade over a few ms to avoid clicks)
+    pub enabled: bool,
+    /// Delay in seconds between note-on and the envelope trigger (silent until then)
+    pub key_delay: f32,
+    /// High-pass on the operator output, before it feeds anything downstream (off by default)
+    pub hpf: OnePoleHighPass,
+    /// DX7-style amplitude modulation sensitivity (0 = none, 3 = full global AM depth)
+    pub am_sens: u8,
+    /// Keyboard level scaling: MIDI note where the scaling is neutral
+    pub level_scale_breakpoint: u8,
+    /// Level change in dB per octave below the breakpoint (positive = louder)
+    pub level_scale_left_depth: f32,
+    /// Level change in dB per octave above the breakpoint (positive = louder)
+    pub level_scale_right_depth: f32,
+    /// Keyboard rate scaling (0-1): at 1.0 envelope times halve every two octaves up
+    pub rate_scale: f32,
 
     // Runtime state
     velocity: f32,
+    /// Samples left before a delayed envelope trigger
+    delay_remaining: u32,
     feedback_sample: f32,
+    /// Extra gain applied by the voice on top of `level` (macros, modulator scaling)
+    level_scale: f32,
+    /// Keyboard level scaling gain for the current note
+    key_level: f32,
+    /// Gain ramping towards `enabled`
+    enable_gain: f32,
+    /// Skip the oscillator while the operator's output is zero anyway
+    skip_silent: bool,
+    /// Gain from the global LFO's amplitude modulation (1.0 = none)
+    am_gain: f32,
+    /// Slow random pitch wander (off by default)
+    drift: Drift,
 }
 
 impl FmOperator {
@@ -89,41 +304,114 @@ impl FmOperator {
             envelope: Envelope::new(sample_rate),
             ratio: 1.0,
             detune: 0.0,
+            detune_mode: DetuneMode::Absolute,
             level: 1.0,
+            level_curve: LevelCurve::Linear,
             velocity_sens: 0.5,
             feedback: 0.0,
+            env_velocity: 0.0,
+            fixed_freq: None,
+            fixed_coarse: 1.0,
+            fixed_fine: 1.0,
+            enabled: true,
+            key_delay: 0.0,
+            hpf: OnePoleHighPass::new(sample_rate),
+            am_sens: 0,
+            level_scale_breakpoint: 60,
+            level_scale_left_depth: 0.0,
+            level_scale_right_depth: 0.0,
+            rate_scale: 0.0,
             velocity: 1.0,
+            delay_remaining: 0,
             feedback_sample: 0.0,
+            level_scale: 1.0,
+            key_level: 1.0,
+            enable_gain: 1.0,
+            skip_silent: true,
+            am_gain: 1.0,
+            drift: Drift::new(),
         }
     }
 
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.oscillator.set_sample_rate(sample_rate);
         self.envelope.set_sample_rate(sample_rate);
+        self.hpf.set_sample_rate(sample_rate);
     }
 
     /// Set frequency based on note frequency and ratio
     pub fn set_note_frequency(&mut self, note_freq: f32) {
-        let detune_mult = (2.0_f32).powf(self.detune / 1200.0);
-        self.oscillator.set_frequency(note_freq * self.ratio * detune_mult);
+        if let Some(fixed) = self.fixed_freq {
+            self.oscillator.set_frequency(fixed);
+            return;
+        }
+        let freq = self.detune_mode.frequency(note_freq, self.ratio, self.detune);
+        self.oscillator.set_frequency(freq);
+    }
+
+    /// Compute the keyboard level and rate scaling for a MIDI note
+    pub fn set_key_note(&mut self, note: u8) {
+        let octaves = (note as f32 - self.level_scale_breakpoint as f32) / 12.0;
+        let depth = if octaves < 0.0 {
+            self.level_scale_left_depth
+        } else {
+            self.level_scale_right_depth
+        };
+        self.key_level = db_to_gain(depth * octaves.abs());
+
+        let above = note as f32 - RATE_SCALE_REFERENCE_NOTE as f32;
+        self.envelope.set_time_scale((2.0_f32).powf(-self.rate_scale * above / 24.0));
+    }
+
+    /// Output level after the level curve and keyboard level scaling
+    pub fn effective_level(&self) -> f32 {
+        self.level_curve.gain(self.level) * self.key_level
+    }
+
+    /// Enter DX7-style fixed mode at `coarse * fine` Hz.
+    /// Coarse snaps to the nearest decade (1, 10, 100, 1000).
+    pub fn set_fixed_coarse_fine(&mut self, coarse: f32, fine: f32) {
+        let decade = coarse.clamp(1.0, 1000.0).log10().round();
+        self.fixed_coarse = 10.0_f32.powi(decade as i32);
+        self.fixed_fine = fine.clamp(1.0, 9.99);
+        self.fixed_freq = Some(self.fixed_coarse * self.fixed_fine);
     }
 
     /// Trigger the operator
     pub fn trigger(&mut self, velocity: f32) {
         self.velocity = velocity;
         self.oscillator.reset();
-        self.envelope.trigger();
+        self.delay_remaining = (self.key_delay.max(0.0) * self.oscillator.sample_rate) as u32;
+        if self.delay_remaining > 0 {
+            // Enter from silence once the delay runs out
+            self.envelope.reset();
+        } else {
+            self.envelope.trigger();
+        }
         self.feedback_sample = 0.0;
+        self.hpf.reset();
+        self.enable_gain = if self.enabled { 1.0 } else { 0.0 };
     }
 
-    /// Release the operator
+    /// Release the operator (a still-delayed operator never enters)
     pub fn release(&mut self) {
+        self.delay_remaining = 0;
         self.envelope.release();
     }
 
     /// Generate a sample with optional phase modulation input
     #[inline]
     pub fn tick(&mut self, phase_mod_in: f32) -> f32 {
+        if !self.tick_delay() {
+            return 0.0;
+        }
+
+        // A silent operator's output is only its high-pass tail; a feedback loop
+        // still needs the oscillator
+        if self.skip_silent && self.feedback == 0.0 && self.is_silent() {
+            return self.advance_state();
+        }
+
         // Apply feedback if enabled
         let total_phase_mod = phase_mod_in + self.feedback_sample * self.feedback * PI;
 
@@ -133,24 +421,113 @@ impl FmOperator {
         // Store for feedback
         self.feedback_sample = osc_out;
 
-        // Apply envelope
-        let env = self.envelope.tick();
+        // Apply envelope, with its depth optionally scaled by velocity
+        let env = self.envelope.tick()
+            * (1.0 - self.env_velocity + self.env_velocity * self.velocity);
 
         // Apply velocity sensitivity
         let vel_scale = 1.0 - self.velocity_sens + self.velocity_sens * self.velocity;
 
-        osc_out * env * self.level * vel_scale
+        let level = self.effective_level() * self.am_gain;
+        let out = osc_out * env * level * self.level_scale * vel_scale * self.tick_enable_gain();
+        self.hpf.tick(out)
+    }
+
+    /// Advance the operator as `tick` would without running the oscillator, for an
+    /// operator whose output nothing audible uses
+    #[inline]
+    fn tick_skipped(&mut self) -> f32 {
+        if !self.tick_delay() {
+            return 0.0;
+        }
+        self.advance_state()
+    }
+
+    /// Count down a key delay and move the drift; false while the operator is still delayed
+    #[inline]
+    fn tick_delay(&mut self) -> bool {
+        if self.delay_remaining > 0 {
+            self.delay_remaining -= 1;
+            if self.delay_remaining == 0 {
+                self.oscillator.reset();
+                self.envelope.trigger();
+            }
+            return false;
+        }
+
+        if self.drift.cents > 0.0 {
+            let drift = self.drift.tick(self.oscillator.sample_rate);
+            self.oscillator.set_drift(drift);
+        }
+        true
+    }
+
+    /// Phase, envelope, enable fade and high-pass state of a skipped sample
+    #[inline]
+    fn advance_state(&mut self) -> f32 {
+        self.oscillator.advance();
+        self.envelope.tick();
+        self.tick_enable_gain();
+        self.hpf.tick(0.0)
+    }
+
+    /// Random pitch drift depth in cents (0 = off, exact pitch)
+    pub fn set_drift(&mut self, cents: f32) {
+        self.drift.cents = cents.max(0.0);
+        if self.drift.cents == 0.0 {
+            self.drift.value = 0.0;
+            self.oscillator.set_drift(1.0);
+        }
+    }
+
+    /// Give this operator its own drift sequence
+    fn seed_drift(&mut self, seed: u32) {
+        self.drift.rng = NoiseGen::with_seed(seed);
+    }
+
+    /// Restart the drift from its seed (deterministic mode)
+    fn reset_drift(&mut self) {
+        self.drift.reset();
+    }
+
+    /// Apply the global amplitude modulation (0.0 = none, 1.0 = full dip), scaled by `am_sens`
+    #[inline]
+    pub fn set_amp_mod(&mut self, amount: f32) {
+        self.am_gain = 1.0 - AM_SENS_DEPTH[self.am_sens.min(3) as usize] * amount;
+    }
+
+    /// Advance the enable/disable fade
+    #[inline]
+    fn tick_enable_gain(&mut self) -> f32 {
+        let target = if self.enabled { 1.0 } else { 0.0 };
+        if self.enable_gain != target {
+            let step = 1.0 / (OP_ENABLE_FADE_MS * 0.001 * self.oscillator.sample_rate);
+            self.enable_gain = if target > self.enable_gain {
+                (self.enable_gain + step).min(target)
+            } else {
+                (self.enable_gain - step).max(target)
+            };
+        }
+        self.enable_gain
+    }
+
+    /// True when the output is exactly zero whatever the oscillator does
+    #[inline]
+    fn is_silent(&self) -> bool {
+        self.level == 0.0 || self.level_scale == 0.0 || (!self.enabled && self.enable_gain == 0.0)
     }
 
     /// Check if operator envelope is finished
     pub fn is_finished(&self) -> bool {
-        self.envelope.is_idle()
+        self.envelope.is_idle() && self.delay_remaining == 0
     }
 
     pub fn reset(&mut self) {
         self.oscillator.reset();
         self.envelope.reset();
+        self.delay_remaining = 0;
         self.feedback_sample = 0.0;
+        self.hpf.reset();
     }
 }
 
@@ -236,6 +613,11 @@ impl FmAlgorithm {
         }
     }
 
+    /// Carriers as a bitmask (bit 0 = OP1)
+    pub fn carrier_mask(&self) -> u8 {
+        self.carriers().iter().fold(0, |mask, &i| mask | (1 << i))
+    }
+
     /// Human readable description
     pub fn description(&self) -> &'static str {
         match self {
@@ -266,13 +648,30 @@ pub struct Fm4OpVoice {
     pub filter_resonance: f32,
     /// Filter enabled
     pub filter_enabled: bool,
+    /// Per-note pitch offset in cents (humanize detune, set at note_on)
+    pub detune_offset: f32,
 
     /// Current MIDI note
     note: u8,
+    /// Id of a frequency-triggered note (None for MIDI notes)
+    id: Option<u32>,
     /// Current velocity
     velocity: f32,
+    /// Samples rendered since note-on
+    age: u64,
+    /// Choke fade gain, and how much it drops per sample (0 = not choking)
+    choke_gain: f32,
+    choke_step: f32,
+    /// Sounding when `panic` started a fade; stopped when the fade ends
+    panicking: bool,
     /// Is voice active
     active: bool,
+    /// Key released while the sustain pedal was down
+    sustained: bool,
+    /// Note-off received since the last note-on
+    released: bool,
+    /// Operator oversampling factor (operators run at `sample_rate * oversample`)
+    oversample: usize,
     /// Sample rate
     sample_rate: f32,
 }
@@ -327,43 +726,145 @@ impl Fm4OpVoice {
             filter_cutoff: 20000.0,
             filter_resonance: 0.0,
             filter_enabled: false,
+            detune_offset: 0.0,
             note: 0,
+            id: None,
             velocity: 0.0,
+            age: 0,
+            choke_gain: 1.0,
+            choke_step: 0.0,
+            panicking: false,
+            sustained: false,
+            released: false,
             active: false,
+            oversample: 1,
             sample_rate,
         }
     }
 
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
+        let operator_rate = sample_rate * self.oversample as f32;
         for op in &mut self.operators {
-            op.set_sample_rate(sample_rate);
+            op.set_sample_rate(operator_rate);
         }
         self.filter.set_sample_rate(sample_rate);
     }
 
+    /// Run the operators at `factor` times the sample rate, averaging down to the output rate
+    pub fn set_oversample(&mut self, factor: usize) {
+        if factor.max(1) != self.oversample {
+            self.oversample = factor.max(1);
+            self.set_sample_rate(self.sample_rate);
+        }
+    }
+
     /// Start a note
     pub fn note_on(&mut self, note: u8, velocity: f32) {
+        self.note_on_at(note, midi_to_freq(note), velocity);
+    }
+
+    /// Start a note sounding at `freq` (the note's tuned frequency)
+    pub fn note_on_at(&mut self, note: u8, freq: f32, velocity: f32) {
         self.note = note;
+        self.id = None;
+        self.start(freq, velocity);
+    }
+
+    /// Start a note at an arbitrary frequency, tracked by `id` instead of note number
+    pub fn note_on_freq(&mut self, id: u32, freq: f32, velocity: f32) {
+        self.note = freq_to_midi(freq);
+        self.id = Some(id);
+        self.start(freq, velocity);
+    }
+
+    fn start(&mut self, freq: f32, velocity: f32) {
         self.velocity = velocity;
+        self.age = 0;
+        self.choke_gain = 1.0;
+        self.choke_step = 0.0;
+        self.panicking = false;
+        self.released = false;
         self.active = true;
 
-        let note_freq = midi_to_freq(note);
+        let note_freq = freq * detune_multiplier(self.detune_offset);
 
         // Set frequency and trigger all operators
         for op in &mut self.operators {
             op.set_note_frequency(note_freq);
+            op.set_key_note(self.note);
             op.trigger(velocity);
         }
     }
 
     /// Release a note
     pub fn note_off(&mut self) {
+        self.released = true;
         for op in &mut self.operators {
             op.release();
         }
     }
 
+    /// Apply a pitch multiplier (vibrato) on top of the note frequency
+    pub fn set_pitch_mod(&mut self, multiplier: f32) {
+        for op in &mut self.operators {
+            op.oscillator.set_pitch_mod(multiplier);
+        }
+    }
+
+    /// Seed each operator's drift from the voice's index so no two wander alike,
+    /// offset by the manager's instance seed
+    fn seed_drift(&mut self, voice_index: usize, instance_seed: u32) {
+        for (index, op) in self.operators.iter_mut().enumerate() {
+            op.seed_drift(offset_seed(drift_seed(voice_index * 4 + index), instance_seed));
+        }
+    }
+
+    fn reset_drift(&mut self) {
+        for op in &mut self.operators {
+            op.reset_drift();
+        }
+    }
+
+    /// Reproduce a legacy 2-op `FmSynth`: OP1 is the carrier, the modulator is OP2
+    /// (Stack) or OP3 on its own stack (Parallel). Envelopes are flat gates and
+    /// velocity is ignored, like the 2-op engine.
+    pub fn configure_from_2op(&mut self, synth: &FmSynth) {
+        for op in &mut self.operators {
+            op.ratio = 1.0;
+            op.level = 0.0;
+            op.detune = 0.0;
+            op.feedback = 0.0;
+            op.fixed_freq = None;
+            op.velocity_sens = 0.0;
+            op.env_velocity = 0.0;
+            op.envelope.attack = 0.0;
+            op.envelope.decay = 0.0;
+            op.envelope.sustain = 1.0;
+            op.envelope.release = 0.01;
+        }
+        self.operators[0].level = 1.0;
+
+        let modulator = match synth.algorithm {
+            FmAlgorithm2Op::Stack | FmAlgorithm2Op::StackFeedback => {
+                // OP4/OP3 silent, so 4→3→2→1 is just 2→1
+                self.algorithm = FmAlgorithm::Algo1Serial;
+                &mut self.operators[1]
+            }
+            FmAlgorithm2Op::Parallel => {
+                // (OP1 + OP3) / 2 with OP2/OP4 silent
+                self.algorithm = FmAlgorithm::Algo3TwoStacks;
+                &mut self.operators[2]
+            }
+        };
+        modulator.ratio = synth.mod_ratio;
+        modulator.level = synth.mod_depth;
+        if synth.algorithm == FmAlgorithm2Op::StackFeedback {
+            modulator.feedback = synth.feedback;
+        }
+        self.filter_enabled = false;
+    }
+
     /// Check if voice is finished
     pub fn is_finished(&self) -> bool {
         // Voice is finished when all carrier operators are done
@@ -377,8 +878,42 @@ impl Fm4OpVoice {
         if !self.active {
             return 0.0;
         }
+        self.age += 1;
+
+        let output = if self.oversample == 1 {
+            self.process_operators()
+        } else {
+            let mut sum = 0.0;
+            for _ in 0..self.oversample {
+                sum += self.process_operators();
+            }
+            sum / self.oversample as f32
+        };
+
+        // Apply optional filter
+        let filtered = if self.filter_enabled {
+            self.filter.set_cutoff(self.filter_cutoff);
+            self.filter.set_resonance(self.filter_resonance);
+            self.filter.tick(output)
+        } else {
+            output
+        };
+        let Some(choke_gain) = self.tick_choke() else {
+            return 0.0;
+        };
+
+        // Check if voice is finished
+        if self.is_finished() {
+            self.active = false;
+        }
+
+        filtered * choke_gain
+    }
 
-        let output = match self.algorithm {
+    /// Run the operators once through the algorithm
+    #[inline]
+    fn process_operators(&mut self) -> f32 {
+        match self.algorithm {
             FmAlgorithm::Algo1Serial => {
                 // 4→3→2→1
                 let op4 = self.operators[3].tick(0.0);
@@ -441,23 +976,7 @@ impl Fm4OpVoice {
                 let op1 = self.operators[0].tick(0.0);
                 (op1 + op2 + op3 + op4) * 0.25
             }
-        };
-
-        // Apply optional filter
-        let filtered = if self.filter_enabled {
-            self.filter.set_cutoff(self.filter_cutoff);
-            self.filter.set_resonance(self.filter_resonance);
-            self.filter.tick(output)
-        } else {
-            output
-        };
-
-        // Check if voice is finished
-        if self.is_finished() {
-            self.active = false;
         }
-
-        filtered
     }
 
     pub fn reset(&mut self) {
@@ -465,6 +984,8 @@ impl Fm4OpVoice {
             op.reset();
         }
         self.filter.reset();
+        self.panicking = false;
+        self.released = false;
         self.active = false;
         self.note = 0;
         self.velocity = 0.0;
@@ -477,6 +998,68 @@ impl Fm4OpVoice {
     pub fn note(&self) -> u8 {
         self.note
     }
+
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// Samples rendered since note-on
+    pub fn age(&self) -> u64 {
+        self.age
+    }
+
+    /// Fade out over `samples` and go idle (choke mode)
+    pub fn choke(&mut self, samples: usize) {
+        self.choke_step = 1.0 / samples.max(1) as f32;
+    }
+
+    /// Advance a choke fade; returns the gain, or None once the voice has gone idle
+    #[inline]
+    fn tick_choke(&mut self) -> Option<f32> {
+        if self.choke_step > 0.0 {
+            self.choke_gain -= self.choke_step;
+            if self.choke_gain <= 0.0 {
+                self.reset();
+                return None;
+            }
+        }
+        Some(self.choke_gain)
+    }
+
+    /// Id of a frequency-triggered note (None for MIDI notes)
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    /// Whether the note has been released (note-off received since the last note-on)
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+
+    /// Stealing order: released (0), held by the sustain pedal (1), key still down (2)
+    fn steal_rank(&self) -> u8 {
+        if self.is_released() {
+            0
+        } else if self.sustained {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// Distinct noise seed for the `index`th operator of a manager
+fn drift_seed(index: usize) -> u32 {
+    (index as u32).wrapping_mul(2654435761).wrapping_add(1)
+}
+
+/// Frequency multiplier for a per-note detune offset in cents
+fn detune_multiplier(cents: f32) -> f32 {
+    if cents != 0.0 {
+        (2.0_f32).powf(cents / 1200.0)
+    } else {
+        1.0
+    }
 }
 
 /// Convert MIDI note to frequency
@@ -492,13 +1075,53 @@ pub struct Fm4OpVoiceManager {
     vibrato_lfo: Lfo,
     /// Vibrato depth in cents (0-100)
     vibrato_depth: f32,
+    /// Ramp the vibrato depth (cents) and rate (Hz) towards their targets
+    vibrato_depth_smoother: Smoother,
+    vibrato_rate_smoother: Smoother,
+    /// Vibrato LFO clocked to a tempo division instead of the vibrato rate
+    vibrato_synced: bool,
     /// Master volume
     master_volume: f32,
+    /// Ramps the applied volume towards `master_volume`
+    volume_smoother: Smoother,
+    /// Fade applied by `panic` before the hard reset
+    panic_fade: PanicFade,
+    /// Master pan (-1 = left, 1 = right)
+    master_pan: f32,
+    /// Maximum random detune applied per note in cents (0 = off)
+    humanize_detune: f32,
+    humanize_rng: NoiseGen,
+    /// Reseed all randomness at note_on so renders are reproducible
+    deterministic: bool,
+    /// Id handed out by the next `note_on_freq`
+    next_note_id: u32,
+    /// Ignore note_off; a second press of a held note releases it
+    latch: bool,
+    /// Note to frequency mapping for MIDI notes
+    tuning: Tuning,
+    /// A new note fast-fades every sounding voice (hi-hat style choke)
+    choke: bool,
+    /// Sustain pedal (CC64) is down
+    sustain_pedal: bool,
+    /// With the pedal down, a repeated note gets a new voice instead of retriggering
+    pedal_stacking: bool,
+    /// Evens out chord vs single-note levels when enabled
+    compressor: Compressor,
+    auto_level: bool,
+    /// Sum the stereo output to mono on both channels (compatibility check)
+    mono_sum: bool,
+    /// Keyboard panning: full pan per 4 octaves from middle C at 1.0 (0 = off)
+    key_pan_amount: f32,
+    /// How much note-on velocity scales each voice's vibrato depth (0 = off)
+    velocity_to_vibrato: f32,
 }
 
 impl Fm4OpVoiceManager {
     pub fn new(num_voices: usize, sample_rate: f32) -> Self {
-        let voices = (0..num_voices).map(|_| Fm4OpVoice::new(sample_rate)).collect();
+        let mut voices: Vec<Fm4OpVoice> = (0..num_voices).map(|_| Fm4OpVoice::new(sample_rate)).collect();
+        for (index, voice) in voices.iter_mut().enumerate() {
+            voice.seed_drift(index, 0);
+        }
         let mut vibrato_lfo = Lfo::new(sample_rate);
         vibrato_lfo.set_frequency(5.0); // Default 5 Hz vibrato rate
         Self {
@@ -506,7 +1129,27 @@ impl Fm4OpVoiceManager {
             sample_rate,
             vibrato_lfo,
             vibrato_depth: 0.0,
+            vibrato_depth_smoother: Smoother::new(0.0, VIBRATO_SMOOTHING_MS, sample_rate),
+            vibrato_rate_smoother: Smoother::new(5.0, VIBRATO_SMOOTHING_MS, sample_rate),
+            vibrato_synced: false,
             master_volume: 0.7,
+            volume_smoother: Smoother::new(0.7, VOLUME_SMOOTHING_MS, sample_rate),
+            panic_fade: PanicFade::default(),
+            master_pan: 0.0,
+            humanize_detune: 0.0,
+            humanize_rng: NoiseGen::new(),
+            deterministic: false,
+            next_note_id: 1,
+            latch: false,
+            tuning: Tuning::default(),
+            choke: false,
+            sustain_pedal: false,
+            pedal_stacking: true,
+            compressor: Compressor::new(sample_rate),
+            auto_level: false,
+            mono_sum: false,
+            key_pan_amount: 0.0,
+            velocity_to_vibrato: 0.0,
         }
     }
 
@@ -516,116 +1159,457 @@ impl Fm4OpVoiceManager {
             voice.set_sample_rate(sample_rate);
         }
         self.vibrato_lfo.set_sample_rate(sample_rate);
+        self.vibrato_depth_smoother.set_sample_rate(sample_rate);
+        self.vibrato_rate_smoother.set_sample_rate(sample_rate);
+        self.volume_smoother.set_sample_rate(sample_rate);
+        self.compressor.set_sample_rate(sample_rate);
     }
 
-    /// Find a free voice or steal the oldest one
-    fn allocate_voice(&mut self) -> Option<&mut Fm4OpVoice> {
-        // First try to find an inactive voice
+    /// Find a free voice, or steal the oldest released voice, and a held key
+    /// only when nothing else is left
+    fn allocate_voice(&mut self) -> Option<usize> {
         let inactive_idx = self.voices.iter().position(|v| !v.is_active());
-
-        if let Some(idx) = inactive_idx {
-            return self.voices.get_mut(idx);
+        if inactive_idx.is_some() {
+            return inactive_idx;
         }
-
-        // Steal first voice (simple round-robin)
-        self.voices.first_mut()
+        (0..self.voices.len()).min_by_key(|&i| (self.voices[i].steal_rank(), Reverse(self.voices[i].age)))
     }
 
-    pub fn note_on(&mut self, note: u8, velocity: f32) {
-        // Check if note is already playing
-        if let Some(voice) = self.voices.iter_mut().find(|v| v.is_active() && v.note() == note) {
-            voice.note_on(note, velocity);
-            return;
+    /// Start a note; returns the index of the voice that plays it
+    pub fn note_on(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        // Velocity 0 note-on is a note-off by MIDI convention
+        if velocity <= 0.0 {
+            self.note_off(note);
+            return None;
+        }
+        if self.latch_release(note) {
+            return None;
         }
 
-        if let Some(voice) = self.allocate_voice() {
-            voice.note_on(note, velocity);
+        // Retrigger a sounding note, unless the pedal is down and repeats stack
+        // or it is fading out in choke mode
+        let existing = if self.choke || (self.sustain_pedal && self.pedal_stacking) {
+            None
+        } else {
+            self.voices
+                .iter()
+                .position(|v| v.is_active() && v.note() == note && v.id().is_none())
+        };
+        self.choke_voices();
+        let freq = self.tuning.frequency(note);
+        let index = self.prepare_voice(existing)?;
+        self.voices[index].note_on_at(note, freq, velocity);
+        Some(index)
+    }
+
+    /// Start a note at an arbitrary frequency (microtonal).
+    /// Returns an id for `note_off_id`; MIDI `note_off` does not release it.
+    pub fn note_on_freq(&mut self, freq: f32, velocity: f32) -> u32 {
+        let id = self.next_note_id;
+        self.next_note_id = self.next_note_id.wrapping_add(1);
+        if velocity > 0.0 {
+            self.choke_voices();
+            if let Some(index) = self.prepare_voice(None) {
+                self.voices[index].note_on_freq(id, freq.clamp(1.0, 20000.0), velocity);
+            }
         }
+        id
     }
 
-    pub fn note_off(&mut self, note: u8) {
+    /// Release a note started with `note_on_freq`
+    pub fn note_off_id(&mut self, id: u32) {
         for voice in &mut self.voices {
-            if voice.is_active() && voice.note() == note {
+            if voice.is_active() && voice.id() == Some(id) {
                 voice.note_off();
             }
         }
     }
 
-    pub fn panic(&mut self) {
-        for voice in &mut self.voices {
-            voice.reset();
+    /// Pick a voice (retriggering `existing` or allocating) and apply per-note randomness
+    fn prepare_voice(&mut self, existing: Option<usize>) -> Option<usize> {
+        self.reseed_if_deterministic();
+        let detune_offset = self.next_humanize_offset();
+        let index = existing.or_else(|| self.allocate_voice())?;
+        let voice = self.voices.get_mut(index)?;
+        voice.detune_offset = detune_offset;
+        if self.deterministic {
+            voice.reset_drift();
         }
+        voice.sustained = false;
+        Some(index)
     }
 
-    pub fn active_voice_count(&self) -> usize {
-        self.voices.iter().filter(|v| v.is_active()).count()
+    /// Set random per-note detune in cents (0 = off)
+    pub fn set_humanize_detune(&mut self, cents: f32) {
+        self.humanize_detune = cents.clamp(0.0, 50.0);
     }
 
-    /// Process all voices and return mixed output
-    pub fn tick(&mut self) -> f32 {
-        // Get vibrato modulation
-        let vibrato = if self.vibrato_depth > 0.0 {
-            let lfo_value = self.vibrato_lfo.tick();
-            // Convert depth in cents to frequency multiplier
-            // depth of 50 cents = half semitone
-            let cents = lfo_value * self.vibrato_depth;
-            (2.0_f32).powf(cents / 1200.0)
-        } else {
-            1.0
-        };
+    /// Reference pitch, transpose, fine tune and scale for MIDI notes (from the next note_on)
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
 
-        let mut output = 0.0;
+    /// Slow, independent random pitch drift of every operator in cents (0 = off)
+    pub fn set_operator_drift(&mut self, cents: f32) {
         for voice in &mut self.voices {
-            // Apply vibrato by temporarily modifying operator frequencies
-            if vibrato != 1.0 && voice.is_active() {
-                for op in &mut voice.operators {
-                    let base_freq = op.oscillator.frequency;
-                    op.oscillator.set_frequency(base_freq * vibrato);
-                }
+            for op in &mut voice.operators {
+                op.set_drift(cents.clamp(0.0, 10.0));
             }
-            output += voice.tick();
-            // Restore frequencies (next tick will recalculate anyway)
         }
-        output * self.master_volume
     }
 
-    /// Set algorithm for all voices
-    pub fn set_algorithm(&mut self, algo: FmAlgorithm) {
-        for voice in &mut self.voices {
-            voice.algorithm = algo;
+    fn next_humanize_offset(&mut self) -> f32 {
+        if self.humanize_detune > 0.0 {
+            self.humanize_rng.tick() * self.humanize_detune
+        } else {
+            0.0
         }
     }
 
-    /// Set operator ratio
-    pub fn set_op_ratio(&mut self, op_index: usize, ratio: f32) {
-        if op_index < 4 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].ratio = ratio.clamp(0.125, 16.0);
-            }
+    /// Restart every random source from its seed whenever a note starts from silence,
+    /// so renders are bit-stable (for tests)
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.deterministic = enabled;
+        if enabled {
+            self.humanize_rng.reseed();
         }
     }
 
-    /// Set operator level
-    pub fn set_op_level(&mut self, op_index: usize, level: f32) {
-        if op_index < 4 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].level = level.clamp(0.0, 1.0);
-            }
+    /// Offset every random source (drift, humanize, vibrato S&H) so layered instances
+    /// don't move in lockstep (0 = the fixed default seeds)
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.humanize_rng.set_instance_seed(seed);
+        self.vibrato_lfo.set_instance_seed(seed);
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.seed_drift(index, seed);
         }
     }
 
-    /// Get operator level (for debugging)
-    pub fn get_op_level(&self, op_index: usize) -> f32 {
-        if op_index < 4 && !self.voices.is_empty() {
-            self.voices[0].operators[op_index].level
-        } else {
-            0.0
+    /// Restart random sources and the vibrato LFO when a note starts from silence;
+    /// notes joining a sounding chord keep drawing from the sequence
+    fn reseed_if_deterministic(&mut self) {
+        if !self.deterministic || self.active_voice_count() > 0 {
+            return;
         }
+        self.humanize_rng.reseed();
+        self.vibrato_lfo.reset();
+        self.vibrato_lfo.reseed();
     }
 
-    /// Get operator ratio (for debugging)
-    pub fn get_op_ratio(&self, op_index: usize) -> f32 {
-        if op_index < 4 && !self.voices.is_empty() {
+    /// Choke mode: each new note quickly fades out whatever is sounding
+    pub fn set_choke(&mut self, enabled: bool) {
+        self.choke = enabled;
+    }
+
+    /// Fast-fade every sounding voice ahead of a new note in choke mode
+    fn choke_voices(&mut self) {
+        if !self.choke {
+            return;
+        }
+        let samples = (CHOKE_FADE_MS * 0.001 * self.sample_rate) as usize;
+        for voice in &mut self.voices {
+            if voice.is_active() {
+                voice.choke(samples);
+            }
+        }
+    }
+
+    /// Latch mode: note_off is ignored and pressing a held note again releases it.
+    /// Turning latch off releases every latched note.
+    pub fn set_latch(&mut self, enabled: bool) {
+        if self.latch && !enabled {
+            for voice in &mut self.voices {
+                if voice.is_active() && voice.id().is_none() {
+                    voice.note_off();
+                }
+            }
+        }
+        self.latch = enabled;
+    }
+
+    /// In latch mode, release `note` if it is still held. Returns true if it was.
+    fn latch_release(&mut self, note: u8) -> bool {
+        if !self.latch {
+            return false;
+        }
+        let held = self
+            .voices
+            .iter_mut()
+            .find(|v| v.is_active() && v.note() == note && v.id().is_none() && !v.is_released());
+        match held {
+            Some(voice) => {
+                voice.note_off();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Release a note (ignored in latch mode)
+    pub fn note_off(&mut self, note: u8) {
+        if self.latch {
+            return;
+        }
+        let pedal = self.sustain_pedal;
+        for voice in &mut self.voices {
+            if voice.is_active() && voice.note() == note && voice.id().is_none() {
+                if pedal {
+                    voice.sustained = true;
+                } else {
+                    voice.note_off();
+                }
+            }
+        }
+    }
+
+    /// Sustain pedal: while down, released keys keep sounding until it is lifted
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        if self.sustain_pedal && !down {
+            for voice in &mut self.voices {
+                if voice.sustained {
+                    voice.sustained = false;
+                    if voice.is_active() {
+                        voice.note_off();
+                    }
+                }
+            }
+        }
+        self.sustain_pedal = down;
+    }
+
+    /// With the pedal down, re-pressing a sounding note stacks a new voice
+    /// instead of retriggering it (on by default)
+    pub fn set_pedal_stacking(&mut self, enabled: bool) {
+        self.pedal_stacking = enabled;
+    }
+
+    /// Fade the sounding voices to zero over the panic fade, then hard-reset them;
+    /// notes played during the fade are unaffected. Instant when the fade is 0,
+    /// nothing is sounding, or a fade is already running.
+    pub fn panic(&mut self) {
+        if self.active_voice_count() == 0
+            || self.panic_fade.is_fading()
+            || !self.panic_fade.start(self.sample_rate)
+        {
+            self.panic_now();
+            return;
+        }
+        for voice in &mut self.voices {
+            voice.panicking = voice.is_active();
+        }
+    }
+
+    /// Immediately stop all voices (emergency panic, no fade)
+    pub fn panic_now(&mut self) {
+        for voice in &mut self.voices {
+            voice.reset();
+        }
+        self.panic_fade.cancel();
+    }
+
+    /// Length of the fade `panic` applies before stopping the voices (0 = instant)
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.panic_fade.set_time_ms(ms);
+    }
+
+    /// Stop the voices a finished panic fade has silenced
+    fn finish_panic(&mut self) {
+        for voice in self.voices.iter_mut().filter(|v| v.panicking) {
+            voice.reset();
+        }
+        self.panic_fade.cancel();
+    }
+
+    /// Clear all DSP state (voices, vibrato LFO, compressor) and the sustain pedal,
+    /// but keep every setting
+    pub fn reset(&mut self) {
+        self.panic_now();
+        self.vibrato_lfo.reset();
+        self.settle_vibrato();
+        self.volume_smoother.reset(self.master_volume);
+        self.sustain_pedal = false;
+        self.compressor.reset();
+    }
+
+    pub fn active_voice_count(&self) -> usize {
+        self.voices.iter().filter(|v| v.is_active()).count()
+    }
+
+    /// Note, velocity, age, envelope stage and level of every active voice (first carrier's envelope)
+    pub fn voice_states(&self) -> Vec<VoiceState> {
+        self.voices
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| {
+                let env = &v.operators[v.algorithm.carriers()[0]].envelope;
+                VoiceState {
+                    note: v.note(),
+                    velocity: v.velocity(),
+                    age: v.age(),
+                    stage: env.stage(),
+                    level: env.level(),
+                }
+            })
+            .collect()
+    }
+
+    /// Process all voices and return mixed output
+    pub fn tick(&mut self) -> f32 {
+        self.render(false).0
+    }
+
+    /// Sum the voices; with `stereo`, each voice is key-panned into (left, right),
+    /// otherwise both channels carry the plain sum
+    fn render(&mut self, stereo: bool) -> (f32, f32) {
+        // Nothing sounding: keep the vibrato LFO running but skip the voices
+        if self.active_voice_count() == 0 {
+            self.vibrato_lfo.tick();
+            self.settle_vibrato();
+            self.volume_smoother.reset(self.master_volume);
+            self.compressor.reset();
+            self.panic_fade.cancel();
+            return (0.0, 0.0);
+        }
+
+        // Get vibrato modulation (depth of 50 cents = half semitone)
+        let vibrato_cents = self.tick_vibrato();
+        let vibrato = if vibrato_cents != 0.0 {
+            (2.0_f32).powf(vibrato_cents / 1200.0)
+        } else {
+            1.0
+        };
+
+        let key_pan_amount = if stereo { self.key_pan_amount } else { 0.0 };
+        let velocity_to_vibrato = self.velocity_to_vibrato;
+        let panic_done = self.panic_fade.tick();
+        let panic_gain = self.panic_fade.gain();
+        let (mut left, mut right) = (0.0, 0.0);
+        for voice in &mut self.voices {
+            // Vibrato scales the stored note frequency, it never compounds
+            if voice.is_active() {
+                if velocity_to_vibrato > 0.0 && vibrato_cents != 0.0 {
+                    let scale = 1.0 - velocity_to_vibrato + velocity_to_vibrato * voice.velocity;
+                    voice.set_pitch_mod((2.0_f32).powf(vibrato_cents * scale / 1200.0));
+                } else {
+                    voice.set_pitch_mod(vibrato);
+                }
+            }
+            let fade = if voice.panicking { panic_gain } else { 1.0 };
+            let sample = voice.tick() * fade;
+            if key_pan_amount == 0.0 {
+                left += sample;
+                right += sample;
+            } else {
+                let (left_gain, right_gain) = pan_gains(key_pan(voice.note(), key_pan_amount));
+                left += sample * left_gain;
+                right += sample * right_gain;
+            }
+        }
+        if panic_done {
+            self.finish_panic();
+        }
+        let volume = self.volume_smoother.tick();
+        let (left, right) = (left * volume, right * volume);
+        if self.auto_level {
+            self.compressor.tick_stereo(left, right)
+        } else {
+            (left, right)
+        }
+    }
+
+    /// Set anti-aliasing quality (operator and filter oversampling)
+    pub fn set_quality(&mut self, quality: Quality) {
+        for voice in &mut self.voices {
+            voice.set_oversample(quality.operator_oversample());
+            voice.filter.oversample = quality.filter_oversample();
+        }
+    }
+
+    /// Compress the summed output so chords and single notes sit at similar levels
+    pub fn set_auto_level(&mut self, enabled: bool) {
+        self.auto_level = enabled;
+    }
+
+    /// Auto-level threshold (dB), ratio, attack and release (ms)
+    pub fn set_auto_level_params(&mut self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.compressor.set_params(threshold_db, ratio, attack_ms, release_ms);
+    }
+
+    /// Process a buffer of samples
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.tick();
+        }
+    }
+
+    /// Process a single stereo sample with master pan applied
+    pub fn tick_stereo(&mut self) -> (f32, f32) {
+        let (left, right) = self.render(true);
+        let (left_gain, right_gain) = pan_gains(self.master_pan);
+        let (left, right) = (left * left_gain, right * right_gain);
+        if self.mono_sum {
+            let mid = (left + right) * 0.5;
+            (mid, mid)
+        } else {
+            (left, right)
+        }
+    }
+
+    /// Process stereo buffer
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.tick_stereo();
+        }
+    }
+
+    /// Set algorithm for all voices
+    pub fn set_algorithm(&mut self, algo: FmAlgorithm) {
+        for voice in &mut self.voices {
+            voice.algorithm = algo;
+        }
+    }
+
+    /// Configure every voice to reproduce a legacy 2-op `FmSynth`
+    pub fn configure_from_2op(&mut self, synth: &FmSynth) {
+        for voice in &mut self.voices {
+            voice.configure_from_2op(synth);
+        }
+    }
+
+    /// Set operator ratio
+    pub fn set_op_ratio(&mut self, op_index: usize, ratio: f32) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].ratio = ratio.clamp(0.125, 16.0);
+            }
+        }
+    }
+
+    /// Set operator level
+    pub fn set_op_level(&mut self, op_index: usize, level: f32) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].level = level.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// Get operator level (for debugging)
+    pub fn get_op_level(&self, op_index: usize) -> f32 {
+        if op_index < 4 && !self.voices.is_empty() {
+            self.voices[0].operators[op_index].level
+        } else {
+            0.0
+        }
+    }
+
+    /// Get operator ratio (for debugging)
+    pub fn get_op_ratio(&self, op_index: usize) -> f32 {
+        if op_index < 4 && !self.voices.is_empty() {
             self.voices[0].operators[op_index].ratio
         } else {
             1.0
@@ -704,6 +1688,42 @@ impl Fm4OpVoiceManager {
         }
     }
 
+    /// Turn an operator on or off (fades over a few ms on sounding notes)
+    pub fn set_op_enabled(&mut self, op_index: usize, enabled: bool) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].enabled = enabled;
+            }
+        }
+    }
+
+    /// Set how much velocity scales an operator's envelope depth
+    pub fn set_op_env_velocity(&mut self, op_index: usize, amount: f32) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].env_velocity = amount.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// High-pass an operator's output before it modulates or is heard (0 Hz = off)
+    pub fn set_op_hpf(&mut self, op_index: usize, cutoff: f32) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].hpf.set_cutoff(cutoff);
+            }
+        }
+    }
+
+    /// Interpret an operator's level linearly or on a DX7-style dB scale
+    pub fn set_op_level_curve(&mut self, op_index: usize, curve: LevelCurve) {
+        if op_index < 4 {
+            for voice in &mut self.voices {
+                voice.operators[op_index].level_curve = curve;
+            }
+        }
+    }
+
     /// Set filter enabled
     pub fn set_filter_enabled(&mut self, enabled: bool) {
         for voice in &mut self.voices {
@@ -725,24 +1745,122 @@ impl Fm4OpVoiceManager {
         }
     }
 
+    /// Set filter response (low-pass, high-pass or band-pass)
+    pub fn set_filter_type(&mut self, filter_type: FilterType) {
+        for voice in &mut self.voices {
+            voice.filter.filter_type = filter_type;
+        }
+    }
+
+    /// Set filter slope (6/12/24 dB/oct)
+    pub fn set_filter_slope(&mut self, slope: FilterSlope) {
+        for voice in &mut self.voices {
+            voice.filter.set_slope(slope);
+        }
+    }
+
+    /// Set filter input drive (1.0 = unity, higher saturates)
+    pub fn set_filter_drive(&mut self, drive: f32) {
+        for voice in &mut self.voices {
+            voice.filter.drive = drive.clamp(1.0, 8.0);
+        }
+    }
+
     /// Get mutable access to voices
     pub fn voices_mut(&mut self) -> &mut [Fm4OpVoice] {
         &mut self.voices
     }
 
-    /// Set vibrato depth in cents (0-100)
+    /// Set vibrato depth in cents (0-100), ramped while notes sound
     pub fn set_vibrato_depth(&mut self, depth: f32) {
         self.vibrato_depth = depth.clamp(0.0, 100.0);
+        self.vibrato_depth_smoother.set_target(self.vibrato_depth);
+        if self.active_voice_count() == 0 {
+            self.settle_vibrato();
+        }
     }
 
-    /// Set vibrato rate in Hz (0.1-20)
+    /// Set vibrato rate in Hz (0.1-20), ramped while notes sound
     pub fn set_vibrato_rate(&mut self, rate: f32) {
-        self.vibrato_lfo.set_frequency(rate.clamp(0.1, 20.0));
+        self.vibrato_rate_smoother.set_target(rate.clamp(0.1, 20.0));
+        if self.active_voice_count() == 0 {
+            self.settle_vibrato();
+        }
+    }
+
+    /// Ramp time for vibrato depth and rate changes in milliseconds (0 = jump)
+    pub fn set_vibrato_smoothing_ms(&mut self, ms: f32) {
+        self.vibrato_depth_smoother.set_time_ms(ms.clamp(0.0, 1000.0));
+        self.vibrato_rate_smoother.set_time_ms(ms.clamp(0.0, 1000.0));
+    }
+
+    /// Vibrato LFO shape (sample & hold gives stepped random pitch)
+    pub fn set_vibrato_waveform(&mut self, waveform: LfoWaveform) {
+        self.vibrato_lfo.waveform = waveform;
+    }
+
+    /// Clock one vibrato cycle (one S&H step) to `division` at the host tempo `bpm`;
+    /// with no division or no tempo the LFO runs free at the vibrato rate
+    pub fn set_vibrato_sync(&mut self, division: Option<NoteDivision>, bpm: Option<f32>) {
+        match (division, bpm) {
+            (Some(division), Some(bpm)) if bpm > 0.0 => {
+                self.vibrato_synced = true;
+                self.vibrato_lfo.sync_to_tempo(bpm, division.beats());
+            }
+            _ => self.vibrato_synced = false,
+        }
+    }
+
+    /// Advance the vibrato LFO and depth/rate ramps; returns the offset in cents
+    fn tick_vibrato(&mut self) -> f32 {
+        let rate = self.vibrato_rate_smoother.tick();
+        if !self.vibrato_synced && rate != self.vibrato_lfo.frequency {
+            self.vibrato_lfo.set_frequency(rate);
+        }
+        // The LFO always runs, so raising the depth picks up mid-cycle
+        let depth = self.vibrato_depth_smoother.tick();
+        self.vibrato_lfo.tick() * depth
+    }
+
+    /// Jump the depth/rate ramps to their targets (nothing is sounding)
+    fn settle_vibrato(&mut self) {
+        self.vibrato_depth_smoother.reset(self.vibrato_depth);
+        let rate = self.vibrato_rate_smoother.target();
+        if !self.vibrato_synced && rate != self.vibrato_lfo.frequency {
+            self.vibrato_rate_smoother.reset(rate);
+            self.vibrato_lfo.set_frequency(rate);
+        }
     }
 
-    /// Set master volume (0.0-1.0)
+    /// Set master volume (0.0-1.0), ramped while notes sound
     pub fn set_master_volume(&mut self, volume: f32) {
         self.master_volume = volume.clamp(0.0, 1.0);
+        self.volume_smoother.set_target(self.master_volume);
+    }
+
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.set_master_volume(db_to_gain(db));
+    }
+
+    /// Set master pan (-1 = left, 0 = centre, 1 = right, equal-power)
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.master_pan = pan.clamp(-1.0, 1.0);
+    }
+
+    /// Write (L+R)/2 to both channels after all stereo processing
+    pub fn set_mono_sum(&mut self, enabled: bool) {
+        self.mono_sum = enabled;
+    }
+
+    /// Pan each voice by its note (low left, high right); negative reverses (-1.0 - 1.0)
+    pub fn set_key_pan_amount(&mut self, amount: f32) {
+        self.key_pan_amount = amount.clamp(-1.0, 1.0);
+    }
+
+    /// Scale each voice's vibrato depth by its velocity (0 = off, 1 = silent vibrato at velocity 0)
+    pub fn set_velocity_to_vibrato(&mut self, amount: f32) {
+        self.velocity_to_vibrato = amount.clamp(0.0, 1.0);
     }
 }
 
@@ -750,6 +1868,87 @@ impl Fm4OpVoiceManager {
 // 6-Operator FM (DX7-style) with 32 algorithms
 // ============================================================================
 
+/// Routing of one DX7 algorithm (operator indices: 0 = OP1, 5 = OP6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dx7Routing {
+    /// (modulator, target) pairs; modulators always have a higher index than their target
+    pub edges: &'static [(usize, usize)],
+    /// Operators summed into the output (those that modulate nothing)
+    pub carriers: &'static [usize],
+    /// Operator carrying the algorithm's feedback loop (modelled as self-feedback)
+    pub feedback: usize,
+    /// Short description of the topology
+    pub description: &'static str,
+}
+
+/// The 32 DX7 algorithms as in the DX7 manual, the single source for routing and carriers
+const DX7_ROUTINGS: [Dx7Routing; 32] = [
+    // 1
+    Dx7Routing { edges: &[(1, 0), (5, 4), (4, 3), (3, 2)], carriers: &[0, 2], feedback: 5, description: "2→1, 6→5→4→3" },
+    // 2
+    Dx7Routing { edges: &[(1, 0), (5, 4), (4, 3), (3, 2)], carriers: &[0, 2], feedback: 1, description: "2→1, 6→5→4→3 (feedback on 2)" },
+    // 3
+    Dx7Routing { edges: &[(2, 1), (1, 0), (5, 4), (4, 3)], carriers: &[0, 3], feedback: 5, description: "3→2→1, 6→5→4" },
+    // 4
+    Dx7Routing { edges: &[(2, 1), (1, 0), (5, 4), (4, 3)], carriers: &[0, 3], feedback: 5, description: "3→2→1, 6→5→4 (feedback 4→6)" },
+    // 5
+    Dx7Routing { edges: &[(1, 0), (3, 2), (5, 4)], carriers: &[0, 2, 4], feedback: 5, description: "2→1, 4→3, 6→5" },
+    // 6
+    Dx7Routing { edges: &[(1, 0), (3, 2), (5, 4)], carriers: &[0, 2, 4], feedback: 5, description: "2→1, 4→3, 6→5 (feedback 5→6)" },
+    // 7
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 2), (5, 4)], carriers: &[0, 2], feedback: 5, description: "2→1, 4+(6→5)→3" },
+    // 8
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 2), (5, 4)], carriers: &[0, 2], feedback: 3, description: "2→1, 4+(6→5)→3 (feedback on 4)" },
+    // 9
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 2), (5, 4)], carriers: &[0, 2], feedback: 1, description: "2→1, 4+(6→5)→3 (feedback on 2)" },
+    // 10
+    Dx7Routing { edges: &[(2, 1), (1, 0), (4, 3), (5, 3)], carriers: &[0, 3], feedback: 2, description: "3→2→1, 5+6→4 (feedback on 3)" },
+    // 11
+    Dx7Routing { edges: &[(2, 1), (1, 0), (4, 3), (5, 3)], carriers: &[0, 3], feedback: 5, description: "3→2→1, 5+6→4" },
+    // 12
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 2), (5, 2)], carriers: &[0, 2], feedback: 1, description: "2→1, 4+5+6→3 (feedback on 2)" },
+    // 13
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 2), (5, 2)], carriers: &[0, 2], feedback: 5, description: "2→1, 4+5+6→3" },
+    // 14
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 3), (5, 3)], carriers: &[0, 2], feedback: 5, description: "2→1, 5+6→4→3" },
+    // 15
+    Dx7Routing { edges: &[(1, 0), (3, 2), (4, 3), (5, 3)], carriers: &[0, 2], feedback: 1, description: "2→1, 5+6→4→3 (feedback on 2)" },
+    // 16
+    Dx7Routing { edges: &[(1, 0), (2, 0), (3, 2), (4, 0), (5, 4)], carriers: &[0], feedback: 5, description: "2+(4→3)+(6→5)→1" },
+    // 17
+    Dx7Routing { edges: &[(1, 0), (2, 0), (3, 2), (4, 0), (5, 4)], carriers: &[0], feedback: 1, description: "2+(4→3)+(6→5)→1 (feedback on 2)" },
+    // 18
+    Dx7Routing { edges: &[(1, 0), (2, 0), (3, 0), (4, 3), (5, 4)], carriers: &[0], feedback: 2, description: "2+3+(6→5→4)→1 (feedback on 3)" },
+    // 19
+    Dx7Routing { edges: &[(2, 1), (1, 0), (5, 3), (5, 4)], carriers: &[0, 3, 4], feedback: 5, description: "3→2→1, 6→4+5" },
+    // 20
+    Dx7Routing { edges: &[(2, 0), (2, 1), (4, 3), (5, 3)], carriers: &[0, 1, 3], feedback: 2, description: "3→1+2, 5+6→4 (feedback on 3)" },
+    // 21
+    Dx7Routing { edges: &[(2, 0), (2, 1), (5, 3), (5, 4)], carriers: &[0, 1, 3, 4], feedback: 2, description: "3→1+2, 6→4+5 (feedback on 3)" },
+    // 22
+    Dx7Routing { edges: &[(1, 0), (5, 2), (5, 3), (5, 4)], carriers: &[0, 2, 3, 4], feedback: 5, description: "2→1, 6→3+4+5" },
+    // 23
+    Dx7Routing { edges: &[(2, 1), (5, 3), (5, 4)], carriers: &[0, 1, 3, 4], feedback: 5, description: "1, 3→2, 6→4+5" },
+    // 24
+    Dx7Routing { edges: &[(5, 2), (5, 3), (5, 4)], carriers: &[0, 1, 2, 3, 4], feedback: 5, description: "1, 2, 6→3+4+5" },
+    // 25
+    Dx7Routing { edges: &[(5, 3), (5, 4)], carriers: &[0, 1, 2, 3, 4], feedback: 5, description: "1, 2, 3, 6→4+5" },
+    // 26
+    Dx7Routing { edges: &[(2, 1), (4, 3), (5, 3)], carriers: &[0, 1, 3], feedback: 5, description: "1, 3→2, 5+6→4" },
+    // 27
+    Dx7Routing { edges: &[(2, 1), (4, 3), (5, 3)], carriers: &[0, 1, 3], feedback: 2, description: "1, 3→2, 5+6→4 (feedback on 3)" },
+    // 28
+    Dx7Routing { edges: &[(1, 0), (4, 3), (3, 2)], carriers: &[0, 2, 5], feedback: 4, description: "2→1, 5→4→3, 6 (feedback on 5)" },
+    // 29
+    Dx7Routing { edges: &[(3, 2), (5, 4)], carriers: &[0, 1, 2, 4], feedback: 5, description: "1, 2, 4→3, 6→5" },
+    // 30
+    Dx7Routing { edges: &[(4, 3), (3, 2)], carriers: &[0, 1, 2, 5], feedback: 4, description: "1, 2, 5→4→3, 6 (feedback on 5)" },
+    // 31
+    Dx7Routing { edges: &[(5, 4)], carriers: &[0, 1, 2, 3, 4], feedback: 5, description: "1, 2, 3, 4, 6→5" },
+    // 32
+    Dx7Routing { edges: &[], carriers: &[0, 1, 2, 3, 4, 5], feedback: 5, description: "1, 2, 3, 4, 5, 6 (additive)" },
+];
+
 /// DX7-style 32 algorithms for 6-operator FM
 /// Operators numbered 1-6, where 6 typically has feedback
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -776,65 +1975,35 @@ impl Dx7Algorithm {
         }
     }
 
+    /// Modulation edges, carriers and feedback operator of this algorithm
+    pub fn routing(&self) -> &'static Dx7Routing {
+        &DX7_ROUTINGS[*self as usize]
+    }
+
     /// Returns which operators are carriers (output to audio) for this algorithm
     /// DX7 operator indices: 0=OP1, 1=OP2, 2=OP3, 3=OP4, 4=OP5, 5=OP6
     pub fn carriers(&self) -> &'static [usize] {
-        match self {
-            // Single carrier algorithms
-            Self::Algo1 | Self::Algo2 | Self::Algo3 | Self::Algo4 => &[0],
-            Self::Algo5 | Self::Algo6 => &[0],
-            Self::Algo7 | Self::Algo8 | Self::Algo9 => &[0],
-            // Two carriers
-            Self::Algo10 | Self::Algo11 | Self::Algo12 => &[0, 2],
-            Self::Algo13 | Self::Algo14 | Self::Algo15 => &[0, 2],
-            Self::Algo16 | Self::Algo17 | Self::Algo18 => &[0, 2],
-            Self::Algo19 | Self::Algo20 | Self::Algo21 => &[0, 1, 2],
-            Self::Algo22 | Self::Algo23 => &[0, 1, 2],
-            // Three+ carriers
-            Self::Algo24 | Self::Algo25 | Self::Algo26 => &[0, 1, 2],
-            Self::Algo27 | Self::Algo28 => &[0, 1, 2, 3],
-            Self::Algo29 | Self::Algo30 => &[0, 1, 2, 3],
-            Self::Algo31 => &[0, 1, 2, 3, 4],
-            Self::Algo32 => &[0, 1, 2, 3, 4, 5], // Full additive
-        }
+        self.routing().carriers
+    }
+
+    /// Operator carrying this algorithm's feedback
+    pub fn feedback_operator(&self) -> usize {
+        self.routing().feedback
+    }
+
+    /// Number of outputs `process_algorithm` averages for this algorithm
+    pub fn output_divisor(&self) -> f32 {
+        self.carriers().len() as f32
+    }
+
+    /// Carriers as a bitmask (bit 0 = OP1)
+    pub fn carrier_mask(&self) -> u8 {
+        self.carriers().iter().fold(0, |mask, &i| mask | (1 << i))
     }
 
     /// Short description of algorithm topology
     pub fn description(&self) -> &'static str {
-        match self {
-            Self::Algo1 => "6→5→4→3→2→1",
-            Self::Algo2 => "6→5→4→3→2, 1",
-            Self::Algo3 => "6→5→4→3, 2→1",
-            Self::Algo4 => "6→5→4, 3→2→1",
-            Self::Algo5 => "6→5, 4→3→2→1",
-            Self::Algo6 => "6→5+4→3→2→1",
-            Self::Algo7 => "6→5→4+3→2→1",
-            Self::Algo8 => "6→5→4→3+2→1",
-            Self::Algo9 => "6→5+4+3→2→1",
-            Self::Algo10 => "6→5→4, 3→2→1",
-            Self::Algo11 => "6→5→4→3, 2→1",
-            Self::Algo12 => "6+5→4→3, 2→1",
-            Self::Algo13 => "6→5→4, 3+2→1",
-            Self::Algo14 => "6→5+4→3, 2→1",
-            Self::Algo15 => "6→5, 4→3, 2→1",
-            Self::Algo16 => "6→5→4, 3, 2→1",
-            Self::Algo17 => "6→5, 4→3, 2, 1",
-            Self::Algo18 => "6→5→4→3, 2, 1",
-            Self::Algo19 => "6→5+4, 3, 2→1",
-            Self::Algo20 => "6→5+4+3, 2→1",
-            Self::Algo21 => "6→5+4, 3+2, 1",
-            Self::Algo22 => "6→5→4, 3, 2, 1",
-            Self::Algo23 => "6→5, 4, 3, 2→1",
-            Self::Algo24 => "6→5, 4→3, 2, 1",
-            Self::Algo25 => "6→5, 4, 3, 2, 1",
-            Self::Algo26 => "6→5, 4→3, 2, 1",
-            Self::Algo27 => "6→5, 4, 3, 2, 1",
-            Self::Algo28 => "6→5→4, 3, 2, 1",
-            Self::Algo29 => "6→5, 4, 3, 2, 1",
-            Self::Algo30 => "6→5→4, 3, 2, 1",
-            Self::Algo31 => "6→5, 4, 3, 2, 1",
-            Self::Algo32 => "6, 5, 4, 3, 2, 1 (additive)",
-        }
+        self.routing().description
     }
 }
 
@@ -850,88 +2019,255 @@ pub struct Fm6OpVoice {
     pub filter_cutoff: f32,
     pub filter_resonance: f32,
     pub filter_enabled: bool,
-
+    /// Per-note pitch offset in cents (humanize detune, set at note_on)
+    pub detune_offset: f32,
+    /// Scale applied to modulator (non-carrier) levels (1.0 = patch as stored)
+    pub brightness: f32,
+    /// Emulate the DX7 output stage (12-bit quantization + gentle saturation)
+    pub vintage: bool,
+    /// Normalize or soft-clip the summed carriers
+    pub additive_mode: AdditiveMode,
+    /// How much modulator levels fall off above the reference note (0.0 - 1.0)
+    pub hf_damping: f32,
+    /// How much velocity scales all modulator levels (0.0 - 1.0)
+    pub velocity_to_fm: f32,
+    /// Hear only this operator, unmodulated, as the sole carrier (patch design aid)
+    pub solo_operator: Option<usize>,
+    /// Pitch envelope, scaled by `pitch_env_depth`
+    pub pitch_env: Envelope,
+    /// Pitch envelope depth in semitones at full level (0 = off)
+    pub pitch_env_depth: f32,
+
+    /// Pitch multiplier from the manager (vibrato, bend), before the pitch envelope
+    pitch_mod: f32,
+    /// Stereo position of this unison copy (-1.0 - 1.0, set at note_on)
+    unison_pan: f32,
+    /// Output gain of this unison copy (1 / sqrt(copies), set at note_on)
+    unison_gain: f32,
     note: u8,
+    /// Id of a frequency-triggered note (None for MIDI notes)
+    id: Option<u32>,
     velocity: f32,
+    /// Samples rendered since note-on
+    age: u64,
+    /// Choke fade gain, and how much it drops per sample (0 = not choking)
+    choke_gain: f32,
+    choke_step: f32,
+    /// Sounding when `panic` started a fade; stopped when the fade ends
+    panicking: bool,
     active: bool,
+    /// Key released while the sustain pedal was down
+    sustained: bool,
+    /// Note-off received since the last note-on
+    released: bool,
     sample_rate: f32,
+
+    // Algorithm crossfade state: a copy of the operators keeps rendering
+    // the previous algorithm while it fades out
+    xfade_operators: [FmOperator; 6],
+    xfade_algorithm: Dx7Algorithm,
+    xfade_remaining: usize,
+    xfade_length: usize,
+    /// Operator oversampling factor (operators run at `sample_rate * oversample`)
+    oversample: usize,
 }
 
 impl Fm6OpVoice {
     pub fn new(sample_rate: f32) -> Self {
         let mut ops: [FmOperator; 6] = std::array::from_fn(|_| FmOperator::new(sample_rate));
-
-        // OP1 (carrier) - default settings
-        ops[0].ratio = 1.0;
-        ops[0].level = 1.0;
-        ops[0].envelope.attack = 0.001;
-        ops[0].envelope.decay = 0.3;
-        ops[0].envelope.sustain = 0.7;
-        ops[0].envelope.release = 0.3;
-
-        // OP2-5 (modulators/carriers depending on algorithm)
-        for i in 1..5 {
-            ops[i].ratio = 1.0 + (i as f32) * 0.5;
-            ops[i].level = 0.5;
-            ops[i].envelope.attack = 0.001;
-            ops[i].envelope.decay = 0.2;
-            ops[i].envelope.sustain = 0.4;
-            ops[i].envelope.release = 0.2;
-        }
-
-        // OP6 (typically has feedback)
-        ops[5].ratio = 1.0;
-        ops[5].level = 0.5;
-        ops[5].feedback = 0.0;
-        ops[5].envelope.attack = 0.001;
-        ops[5].envelope.decay = 0.15;
-        ops[5].envelope.sustain = 0.3;
-        ops[5].envelope.release = 0.15;
+        for (op, params) in ops.iter_mut().zip(Fm6OpParams::default().operators.iter()) {
+            params.apply_to(op);
+        }
 
         Self {
+            xfade_operators: ops.clone(),
             operators: ops,
             algorithm: Dx7Algorithm::default(),
             filter: LadderFilter::new(sample_rate),
             filter_cutoff: 20000.0,
             filter_resonance: 0.0,
             filter_enabled: false,
+            detune_offset: 0.0,
+            brightness: 1.0,
+            vintage: false,
+            additive_mode: AdditiveMode::Normalize,
+            hf_damping: 0.0,
+            velocity_to_fm: 0.0,
+            solo_operator: None,
+            pitch_env: Envelope::new(sample_rate),
+            pitch_env_depth: 0.0,
+            pitch_mod: 1.0,
+            unison_pan: 0.0,
+            unison_gain: 1.0,
             note: 0,
+            id: None,
             velocity: 0.0,
+            age: 0,
+            choke_gain: 1.0,
+            choke_step: 0.0,
+            panicking: false,
             active: false,
+            sustained: false,
+            released: false,
             sample_rate,
+            xfade_algorithm: Dx7Algorithm::default(),
+            xfade_remaining: 0,
+            xfade_length: 0,
+            oversample: 1,
         }
     }
 
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
-        for op in &mut self.operators {
-            op.set_sample_rate(sample_rate);
+        let operator_rate = sample_rate * self.oversample as f32;
+        for op in self.operators.iter_mut().chain(self.xfade_operators.iter_mut()) {
+            op.set_sample_rate(operator_rate);
         }
         self.filter.set_sample_rate(sample_rate);
+        self.pitch_env.set_sample_rate(sample_rate);
+    }
+
+    /// Run the operators at `factor` times the sample rate, averaging down to the output rate
+    pub fn set_oversample(&mut self, factor: usize) {
+        if factor.max(1) != self.oversample {
+            self.oversample = factor.max(1);
+            self.set_sample_rate(self.sample_rate);
+        }
+    }
+
+    /// Switch algorithm, fading the old topology out over `samples` if the voice is sounding
+    pub fn set_algorithm_with_crossfade(&mut self, algo: Dx7Algorithm, samples: usize) {
+        if algo == self.algorithm {
+            return;
+        }
+        if self.active && samples > 0 {
+            self.xfade_operators = self.operators.clone();
+            self.xfade_algorithm = self.algorithm;
+            self.xfade_remaining = samples;
+            self.xfade_length = samples;
+        }
+        self.algorithm = algo;
+        self.update_level_scales();
+    }
+
+    /// Recompute per-operator gain from the voice macros and the algorithm's carrier set
+    pub fn update_level_scales(&mut self) {
+        let carriers = self.algorithm.carriers();
+        let velocity_scale = 1.0 - self.velocity_to_fm + self.velocity_to_fm * self.velocity;
+        let modulator_scale = self.brightness * self.key_damping() * velocity_scale;
+        for (i, op) in self.operators.iter_mut().enumerate() {
+            op.level_scale = if carriers.contains(&i) { 1.0 } else { modulator_scale };
+        }
+    }
+
+    /// Modulator gain from high-frequency damping for the current note
+    fn key_damping(&self) -> f32 {
+        let above = self.note.saturating_sub(HF_DAMPING_REFERENCE_NOTE) as f32;
+        let range = (127 - HF_DAMPING_REFERENCE_NOTE) as f32;
+        (1.0 - self.hf_damping * above / range).max(0.0)
     }
 
     pub fn note_on(&mut self, note: u8, velocity: f32) {
+        self.note_on_at(note, midi_to_freq(note), velocity);
+    }
+
+    /// Start a note sounding at `freq` (the note's tuned frequency)
+    pub fn note_on_at(&mut self, note: u8, freq: f32, velocity: f32) {
         self.note = note;
+        self.id = None;
+        self.start(freq, velocity);
+    }
+
+    /// Start a note at an arbitrary frequency, tracked by `id` instead of note number
+    pub fn note_on_freq(&mut self, id: u32, freq: f32, velocity: f32) {
+        self.note = freq_to_midi(freq);
+        self.id = Some(id);
+        self.start(freq, velocity);
+    }
+
+    fn start(&mut self, freq: f32, velocity: f32) {
         self.velocity = velocity;
+        self.age = 0;
+        self.choke_gain = 1.0;
+        self.choke_step = 0.0;
+        self.panicking = false;
+        self.released = false;
         self.active = true;
+        self.xfade_remaining = 0;
+        self.update_level_scales();
 
-        let note_freq = midi_to_freq(note);
+        let note_freq = freq * detune_multiplier(self.detune_offset);
 
         for op in &mut self.operators {
             op.set_note_frequency(note_freq);
+            op.set_key_note(self.note);
             op.trigger(velocity);
         }
+        self.pitch_env.trigger();
+    }
+
+    /// Start every operator `seconds` into its cycle, as if the note began that much earlier
+    pub fn skip_phases(&mut self, seconds: f32) {
+        for op in &mut self.operators {
+            op.oscillator.skip(seconds);
+        }
     }
 
     pub fn note_off(&mut self) {
+        self.released = true;
         for op in &mut self.operators {
             op.release();
         }
+        for op in &mut self.xfade_operators {
+            op.release();
+        }
+        self.pitch_env.release();
+    }
+
+    /// Apply a pitch multiplier (vibrato) on top of the note frequency;
+    /// the pitch envelope is layered on top in `tick`
+    pub fn set_pitch_mod(&mut self, multiplier: f32) {
+        self.pitch_mod = multiplier;
+        self.apply_pitch_mod(multiplier);
+    }
+
+    fn apply_pitch_mod(&mut self, multiplier: f32) {
+        for op in self.operators.iter_mut().chain(self.xfade_operators.iter_mut()) {
+            op.oscillator.set_pitch_mod(multiplier);
+        }
+    }
+
+    /// Seed each operator's drift from the voice's index so no two wander alike,
+    /// offset by the manager's instance seed
+    fn seed_drift(&mut self, voice_index: usize, instance_seed: u32) {
+        for (index, op) in self.operators.iter_mut().enumerate() {
+            op.seed_drift(offset_seed(drift_seed(voice_index * 6 + index), instance_seed));
+        }
+    }
+
+    fn reset_drift(&mut self) {
+        for op in &mut self.operators {
+            op.reset_drift();
+        }
+    }
+
+    /// Apply the global amplitude modulation to every operator per its `am_sens`
+    pub fn set_amp_mod(&mut self, amount: f32) {
+        for op in self.operators.iter_mut().chain(self.xfade_operators.iter_mut()) {
+            op.set_amp_mod(amount);
+        }
+    }
+
+    /// Skip oscillator work for operators at zero level (output is unchanged)
+    pub fn set_skip_silent_operators(&mut self, enabled: bool) {
+        for op in self.operators.iter_mut().chain(self.xfade_operators.iter_mut()) {
+            op.skip_silent = enabled;
+        }
     }
 
     pub fn is_finished(&self) -> bool {
         let carriers = self.algorithm.carriers();
-        carriers.iter().all(|&i| self.operators[i].is_finished())
+        self.xfade_remaining == 0 && carriers.iter().all(|&i| self.operators[i].is_finished())
     }
 
     /// Generate next sample using selected algorithm
@@ -940,10 +2276,27 @@ impl Fm6OpVoice {
         if !self.active {
             return 0.0;
         }
+        self.age += 1;
+
+        if self.pitch_env_depth != 0.0 {
+            let semitones = self.pitch_env_depth * self.pitch_env.tick();
+            self.apply_pitch_mod(self.pitch_mod * (2.0_f32).powf(semitones / 12.0));
+        }
 
         // Get operator outputs - we need to call tick() in the right order
         // based on the algorithm topology
-        let output = self.process_algorithm();
+        let mut output = self.process_oversampled(false);
+
+        // Blend in the previous algorithm while a switch is fading
+        if self.xfade_remaining > 0 {
+            let old = self.process_oversampled(true);
+            let t = self.xfade_remaining as f32 / self.xfade_length as f32;
+            output = output * (1.0 - t) + old * t;
+            self.xfade_remaining -= 1;
+        }
+        if self.additive_mode == AdditiveMode::Clamp {
+            output = output.tanh();
+        }
 
         // Apply optional filter
         let filtered = if self.filter_enabled {
@@ -954,335 +2307,51 @@ impl Fm6OpVoice {
             output
         };
 
+        let filtered = if self.vintage {
+            // Gentle saturation into a 12-bit DAC
+            let saturated = filtered.tanh();
+            (saturated * VINTAGE_DAC_STEPS).round() / VINTAGE_DAC_STEPS
+        } else {
+            filtered
+        };
+        let Some(choke_gain) = self.tick_choke() else {
+            return 0.0;
+        };
+
         if self.is_finished() {
             self.active = false;
         }
 
-        filtered
+        filtered * choke_gain
     }
 
-    /// Process the selected algorithm and return output
+    /// Render one output sample of the current (or fading) operator set,
+    /// averaging `oversample` sub-samples
     #[inline]
-    fn process_algorithm(&mut self) -> f32 {
-        // Operator indices: 0=OP1, 1=OP2, 2=OP3, 3=OP4, 4=OP5, 5=OP6
-        // In DX7, higher numbered operators typically modulate lower ones
-        match self.algorithm {
-            Dx7Algorithm::Algo1 => {
-                // 6→5→4→3→2→1 (full serial stack)
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(op3 * PI);
-                self.operators[0].tick(op2 * PI)
-            }
-            Dx7Algorithm::Algo2 => {
-                // 6→5→4→3→2, 1 output separately
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(op3 * PI);
-                let op1 = self.operators[0].tick(0.0);
-                (op2 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo3 => {
-                // 6→5→4→3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op3 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo4 => {
-                // 6→5→4, 3→2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(op3 * PI);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op4 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo5 => {
-                // 6→5, 4→3→2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(op3 * PI);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op5 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo6 => {
-                // 6→5+4 combined → 3→2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op6 * PI);
-                let op3 = self.operators[2].tick((op5 + op4) * PI * 0.5);
-                let op2 = self.operators[1].tick(op3 * PI);
-                self.operators[0].tick(op2 * PI)
-            }
-            Dx7Algorithm::Algo7 => {
-                // 6→5→4+3→2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick((op4 + op3) * PI * 0.5);
-                self.operators[0].tick(op2 * PI)
-            }
-            Dx7Algorithm::Algo8 => {
-                // 6→5→4→3+2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                self.operators[0].tick((op3 + op2) * PI * 0.5)
-            }
-            Dx7Algorithm::Algo9 => {
-                // 6→5+4+3→2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick((op5 + op4 + op3) * PI / 3.0);
-                self.operators[0].tick(op2 * PI)
-            }
-            Dx7Algorithm::Algo10 => {
-                // 6→5→4, 3→2→1 (two stacks, both output)
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(op3 * PI);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op4 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo11 => {
-                // 6→5→4→3 out, 2→1 out
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op3 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo12 => {
-                // 6+5→4→3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(0.0);
-                let op4 = self.operators[3].tick((op6 + op5) * PI * 0.5);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op3 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo13 => {
-                // 6→5→4, 3+2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick((op4 + op3 + op2) * PI / 3.0);
-                op1
-            }
-            Dx7Algorithm::Algo14 => {
-                // 6→5+4→3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op6 * PI);
-                let op3 = self.operators[2].tick((op5 + op4) * PI * 0.5);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op3 + op1) * 0.5
-            }
-            Dx7Algorithm::Algo15 => {
-                // 6→5, 4→3, 2→1 (three parallel stacks)
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op5 + op3 + op1) / 3.0
-            }
-            Dx7Algorithm::Algo16 => {
-                // 6→5→4, 3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op4 + op3 + op1) / 3.0
-            }
-            Dx7Algorithm::Algo17 => {
-                // 6→5, 4→3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo18 => {
-                // 6→5→4→3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op3 + op2 + op1) / 3.0
-            }
-            Dx7Algorithm::Algo19 => {
-                // 6→5+4, 3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op6 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op5 + op4 + op3 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo20 => {
-                // 6→5+4+3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op6 * PI);
-                let op3 = self.operators[2].tick(op6 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op5 + op4 + op3 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo21 => {
-                // 6→5+4, 3+2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op6 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(op3 * PI);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op4 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo22 => {
-                // 6→5→4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op4 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo23 => {
-                // 6→5, 4, 3, 2→1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(op2 * PI);
-                (op5 + op4 + op3 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo24 => {
-                // 6→5, 4→3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo25 => {
-                // 6→5, 4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op4 + op3 + op2 + op1) / 5.0
-            }
-            Dx7Algorithm::Algo26 => {
-                // 6→5, 4→3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(op4 * PI);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo27 => {
-                // 6→5, 4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op4 + op3 + op2 + op1) / 5.0
-            }
-            Dx7Algorithm::Algo28 => {
-                // 6→5→4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op4 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo29 => {
-                // 6→5, 4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op4 + op3 + op2 + op1) / 5.0
-            }
-            Dx7Algorithm::Algo30 => {
-                // 6→5→4, 3, 2, 1
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(op5 * PI);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op4 + op3 + op2 + op1) * 0.25
-            }
-            Dx7Algorithm::Algo31 => {
-                // 6→5, 4, 3, 2, 1 (5 carriers)
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(op6 * PI);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op5 + op4 + op3 + op2 + op1) / 5.0
-            }
-            Dx7Algorithm::Algo32 => {
-                // 6, 5, 4, 3, 2, 1 (full additive - all carriers)
-                let op6 = self.operators[5].tick(0.0);
-                let op5 = self.operators[4].tick(0.0);
-                let op4 = self.operators[3].tick(0.0);
-                let op3 = self.operators[2].tick(0.0);
-                let op2 = self.operators[1].tick(0.0);
-                let op1 = self.operators[0].tick(0.0);
-                (op6 + op5 + op4 + op3 + op2 + op1) / 6.0
-            }
+    fn process_oversampled(&mut self, fading: bool) -> f32 {
+        let (algorithm, operators) = if fading {
+            (self.xfade_algorithm, &mut self.xfade_operators)
+        } else {
+            (self.algorithm, &mut self.operators)
+        };
+        // Clamp mode undoes the carrier averaging; tick() soft-clips the sum
+        let gain = match (self.additive_mode, self.solo_operator) {
+            (_, Some(_)) | (AdditiveMode::Normalize, None) => 1.0,
+            (AdditiveMode::Clamp, None) => algorithm.output_divisor(),
+        };
+        let solo = self.solo_operator;
+        let process = |operators: &mut [FmOperator; 6]| match solo {
+            Some(index) => process_solo(index, operators),
+            None => process_algorithm(algorithm, operators),
+        };
+        if self.oversample == 1 {
+            return process(operators) * gain;
+        }
+        let mut sum = 0.0;
+        for _ in 0..self.oversample {
+            sum += process(operators);
         }
+        sum * gain / self.oversample as f32
     }
 
     pub fn reset(&mut self) {
@@ -1290,9 +2359,13 @@ impl Fm6OpVoice {
             op.reset();
         }
         self.filter.reset();
+        self.pitch_env.reset();
+        self.panicking = false;
+        self.released = false;
         self.active = false;
         self.note = 0;
         self.velocity = 0.0;
+        self.xfade_remaining = 0;
     }
 
     pub fn is_active(&self) -> bool {
@@ -1302,353 +2375,3305 @@ impl Fm6OpVoice {
     pub fn note(&self) -> u8 {
         self.note
     }
-}
-
-/// 6-Op FM Voice Manager (DX7-style, polyphonic)
-pub struct Fm6OpVoiceManager {
-    voices: Vec<Fm6OpVoice>,
-    sample_rate: f32,
-    vibrato_lfo: Lfo,
-    vibrato_depth: f32,
-    master_volume: f32,
-}
 
-impl Fm6OpVoiceManager {
-    pub fn new(num_voices: usize, sample_rate: f32) -> Self {
-        let voices = (0..num_voices).map(|_| Fm6OpVoice::new(sample_rate)).collect();
-        let mut vibrato_lfo = Lfo::new(sample_rate);
-        vibrato_lfo.set_frequency(5.0);
-        Self {
-            voices,
-            sample_rate,
-            vibrato_lfo,
-            vibrato_depth: 0.0,
-            master_volume: 0.7,
-        }
+    pub fn velocity(&self) -> f32 {
+        self.velocity
     }
 
-    fn allocate_voice(&mut self) -> Option<&mut Fm6OpVoice> {
-        let inactive_idx = self.voices.iter().position(|v| !v.is_active());
-        if let Some(idx) = inactive_idx {
-            return self.voices.get_mut(idx);
-        }
-        self.voices.first_mut()
+    /// Samples rendered since note-on
+    pub fn age(&self) -> u64 {
+        self.age
     }
 
-    pub fn note_on(&mut self, note: u8, velocity: f32) {
-        if let Some(voice) = self.voices.iter_mut().find(|v| v.is_active() && v.note() == note) {
-            voice.note_on(note, velocity);
-            return;
-        }
-        if let Some(voice) = self.allocate_voice() {
-            voice.note_on(note, velocity);
-        }
+    /// Fade out over `samples` and go idle (choke mode)
+    pub fn choke(&mut self, samples: usize) {
+        self.choke_step = 1.0 / samples.max(1) as f32;
     }
 
-    pub fn note_off(&mut self, note: u8) {
-        for voice in &mut self.voices {
-            if voice.is_active() && voice.note() == note {
-                voice.note_off();
+    /// Advance a choke fade; returns the gain, or None once the voice has gone idle
+    #[inline]
+    fn tick_choke(&mut self) -> Option<f32> {
+        if self.choke_step > 0.0 {
+            self.choke_gain -= self.choke_step;
+            if self.choke_gain <= 0.0 {
+                self.reset();
+                return None;
             }
         }
+        Some(self.choke_gain)
     }
 
-    pub fn panic(&mut self) {
-        for voice in &mut self.voices {
-            voice.reset();
-        }
+    /// Id of a frequency-triggered note (None for MIDI notes)
+    pub fn id(&self) -> Option<u32> {
+        self.id
     }
 
-    pub fn active_voice_count(&self) -> usize {
-        self.voices.iter().filter(|v| v.is_active()).count()
+    /// Whether the note has been released (note-off received since the last note-on)
+    pub fn is_released(&self) -> bool {
+        self.released
     }
 
-    pub fn tick(&mut self) -> f32 {
-        let vibrato = if self.vibrato_depth > 0.0 {
-            let lfo_value = self.vibrato_lfo.tick();
-            let cents = lfo_value * self.vibrato_depth;
-            (2.0_f32).powf(cents / 1200.0)
+    /// Stealing order: released (0), held by the sustain pedal (1), key still down (2)
+    fn steal_rank(&self) -> u8 {
+        if self.is_released() {
+            0
+        } else if self.sustained {
+            1
         } else {
-            1.0
-        };
-
-        let mut output = 0.0;
-        for voice in &mut self.voices {
-            if vibrato != 1.0 && voice.is_active() {
-                for op in &mut voice.operators {
-                    let base_freq = op.oscillator.frequency;
-                    op.oscillator.set_frequency(base_freq * vibrato);
-                }
-            }
-            output += voice.tick();
+            2
         }
-        output * self.master_volume
     }
+}
 
-    pub fn set_algorithm(&mut self, algo: Dx7Algorithm) {
-        for voice in &mut self.voices {
-            voice.algorithm = algo;
+/// Run every operator unmodulated (keeping envelopes in step) and return only `index`
+#[inline]
+fn process_solo(index: usize, operators: &mut [FmOperator; 6]) -> f32 {
+    let mut output = 0.0;
+    for (i, op) in operators.iter_mut().enumerate() {
+        let sample = op.tick(0.0);
+        if i == index {
+            output = sample;
         }
     }
+    output
+}
 
-    pub fn set_op_ratio(&mut self, op_index: usize, ratio: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].ratio = ratio.clamp(0.125, 16.0);
-            }
+/// Operators whose oscillator can be skipped without changing the output: silent ones
+/// without feedback, and modulators that only reach skipped operators (their
+/// feedback and high-pass off, so no state depends on the samples they would make)
+fn skipped_operators(routing: &Dx7Routing, operators: &[FmOperator; 6]) -> [bool; 6] {
+    let mut skipped = [false; 6];
+    // Targets have lower indices than their modulators, so they are decided first
+    for (index, op) in operators.iter().enumerate() {
+        if !op.skip_silent || op.feedback != 0.0 {
+            continue;
         }
+        let mut targets = routing.edges.iter().filter(|&&(modulator, _)| modulator == index).peekable();
+        let unheard = targets.peek().is_some() && !op.hpf.is_enabled() && targets.all(|&(_, target)| skipped[target]);
+        skipped[index] = op.is_silent() || unheard;
     }
+    skipped
+}
 
-    pub fn set_op_level(&mut self, op_index: usize, level: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].level = level.clamp(0.0, 1.0);
-            }
-        }
+/// Process the given algorithm over a set of operators and return output.
+/// Operators run from OP6 down to OP1, so every modulator is ready before its target;
+/// several modulators into one target and several carriers are averaged.
+#[inline]
+fn process_algorithm(algorithm: Dx7Algorithm, operators: &mut [FmOperator; 6]) -> f32 {
+    let routing = algorithm.routing();
+    let mut modulation = [0.0; 6];
+    let mut inputs = [0u8; 6];
+    for &(_, target) in routing.edges {
+        inputs[target] += 1;
     }
 
-    pub fn set_op_detune(&mut self, op_index: usize, detune: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].detune = detune.clamp(-100.0, 100.0);
-            }
-        }
-    }
+    let skipped = skipped_operators(routing, operators);
 
-    pub fn set_op_attack(&mut self, op_index: usize, attack: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].envelope.attack = attack.max(0.001);
+    let mut output = 0.0;
+    for index in (0..6).rev() {
+        let sample = if skipped[index] {
+            operators[index].tick_skipped()
+        } else {
+            let phase_mod = if inputs[index] > 0 { modulation[index] * PI / inputs[index] as f32 } else { 0.0 };
+            operators[index].tick(phase_mod)
+        };
+        let mut carrier = true;
+        for &(modulator, target) in routing.edges {
+            if modulator == index {
+                modulation[target] += sample;
+                carrier = false;
             }
         }
-    }
-
-    pub fn set_op_decay(&mut self, op_index: usize, decay: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].envelope.decay = decay.max(0.001);
-            }
+        if carrier {
+            output += sample;
         }
     }
+    output / algorithm.output_divisor()
+}
 
-    pub fn set_op_sustain(&mut self, op_index: usize, sustain: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].envelope.sustain = sustain.clamp(0.0, 1.0);
+/// Per-operator settings of a 6-op patch snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FmOperatorParams {
+    pub ratio: f32,
+    pub detune: f32,
+    pub level: f32,
+    pub velocity_sens: f32,
+    pub feedback: f32,
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    #[serde(default)]
+    pub env_velocity: f32,
+    #[serde(default)]
+    pub fixed_freq: Option<f32>,
+    #[serde(default = "unity")]
+    pub fixed_coarse: f32,
+    #[serde(default = "unity")]
+    pub fixed_fine: f32,
+    #[serde(default)]
+    pub key_delay: f32,
+    #[serde(default)]
+    pub detune_mode: DetuneMode,
+    #[serde(default)]
+    pub hpf_cutoff: f32,
+    #[serde(default)]
+    pub level_curve: LevelCurve,
+    #[serde(default)]
+    pub am_sens: u8,
+    #[serde(default = "middle_c")]
+    pub level_scale_breakpoint: u8,
+    #[serde(default)]
+    pub level_scale_left_depth: f32,
+    #[serde(default)]
+    pub level_scale_right_depth: f32,
+    #[serde(default)]
+    pub rate_scale: f32,
+    #[serde(default)]
+    pub env_curve: EnvelopeCurve,
+    #[serde(default)]
+    pub env_delay: f32,
+}
+
+impl Default for FmOperatorParams {
+    /// Settings of a freshly created `FmOperator`
+    fn default() -> Self {
+        let envelope = Envelope::default();
+        Self {
+            ratio: 1.0,
+            detune: 0.0,
+            level: 1.0,
+            velocity_sens: 0.5,
+            feedback: 0.0,
+            attack: envelope.attack,
+            decay: envelope.decay,
+            sustain: envelope.sustain,
+            release: envelope.release,
+            env_velocity: 0.0,
+            fixed_freq: None,
+            fixed_coarse: 1.0,
+            fixed_fine: 1.0,
+            key_delay: 0.0,
+            detune_mode: DetuneMode::Absolute,
+            hpf_cutoff: 0.0,
+            level_curve: LevelCurve::Linear,
+            am_sens: 0,
+            level_scale_breakpoint: middle_c(),
+            level_scale_left_depth: 0.0,
+            level_scale_right_depth: 0.0,
+            rate_scale: 0.0,
+            env_curve: envelope.curve,
+            env_delay: envelope.delay,
+        }
+    }
+}
+
+fn middle_c() -> u8 {
+    60
+}
+
+impl FmOperatorParams {
+    /// Capture the settings of an operator
+    pub fn from_operator(op: &FmOperator) -> Self {
+        Self {
+            ratio: op.ratio,
+            detune: op.detune,
+            level: op.level,
+            velocity_sens: op.velocity_sens,
+            feedback: op.feedback,
+            attack: op.envelope.attack,
+            decay: op.envelope.decay,
+            sustain: op.envelope.sustain,
+            release: op.envelope.release,
+            env_velocity: op.env_velocity,
+            fixed_freq: op.fixed_freq,
+            fixed_coarse: op.fixed_coarse,
+            fixed_fine: op.fixed_fine,
+            key_delay: op.key_delay,
+            detune_mode: op.detune_mode,
+            hpf_cutoff: op.hpf.cutoff(),
+            level_curve: op.level_curve,
+            am_sens: op.am_sens,
+            level_scale_breakpoint: op.level_scale_breakpoint,
+            level_scale_left_depth: op.level_scale_left_depth,
+            level_scale_right_depth: op.level_scale_right_depth,
+            rate_scale: op.rate_scale,
+            env_curve: op.envelope.curve,
+            env_delay: op.envelope.delay,
+        }
+    }
+
+    /// Write these settings into an operator
+    pub fn apply_to(&self, op: &mut FmOperator) {
+        op.ratio = self.ratio;
+        op.detune = self.detune;
+        op.level = self.level;
+        op.velocity_sens = self.velocity_sens;
+        op.feedback = self.feedback;
+        op.envelope.attack = self.attack;
+        op.envelope.decay = self.decay;
+        op.envelope.sustain = self.sustain;
+        op.envelope.release = self.release;
+        op.env_velocity = self.env_velocity;
+        op.fixed_freq = self.fixed_freq;
+        op.fixed_coarse = self.fixed_coarse;
+        op.fixed_fine = self.fixed_fine;
+        op.key_delay = self.key_delay;
+        op.detune_mode = self.detune_mode;
+        op.hpf.set_cutoff(self.hpf_cutoff);
+        op.level_curve = self.level_curve;
+        op.am_sens = self.am_sens;
+        op.level_scale_breakpoint = self.level_scale_breakpoint;
+        op.level_scale_left_depth = self.level_scale_left_depth;
+        op.level_scale_right_depth = self.level_scale_right_depth;
+        op.rate_scale = self.rate_scale;
+        op.envelope.curve = self.env_curve;
+        op.envelope.delay = self.env_delay;
+    }
+
+    /// Linear interpolation towards `other` (t = 0 → self, t = 1 → other).
+    /// Fixed-mode settings switch at the midpoint.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let pick = if t < 0.5 { self } else { other };
+        Self {
+            ratio: mix(self.ratio, other.ratio),
+            detune: mix(self.detune, other.detune),
+            level: mix(self.level, other.level),
+            velocity_sens: mix(self.velocity_sens, other.velocity_sens),
+            feedback: mix(self.feedback, other.feedback),
+            attack: mix(self.attack, other.attack),
+            decay: mix(self.decay, other.decay),
+            sustain: mix(self.sustain, other.sustain),
+            release: mix(self.release, other.release),
+            env_velocity: mix(self.env_velocity, other.env_velocity),
+            fixed_freq: pick.fixed_freq,
+            fixed_coarse: pick.fixed_coarse,
+            fixed_fine: pick.fixed_fine,
+            key_delay: mix(self.key_delay, other.key_delay),
+            detune_mode: pick.detune_mode,
+            hpf_cutoff: mix(self.hpf_cutoff, other.hpf_cutoff),
+            level_curve: pick.level_curve,
+            am_sens: pick.am_sens,
+            level_scale_breakpoint: pick.level_scale_breakpoint,
+            level_scale_left_depth: mix(self.level_scale_left_depth, other.level_scale_left_depth),
+            level_scale_right_depth: mix(self.level_scale_right_depth, other.level_scale_right_depth),
+            rate_scale: mix(self.rate_scale, other.rate_scale),
+            env_curve: pick.env_curve,
+            env_delay: mix(self.env_delay, other.env_delay),
+        }
+    }
+}
+
+/// Snapshot of a complete 6-op patch (serializable for presets)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fm6OpParams {
+    pub algorithm: Dx7Algorithm,
+    pub operators: [FmOperatorParams; 6],
+    pub filter_enabled: bool,
+    pub filter_cutoff: f32,
+    pub filter_resonance: f32,
+    pub vibrato_depth: f32,
+    pub vibrato_rate: f32,
+    #[serde(default)]
+    pub am_depth: f32,
+    pub master_volume: f32,
+}
+
+impl Default for Fm6OpParams {
+    /// The patch a new 6-op voice starts with
+    fn default() -> Self {
+        // OP1 is the full-level carrier, OP2-5 step up in ratio, OP6 usually takes the feedback
+        let op = |ratio, level, decay, sustain, release| FmOperatorParams {
+            ratio,
+            level,
+            attack: 0.001,
+            decay,
+            sustain,
+            release,
+            ..FmOperatorParams::default()
+        };
+        Self {
+            algorithm: Dx7Algorithm::default(),
+            operators: [
+                op(1.0, 1.0, 0.3, 0.7, 0.3),
+                op(1.5, 0.5, 0.2, 0.4, 0.2),
+                op(2.0, 0.5, 0.2, 0.4, 0.2),
+                op(2.5, 0.5, 0.2, 0.4, 0.2),
+                op(3.0, 0.5, 0.2, 0.4, 0.2),
+                op(1.0, 0.5, 0.15, 0.3, 0.15),
+            ],
+            filter_enabled: false,
+            filter_cutoff: 20000.0,
+            filter_resonance: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_rate: 5.0,
+            am_depth: 0.0,
+            master_volume: 0.7,
+        }
+    }
+}
+
+impl Fm6OpParams {
+    /// Interpolate two patches (t = 0 → self, t = 1 → other).
+    /// Discrete settings (algorithm, filter on/off) switch at the midpoint.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let pick = if t < 0.5 { self } else { other };
+        Self {
+            algorithm: pick.algorithm,
+            operators: std::array::from_fn(|i| self.operators[i].lerp(&other.operators[i], t)),
+            filter_enabled: pick.filter_enabled,
+            filter_cutoff: mix(self.filter_cutoff, other.filter_cutoff),
+            filter_resonance: mix(self.filter_resonance, other.filter_resonance),
+            vibrato_depth: mix(self.vibrato_depth, other.vibrato_depth),
+            vibrato_rate: mix(self.vibrato_rate, other.vibrato_rate),
+            am_depth: mix(self.am_depth, other.am_depth),
+            master_volume: mix(self.master_volume, other.master_volume),
+        }
+    }
+
+    /// Configure a single voice from the per-voice parts of this patch, with the cutoff
+    /// scaled by the voice's brightness and the algorithm crossfading over `crossfade` samples
+    fn apply_to_voice(&self, voice: &mut Fm6OpVoice, crossfade: usize) {
+        voice.set_algorithm_with_crossfade(self.algorithm, crossfade);
+        for (op, params) in voice.operators.iter_mut().zip(self.operators.iter()) {
+            params.apply_to(op);
+        }
+        voice.filter_enabled = self.filter_enabled;
+        voice.filter_cutoff = (self.filter_cutoff * voice.brightness).clamp(20.0, 20000.0);
+        voice.filter_resonance = self.filter_resonance;
+    }
+}
+
+/// 6-Op FM Voice Manager (DX7-style, polyphonic)
+pub struct Fm6OpVoiceManager {
+    voices: Vec<Fm6OpVoice>,
+    sample_rate: f32,
+    vibrato_lfo: Lfo,
+    vibrato_depth: f32,
+    /// Ramp the vibrato depth (cents) and rate (Hz) towards their targets
+    vibrato_depth_smoother: Smoother,
+    vibrato_rate_smoother: Smoother,
+    /// Vibrato LFO clocked to a tempo division instead of the vibrato rate
+    vibrato_synced: bool,
+    master_volume: f32,
+    /// Ramps the applied volume towards `master_volume`
+    volume_smoother: Smoother,
+    /// Fade applied by `panic` before the hard reset
+    panic_fade: PanicFade,
+    master_pan: f32,
+    /// Algorithm switch crossfade length in milliseconds (0 = hard switch)
+    algorithm_crossfade_ms: f32,
+    /// Maximum random detune applied per note in cents (0 = off)
+    humanize_detune: f32,
+    humanize_rng: NoiseGen,
+    /// Reseed all randomness at note_on so renders are reproducible
+    deterministic: bool,
+    /// Id handed out by the next `note_on_freq`
+    next_note_id: u32,
+    /// Ignore note_off; a second press of a held note releases it
+    latch: bool,
+    /// Note to frequency mapping for MIDI notes
+    tuning: Tuning,
+    /// A new note fast-fades every sounding voice (hi-hat style choke)
+    choke: bool,
+    /// Lowest and highest note that starts a voice (key split)
+    key_range: (u8, u8),
+    /// Sustain pedal (CC64) is down
+    sustain_pedal: bool,
+    /// With the pedal down, a repeated note gets a new voice instead of retriggering
+    pedal_stacking: bool,
+    /// Evens out chord vs single-note levels when enabled
+    compressor: Compressor,
+    auto_level: bool,
+    /// Sum the stereo output to mono on both channels (compatibility check)
+    mono_sum: bool,
+    /// Keyboard panning: full pan per 4 octaves from middle C at 1.0 (0 = off)
+    key_pan_amount: f32,
+    /// How much note-on velocity scales each voice's vibrato depth (0 = off)
+    velocity_to_vibrato: f32,
+    /// Pitch bend position (-1 to 1), smoothed
+    pitch_bend: Smoother,
+    /// Pitch bend range in semitones
+    pitch_bend_range: f32,
+    /// Voices stacked per note (1 = off)
+    unison_voices: usize,
+    /// Spread between the outermost unison copies in cents
+    unison_detune: f32,
+    /// Stereo spread of the unison copies (0 = mono, 1 = hard left/right)
+    unison_width: f32,
+    /// How deeply the vibrato LFO dips operator levels (0-1, scaled per operator by `am_sens`)
+    am_depth: f32,
+    /// Start each unison copy at a different phase so the stack doesn't spike
+    unison_random_phase: bool,
+    /// Soft/hard patches crossfaded by velocity at note_on
+    velocity_layers: Option<Box<(Fm6OpParams, Fm6OpParams)>>,
+    /// Level of the last `process` or `process_stereo` block
+    meter: OutputMeter,
+    /// Bit crusher / sample-rate reducer on the stereo output
+    lofi: LoFi,
+    /// Allpass phase smearing on the summed voices (off by default)
+    dispersion: Dispersion,
+    /// Brightness macro (1.0 = patch as stored)
+    brightness: f32,
+    /// Filter cutoff as stored in the patch (before brightness)
+    filter_cutoff: f32,
+    /// The patch as set by the user; velocity layers only change the voices
+    algorithm: Dx7Algorithm,
+    patch_operators: [FmOperator; 6],
+    filter_enabled: bool,
+    filter_resonance: f32,
+}
+
+impl Fm6OpVoiceManager {
+    pub fn new(num_voices: usize, sample_rate: f32) -> Self {
+        let mut voices: Vec<Fm6OpVoice> = (0..num_voices).map(|_| Fm6OpVoice::new(sample_rate)).collect();
+        for (index, voice) in voices.iter_mut().enumerate() {
+            voice.seed_drift(index, 0);
+        }
+        let mut vibrato_lfo = Lfo::new(sample_rate);
+        vibrato_lfo.set_frequency(5.0);
+        Self {
+            voices,
+            sample_rate,
+            vibrato_lfo,
+            vibrato_depth: 0.0,
+            vibrato_depth_smoother: Smoother::new(0.0, VIBRATO_SMOOTHING_MS, sample_rate),
+            vibrato_rate_smoother: Smoother::new(5.0, VIBRATO_SMOOTHING_MS, sample_rate),
+            vibrato_synced: false,
+            master_volume: 0.7,
+            volume_smoother: Smoother::new(0.7, VOLUME_SMOOTHING_MS, sample_rate),
+            panic_fade: PanicFade::default(),
+            master_pan: 0.0,
+            algorithm_crossfade_ms: 0.0,
+            humanize_detune: 0.0,
+            humanize_rng: NoiseGen::new(),
+            deterministic: false,
+            next_note_id: 1,
+            latch: false,
+            tuning: Tuning::default(),
+            choke: false,
+            key_range: (0, 127),
+            sustain_pedal: false,
+            pedal_stacking: true,
+            compressor: Compressor::new(sample_rate),
+            auto_level: false,
+            mono_sum: false,
+            key_pan_amount: 0.0,
+            velocity_to_vibrato: 0.0,
+            pitch_bend: Smoother::new(0.0, 5.0, sample_rate),
+            pitch_bend_range: 2.0,
+            unison_voices: 1,
+            unison_detune: 20.0,
+            unison_width: 1.0,
+            am_depth: 0.0,
+            unison_random_phase: true,
+            velocity_layers: None,
+            meter: OutputMeter::new(),
+            lofi: LoFi::new(),
+            dispersion: Dispersion::new(sample_rate),
+            brightness: 1.0,
+            filter_cutoff: 20000.0,
+            algorithm: Dx7Algorithm::default(),
+            patch_operators: Fm6OpVoice::new(sample_rate).operators,
+            filter_enabled: false,
+            filter_resonance: 0.0,
+        }
+    }
+
+    /// Change the sample rate, keeping every patch setting
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for voice in &mut self.voices {
+            voice.set_sample_rate(sample_rate);
+        }
+        for op in &mut self.patch_operators {
+            op.set_sample_rate(sample_rate);
+        }
+        self.vibrato_lfo.set_sample_rate(sample_rate);
+        self.vibrato_depth_smoother.set_sample_rate(sample_rate);
+        self.vibrato_rate_smoother.set_sample_rate(sample_rate);
+        self.volume_smoother.set_sample_rate(sample_rate);
+        self.pitch_bend.set_sample_rate(sample_rate);
+        self.compressor.set_sample_rate(sample_rate);
+        self.dispersion.set_sample_rate(sample_rate);
+    }
+
+    /// Find a free voice, or steal the oldest released voice, then the oldest
+    /// pedal-held one, and a held key only when nothing else is left
+    fn allocate_voice(&mut self) -> Option<usize> {
+        let inactive_idx = self.voices.iter().position(|v| !v.is_active());
+        if inactive_idx.is_some() {
+            return inactive_idx;
+        }
+        (0..self.voices.len()).min_by_key(|&i| (self.voices[i].steal_rank(), Reverse(self.voices[i].age)))
+    }
+
+    /// Start a note; returns the index of the voice that plays it (the first copy
+    /// in unison), or None if no voice started
+    pub fn note_on(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        // Velocity 0 note-on is a note-off by MIDI convention
+        if velocity <= 0.0 {
+            self.note_off(note);
+            return None;
+        }
+        if note < self.key_range.0 || note > self.key_range.1 {
+            return None;
+        }
+        if self.latch_release(note) {
+            return None;
+        }
+        self.choke_voices();
+
+        if self.unison_voices > 1 {
+            return self.note_on_unison(note, velocity);
+        }
+
+        // Retrigger a sounding note, unless the pedal is down and repeats stack
+        // or it is fading out in choke mode
+        let existing = if self.choke || (self.sustain_pedal && self.pedal_stacking) {
+            None
+        } else {
+            self.voices
+                .iter()
+                .position(|v| v.is_active() && v.note() == note && v.id().is_none())
+        };
+        let freq = self.tuning.frequency(note);
+        let index = self.prepare_voice(existing, velocity)?;
+        self.voices[index].note_on_at(note, freq, velocity);
+        Some(index)
+    }
+
+    /// Start `unison_voices` copies of a note, detuned and panned symmetrically;
+    /// returns the first copy's voice
+    fn note_on_unison(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        // A repeated note releases its previous stack rather than retriggering one copy
+        if !(self.sustain_pedal && self.pedal_stacking) {
+            for voice in &mut self.voices {
+                if voice.is_active() && voice.note() == note && voice.id().is_none() {
+                    voice.note_off();
+                }
+            }
+        }
+
+        let copies = self.unison_voices;
+        let (detune, width) = (self.unison_detune, self.unison_width);
+        let gain = 1.0 / (copies as f32).sqrt();
+
+        // Start phases as a fraction of the note's period: random, or evenly
+        // spread when renders must be reproducible
+        let mut phases = [0.0; 8];
+        if self.unison_random_phase {
+            for (copy, phase) in phases.iter_mut().enumerate().take(copies) {
+                *phase = if self.deterministic {
+                    copy as f32 / copies as f32
+                } else {
+                    self.humanize_rng.tick() * 0.5 + 0.5
+                };
+            }
+        }
+        let freq = self.tuning.frequency(note);
+        let period = 1.0 / freq;
+
+        let mut first = None;
+        for (copy, phase) in phases.iter().enumerate().take(copies) {
+            // -1 for the lowest copy, +1 for the highest
+            let spread = copy as f32 / (copies - 1) as f32 * 2.0 - 1.0;
+            if let Some(index) = self.prepare_voice(None, velocity) {
+                let voice = &mut self.voices[index];
+                voice.detune_offset += spread * detune * 0.5;
+                voice.unison_pan = spread * width;
+                voice.unison_gain = gain;
+                voice.note_on_at(note, freq, velocity);
+                voice.skip_phases(phase * period);
+                first = first.or(Some(index));
+            }
+        }
+        first
+    }
+
+    /// Start a note at an arbitrary frequency (microtonal).
+    /// Returns an id for `note_off_id`; MIDI `note_off` does not release it.
+    pub fn note_on_freq(&mut self, freq: f32, velocity: f32) -> u32 {
+        let id = self.next_note_id;
+        self.next_note_id = self.next_note_id.wrapping_add(1);
+        if velocity > 0.0 {
+            self.choke_voices();
+            if let Some(index) = self.prepare_voice(None, velocity) {
+                self.voices[index].note_on_freq(id, freq.clamp(1.0, 20000.0), velocity);
+            }
+        }
+        id
+    }
+
+    /// Release a note started with `note_on_freq`
+    pub fn note_off_id(&mut self, id: u32) {
+        for voice in &mut self.voices {
+            if voice.is_active() && voice.id() == Some(id) {
+                voice.note_off();
+            }
+        }
+    }
+
+    /// Pick a voice (retriggering `existing` or allocating) and apply per-note
+    /// randomness and velocity layers
+    fn prepare_voice(&mut self, existing: Option<usize>, velocity: f32) -> Option<usize> {
+        self.reseed_if_deterministic();
+        let detune_offset = self.next_humanize_offset();
+        let layer = self
+            .velocity_layers
+            .as_ref()
+            .map(|layers| layers.0.lerp(&layers.1, velocity));
+
+        let crossfade = self.crossfade_samples();
+        let index = existing.or_else(|| self.allocate_voice())?;
+        let voice = self.voices.get_mut(index)?;
+        if let Some(patch) = &layer {
+            patch.apply_to_voice(voice, crossfade);
+        }
+        voice.detune_offset = detune_offset;
+        if self.deterministic {
+            voice.reset_drift();
+        }
+        voice.unison_pan = 0.0;
+        voice.unison_gain = 1.0;
+        voice.sustained = false;
+        Some(index)
+    }
+
+    /// Capture the current patch (the user's settings, not a velocity layer a voice is playing)
+    pub fn snapshot(&self) -> Fm6OpParams {
+        Fm6OpParams {
+            algorithm: self.algorithm,
+            operators: std::array::from_fn(|i| FmOperatorParams::from_operator(&self.patch_operators[i])),
+            filter_enabled: self.filter_enabled,
+            filter_cutoff: self.filter_cutoff,
+            filter_resonance: self.filter_resonance,
+            vibrato_depth: self.vibrato_depth,
+            vibrato_rate: self.vibrato_rate_smoother.target(),
+            am_depth: self.am_depth,
+            master_volume: self.master_volume,
+        }
+    }
+
+    /// Apply a complete patch to all voices
+    pub fn apply_params(&mut self, params: &Fm6OpParams) {
+        self.set_algorithm(params.algorithm);
+        for (index, op_params) in params.operators.iter().enumerate() {
+            self.update_op(index, |op| op_params.apply_to(op));
+        }
+        self.set_filter_enabled(params.filter_enabled);
+        self.set_filter_resonance(params.filter_resonance);
+        self.set_filter_cutoff(params.filter_cutoff);
+        self.set_vibrato_depth(params.vibrato_depth);
+        self.set_vibrato_rate(params.vibrato_rate);
+        self.set_am_depth(params.am_depth);
+        self.set_master_volume(params.master_volume);
+    }
+
+    /// Load the algorithm and operators of a DX7 voice (see `dx7_sysex`), keeping everything else
+    pub fn load_dx7_voice(&mut self, voice: &Dx7VoiceData) {
+        let mut params = self.snapshot();
+        voice.apply_to(&mut params);
+        self.apply_params(&params);
+    }
+
+    /// Crossfade two patches by velocity at note_on (None = use the current patch)
+    pub fn set_velocity_layers(&mut self, layers: Option<(Fm6OpParams, Fm6OpParams)>) {
+        if layers.is_none() && self.velocity_layers.is_some() {
+            // Put the user's patch back on voices a layer was applied to
+            let patch = self.snapshot();
+            let crossfade = self.crossfade_samples();
+            for voice in &mut self.voices {
+                patch.apply_to_voice(voice, crossfade);
+            }
+        }
+        self.velocity_layers = layers.map(Box::new);
+    }
+
+    /// Set random per-note detune in cents (0 = off)
+    pub fn set_humanize_detune(&mut self, cents: f32) {
+        self.humanize_detune = cents.clamp(0.0, 50.0);
+    }
+
+    /// Reference pitch, transpose, fine tune and scale for MIDI notes (from the next note_on)
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    /// Slow, independent random pitch drift of every operator in cents (0 = off)
+    pub fn set_operator_drift(&mut self, cents: f32) {
+        for voice in &mut self.voices {
+            for op in &mut voice.operators {
+                op.set_drift(cents.clamp(0.0, 10.0));
+            }
+        }
+    }
+
+    fn next_humanize_offset(&mut self) -> f32 {
+        if self.humanize_detune > 0.0 {
+            self.humanize_rng.tick() * self.humanize_detune
+        } else {
+            0.0
+        }
+    }
+
+    /// Restart every random source from its seed whenever a note starts from silence,
+    /// so renders are bit-stable (for tests)
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.deterministic = enabled;
+        if enabled {
+            self.humanize_rng.reseed();
+        }
+    }
+
+    /// Offset every random source (drift, humanize, vibrato S&H) so layered instances
+    /// don't move in lockstep (0 = the fixed default seeds)
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.humanize_rng.set_instance_seed(seed);
+        self.vibrato_lfo.set_instance_seed(seed);
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.seed_drift(index, seed);
+        }
+    }
+
+    /// Restart random sources and the vibrato LFO when a note starts from silence;
+    /// notes joining a sounding chord keep drawing from the sequence
+    fn reseed_if_deterministic(&mut self) {
+        if !self.deterministic || self.active_voice_count() > 0 {
+            return;
+        }
+        self.humanize_rng.reseed();
+        self.vibrato_lfo.reset();
+        self.vibrato_lfo.reseed();
+    }
+
+    /// Respond only to notes from `low` to `high` (inclusive). Notes already
+    /// sounding still release normally.
+    pub fn set_key_range(&mut self, low: u8, high: u8) {
+        let (low, high) = (low.min(127), high.min(127));
+        self.key_range = (low.min(high), low.max(high));
+    }
+
+    /// Choke mode: each new note quickly fades out whatever is sounding
+    pub fn set_choke(&mut self, enabled: bool) {
+        self.choke = enabled;
+    }
+
+    /// Fast-fade every sounding voice ahead of a new note in choke mode
+    fn choke_voices(&mut self) {
+        if !self.choke {
+            return;
+        }
+        let samples = (CHOKE_FADE_MS * 0.001 * self.sample_rate) as usize;
+        for voice in &mut self.voices {
+            if voice.is_active() {
+                voice.choke(samples);
+            }
+        }
+    }
+
+    /// Latch mode: note_off is ignored and pressing a held note again releases it.
+    /// Turning latch off releases every latched note.
+    pub fn set_latch(&mut self, enabled: bool) {
+        if self.latch && !enabled {
+            for voice in &mut self.voices {
+                if voice.is_active() && voice.id().is_none() {
+                    voice.note_off();
+                }
+            }
+        }
+        self.latch = enabled;
+    }
+
+    /// In latch mode, release `note` if it is still held. Returns true if it was.
+    fn latch_release(&mut self, note: u8) -> bool {
+        if !self.latch {
+            return false;
+        }
+        let held = self
+            .voices
+            .iter_mut()
+            .find(|v| v.is_active() && v.note() == note && v.id().is_none() && !v.is_released());
+        match held {
+            Some(voice) => {
+                voice.note_off();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Release a note (ignored in latch mode)
+    pub fn note_off(&mut self, note: u8) {
+        if self.latch {
+            return;
+        }
+        let pedal = self.sustain_pedal;
+        for voice in &mut self.voices {
+            if voice.is_active() && voice.note() == note && voice.id().is_none() {
+                if pedal {
+                    voice.sustained = true;
+                } else {
+                    voice.note_off();
+                }
+            }
+        }
+    }
+
+    /// Sustain pedal: while down, released keys keep sounding until it is lifted
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        if self.sustain_pedal && !down {
+            for voice in &mut self.voices {
+                if voice.sustained {
+                    voice.sustained = false;
+                    if voice.is_active() {
+                        voice.note_off();
+                    }
+                }
+            }
+        }
+        self.sustain_pedal = down;
+    }
+
+    /// With the pedal down, re-pressing a sounding note stacks a new voice
+    /// instead of retriggering it (on by default)
+    pub fn set_pedal_stacking(&mut self, enabled: bool) {
+        self.pedal_stacking = enabled;
+    }
+
+    /// Fade the sounding voices to zero over the panic fade, then hard-reset them;
+    /// notes played during the fade are unaffected. Instant when the fade is 0,
+    /// nothing is sounding, or a fade is already running.
+    pub fn panic(&mut self) {
+        if self.active_voice_count() == 0
+            || self.panic_fade.is_fading()
+            || !self.panic_fade.start(self.sample_rate)
+        {
+            self.panic_now();
+            return;
+        }
+        for voice in &mut self.voices {
+            voice.panicking = voice.is_active();
+        }
+    }
+
+    /// Immediately stop all voices (emergency panic, no fade)
+    pub fn panic_now(&mut self) {
+        for voice in &mut self.voices {
+            voice.reset();
+        }
+        self.panic_fade.cancel();
+    }
+
+    /// Length of the fade `panic` applies before stopping the voices (0 = instant)
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.panic_fade.set_time_ms(ms);
+    }
+
+    /// Stop the voices a finished panic fade has silenced
+    fn finish_panic(&mut self) {
+        for voice in self.voices.iter_mut().filter(|v| v.panicking) {
+            voice.reset();
+        }
+        self.panic_fade.cancel();
+    }
+
+    /// Clear all DSP state (voices, vibrato LFO, smoothers, effects) and controller
+    /// state (pitch bend, sustain pedal) but keep every patch setting
+    pub fn reset(&mut self) {
+        self.panic_now();
+        self.vibrato_lfo.reset();
+        self.settle_vibrato();
+        self.volume_smoother.reset(self.master_volume);
+        self.pitch_bend.reset(0.0);
+        self.sustain_pedal = false;
+        self.compressor.reset();
+        self.lofi.reset();
+        self.dispersion.reset();
+    }
+
+    pub fn active_voice_count(&self) -> usize {
+        self.voices.iter().filter(|v| v.is_active()).count()
+    }
+
+    /// Note, velocity, age, envelope stage and level of every active voice (first carrier's envelope)
+    pub fn voice_states(&self) -> Vec<VoiceState> {
+        self.voices
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| {
+                let env = &v.operators[v.algorithm.carriers()[0]].envelope;
+                VoiceState {
+                    note: v.note(),
+                    velocity: v.velocity(),
+                    age: v.age(),
+                    stage: env.stage(),
+                    level: env.level(),
+                }
+            })
+            .collect()
+    }
+
+    /// Process all voices and return mixed output
+    pub fn tick(&mut self) -> f32 {
+        self.render(false).0
+    }
+
+    /// Sum the voices; with `stereo`, each voice is key-panned into (left, right),
+    /// otherwise both channels carry the plain sum
+    fn render(&mut self, stereo: bool) -> (f32, f32) {
+        // Nothing sounding: keep the vibrato LFO running but skip the voices
+        if self.active_voice_count() == 0 {
+            self.vibrato_lfo.tick();
+            self.settle_vibrato();
+            self.volume_smoother.reset(self.master_volume);
+            self.pitch_bend.tick();
+            self.compressor.reset();
+            self.dispersion.reset();
+            self.panic_fade.cancel();
+            return (0.0, 0.0);
+        }
+
+        let (vibrato_cents, lfo) = self.tick_vibrato();
+        let amp_mod = self.am_depth * (lfo * 0.5 + 0.5);
+        let bend_cents = self.pitch_bend.tick() * self.pitch_bend_range * 100.0;
+        let vibrato = if vibrato_cents + bend_cents != 0.0 {
+            (2.0_f32).powf((vibrato_cents + bend_cents) / 1200.0)
+        } else {
+            1.0
+        };
+
+        let key_pan_amount = if stereo { self.key_pan_amount } else { 0.0 };
+        let velocity_to_vibrato = self.velocity_to_vibrato;
+        let panic_done = self.panic_fade.tick();
+        let panic_gain = self.panic_fade.gain();
+        let (mut left, mut right) = (0.0, 0.0);
+        for voice in &mut self.voices {
+            // Vibrato scales the stored note frequency, it never compounds
+            if voice.is_active() {
+                if velocity_to_vibrato > 0.0 && vibrato_cents != 0.0 {
+                    let scale = 1.0 - velocity_to_vibrato + velocity_to_vibrato * voice.velocity;
+                    voice.set_pitch_mod((2.0_f32).powf((vibrato_cents * scale + bend_cents) / 1200.0));
+                } else {
+                    voice.set_pitch_mod(vibrato);
+                }
+                voice.set_amp_mod(amp_mod);
+            }
+            let fade = if voice.panicking { panic_gain } else { 1.0 };
+            let sample = voice.tick() * voice.unison_gain * fade;
+            let pan = if stereo { voice.unison_pan } else { 0.0 };
+            if key_pan_amount == 0.0 && pan == 0.0 {
+                left += sample;
+                right += sample;
+            } else {
+                let pan = (key_pan(voice.note(), key_pan_amount) + pan).clamp(-1.0, 1.0);
+                let (left_gain, right_gain) = pan_gains(pan);
+                left += sample * left_gain;
+                right += sample * right_gain;
+            }
+        }
+        if panic_done {
+            self.finish_panic();
+        }
+        let volume = self.volume_smoother.tick();
+        let (left, right) = self.dispersion.tick(left * volume, right * volume);
+        if self.auto_level {
+            self.compressor.tick_stereo(left, right)
+        } else {
+            (left, right)
+        }
+    }
+
+    /// Compress the summed output so chords and single notes sit at similar levels
+    pub fn set_auto_level(&mut self, enabled: bool) {
+        self.auto_level = enabled;
+    }
+
+    /// Auto-level threshold (dB), ratio, attack and release (ms)
+    pub fn set_auto_level_params(&mut self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.compressor.set_params(threshold_db, ratio, attack_ms, release_ms);
+    }
+
+    /// Process a buffer of samples
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.tick();
+        }
+        self.meter.update(buffer, &[]);
+    }
+
+    /// Process a single stereo sample with master pan applied
+    pub fn tick_stereo(&mut self) -> (f32, f32) {
+        let (left, right) = self.render(true);
+        let (left_gain, right_gain) = pan_gains(self.master_pan);
+        let (left, right) = self.lofi.tick(left * left_gain, right * right_gain);
+        if self.mono_sum {
+            let mid = (left + right) * 0.5;
+            (mid, mid)
+        } else {
+            (left, right)
+        }
+    }
+
+    /// Process stereo buffer
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.tick_stereo();
+        }
+        self.meter.update(left, right);
+    }
+
+    /// Peak/RMS/clip of the last `process` or `process_stereo` block (single ticks aren't metered)
+    pub fn meter(&self) -> &OutputMeter {
+        &self.meter
+    }
+
+    pub fn set_algorithm(&mut self, algo: Dx7Algorithm) {
+        self.algorithm = algo;
+        let samples = self.crossfade_samples();
+        for voice in &mut self.voices {
+            voice.set_algorithm_with_crossfade(algo, samples);
+        }
+    }
+
+    /// Length of an algorithm crossfade in samples
+    fn crossfade_samples(&self) -> usize {
+        (self.algorithm_crossfade_ms * 0.001 * self.sample_rate) as usize
+    }
+
+    /// Change operator `op_index` of the stored patch and of every voice
+    fn update_op(&mut self, op_index: usize, update: impl Fn(&mut FmOperator)) {
+        if op_index < 6 {
+            update(&mut self.patch_operators[op_index]);
+            for voice in &mut self.voices {
+                update(&mut voice.operators[op_index]);
+            }
+        }
+    }
+
+    /// Recompute an operator's keyboard scaling for the note each voice is playing
+    fn update_key_note(&mut self, op_index: usize) {
+        if op_index < 6 {
+            for voice in &mut self.voices {
+                let note = voice.note();
+                voice.operators[op_index].set_key_note(note);
+            }
+        }
+    }
+
+    /// Set how long sounding voices crossfade when the algorithm changes (0 = hard switch,
+    /// the default). The outgoing algorithm fades out with the operator settings it had
+    /// at the switch; parameter changes during the fade only reach the new one.
+    pub fn set_algorithm_crossfade_ms(&mut self, ms: f32) {
+        self.algorithm_crossfade_ms = ms.clamp(0.0, 100.0);
+    }
+
+    pub fn set_op_ratio(&mut self, op_index: usize, ratio: f32) {
+        self.update_op(op_index, |op| op.ratio = ratio.clamp(0.125, 16.0));
+    }
+
+    pub fn set_op_level(&mut self, op_index: usize, level: f32) {
+        self.update_op(op_index, |op| op.level = level.clamp(0.0, 1.0));
+    }
+
+    pub fn set_op_detune(&mut self, op_index: usize, detune: f32) {
+        self.update_op(op_index, |op| op.detune = detune.clamp(-100.0, 100.0));
+    }
+
+    pub fn set_op_attack(&mut self, op_index: usize, attack: f32) {
+        self.update_op(op_index, |op| op.envelope.attack = attack.max(0.001));
+    }
+
+    pub fn set_op_decay(&mut self, op_index: usize, decay: f32) {
+        self.update_op(op_index, |op| op.envelope.decay = decay.max(0.001));
+    }
+
+    pub fn set_op_sustain(&mut self, op_index: usize, sustain: f32) {
+        self.update_op(op_index, |op| op.envelope.sustain = sustain.clamp(0.0, 1.0));
+    }
+
+    pub fn set_op_release(&mut self, op_index: usize, release: f32) {
+        self.update_op(op_index, |op| op.envelope.release = release.max(0.001));
+    }
+
+    pub fn set_op_feedback(&mut self, op_index: usize, feedback: f32) {
+        self.update_op(op_index, |op| op.feedback = feedback.clamp(0.0, 1.0));
+    }
+
+    pub fn set_op_velocity_sens(&mut self, op_index: usize, sens: f32) {
+        self.update_op(op_index, |op| op.velocity_sens = sens.clamp(0.0, 1.0));
+    }
+
+    /// Skip oscillator work for operators at zero level (on by default, output is unchanged)
+    pub fn set_skip_silent_operators(&mut self, enabled: bool) {
+        for voice in &mut self.voices {
+            voice.set_skip_silent_operators(enabled);
+        }
+    }
+
+    /// Set anti-aliasing quality (operator and filter oversampling)
+    pub fn set_quality(&mut self, quality: Quality) {
+        for voice in &mut self.voices {
+            voice.set_oversample(quality.operator_oversample());
+            voice.filter.oversample = quality.filter_oversample();
+        }
+    }
+
+    /// Turn an operator on or off (fades over a few ms on sounding notes)
+    pub fn set_op_enabled(&mut self, op_index: usize, enabled: bool) {
+        self.update_op(op_index, |op| op.enabled = enabled);
+    }
+
+    /// Set how much velocity scales an operator's envelope depth
+    pub fn set_op_env_velocity(&mut self, op_index: usize, amount: f32) {
+        self.update_op(op_index, |op| op.env_velocity = amount.clamp(0.0, 1.0));
+    }
+
+    /// Choose whether an operator's detune is a cent offset or a ratio offset
+    pub fn set_op_detune_mode(&mut self, op_index: usize, mode: DetuneMode) {
+        self.update_op(op_index, |op| op.detune_mode = mode);
+    }
+
+    /// Delay an operator's entry after note-on, in seconds (0 - 5)
+    pub fn set_op_key_delay(&mut self, op_index: usize, seconds: f32) {
+        self.update_op(op_index, |op| op.key_delay = seconds.clamp(0.0, 5.0));
+    }
+
+    /// High-pass an operator's output before it modulates or is heard (0 Hz = off)
+    pub fn set_op_hpf(&mut self, op_index: usize, cutoff: f32) {
+        self.update_op(op_index, |op| op.hpf.set_cutoff(cutoff));
+    }
+
+    /// Interpret an operator's level linearly or on a DX7-style dB scale
+    pub fn set_op_level_curve(&mut self, op_index: usize, curve: LevelCurve) {
+        self.update_op(op_index, |op| op.level_curve = curve);
+    }
+
+    /// How much the global AM depth reaches an operator (0-3, like the DX7's AMS)
+    pub fn set_op_am_sens(&mut self, op_index: usize, sens: u8) {
+        self.update_op(op_index, |op| op.am_sens = sens.min(3));
+    }
+
+    /// DX7-style keyboard level scaling: dB per octave below (`left`) and above (`right`)
+    /// the `breakpoint` note, on top of the operator's level
+    pub fn set_op_level_scaling(&mut self, op_index: usize, breakpoint: u8, left: f32, right: f32) {
+        self.update_op(op_index, |op| {
+            op.level_scale_breakpoint = breakpoint.min(127);
+            op.level_scale_left_depth = left.clamp(-24.0, 24.0);
+            op.level_scale_right_depth = right.clamp(-24.0, 24.0);
+        });
+        self.update_key_note(op_index);
+    }
+
+    /// Set an operator's whole envelope, with a delay in seconds before the attack
+    /// (the oscillator runs during the delay, unlike `key_delay`)
+    pub fn set_op_dadsr(&mut self, op_index: usize, delay: f32, attack: f32, decay: f32, sustain: f32, release: f32) {
+        if op_index < 6 {
+            self.update_op(op_index, |op| op.envelope.delay = delay.max(0.0));
+            self.set_op_attack(op_index, attack);
+            self.set_op_decay(op_index, decay);
+            self.set_op_sustain(op_index, sustain);
+            self.set_op_release(op_index, release);
+        }
+    }
+
+    /// Linear or exponential segments for an operator's envelope
+    pub fn set_op_env_curve(&mut self, op_index: usize, curve: EnvelopeCurve) {
+        self.update_op(op_index, |op| op.envelope.curve = curve);
+    }
+
+    /// Keyboard rate scaling (0-1): shortens the operator's envelope on higher notes
+    pub fn set_op_rate_scaling(&mut self, op_index: usize, amount: f32) {
+        self.update_op(op_index, |op| op.rate_scale = amount.clamp(0.0, 1.0));
+        self.update_key_note(op_index);
+    }
+
+    /// Switch an operator between ratio and fixed-frequency mode
+    pub fn set_op_fixed_mode(&mut self, op_index: usize, enabled: bool) {
+        self.update_op(op_index, |op| {
+            op.fixed_freq = enabled.then_some(op.fixed_coarse * op.fixed_fine);
+        });
+    }
+
+    /// Pitch envelope times in seconds and sustain level (0.0 - 1.0)
+    pub fn set_pitch_env_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        for voice in &mut self.voices {
+            voice.pitch_env.attack = attack.max(0.001);
+            voice.pitch_env.decay = decay.max(0.001);
+            voice.pitch_env.sustain = sustain.clamp(0.0, 1.0);
+            voice.pitch_env.release = release.max(0.001);
+        }
+    }
+
+    /// Pitch envelope depth in semitones at full envelope level (0 = off, negative bends down)
+    pub fn set_pitch_env_depth(&mut self, semitones: f32) {
+        for voice in &mut self.voices {
+            voice.pitch_env_depth = semitones.clamp(-48.0, 48.0);
+        }
+    }
+
+    /// Run an operator at a fixed frequency in Hz regardless of the note (None = track the note).
+    /// The operator's envelope and level still apply.
+    pub fn set_op_fixed_freq(&mut self, op_index: usize, freq: Option<f32>) {
+        self.update_op(op_index, |op| op.fixed_freq = freq.map(|hz| hz.clamp(1.0, 20000.0)));
+    }
+
+    /// Set fixed-mode coarse decade in Hz (1, 10, 100, 1000) and enable fixed mode
+    pub fn set_op_fixed_coarse(&mut self, op_index: usize, coarse: f32) {
+        self.update_op(op_index, |op| {
+            let fine = op.fixed_fine;
+            op.set_fixed_coarse_fine(coarse, fine);
+        });
+    }
+
+    /// Set fixed-mode fine multiplier (1.0 - 9.99) and enable fixed mode
+    pub fn set_op_fixed_fine(&mut self, op_index: usize, fine: f32) {
+        self.update_op(op_index, |op| {
+            let coarse = op.fixed_coarse;
+            op.set_fixed_coarse_fine(coarse, fine);
+        });
+    }
+
+    pub fn set_filter_enabled(&mut self, enabled: bool) {
+        self.filter_enabled = enabled;
+        for voice in &mut self.voices {
+            voice.filter_enabled = enabled;
+        }
+    }
+
+    pub fn set_filter_cutoff(&mut self, cutoff: f32) {
+        self.filter_cutoff = cutoff.clamp(20.0, 20000.0);
+        let effective = (self.filter_cutoff * self.brightness).clamp(20.0, 20000.0);
+        for voice in &mut self.voices {
+            voice.filter_cutoff = effective;
+        }
+    }
+
+    /// Toggle DX7-style output quantization and saturation on every voice
+    pub fn set_vintage_mode(&mut self, enabled: bool) {
+        for voice in &mut self.voices {
+            voice.vintage = enabled;
+        }
+    }
+
+    /// Choose whether multi-carrier algorithms are normalized or soft-clipped
+    pub fn set_additive_mode(&mut self, mode: AdditiveMode) {
+        for voice in &mut self.voices {
+            voice.additive_mode = mode;
+        }
+    }
+
+    /// Hear one operator alone as a plain carrier, ignoring the algorithm (None = normal)
+    pub fn set_solo_operator(&mut self, op_index: Option<usize>) {
+        let solo = op_index.filter(|&index| index < 6);
+        for voice in &mut self.voices {
+            voice.solo_operator = solo;
+        }
+    }
+
+    /// Reduce modulator levels for notes above C4 at note_on (0.0 = off, 1.0 = silent at the top)
+    pub fn set_hf_damping(&mut self, amount: f32) {
+        for voice in &mut self.voices {
+            voice.hf_damping = amount.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Let velocity scale every modulator's FM depth at note_on (0.0 = off)
+    pub fn set_velocity_to_fm(&mut self, amount: f32) {
+        for voice in &mut self.voices {
+            voice.velocity_to_fm = amount.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Brightness macro: scales modulator levels and filter cutoff (0.0 - 2.0, 1.0 = as stored)
+    pub fn set_brightness(&mut self, amount: f32) {
+        self.brightness = amount.clamp(0.0, 2.0);
+        for voice in &mut self.voices {
+            voice.brightness = self.brightness;
+            voice.update_level_scales();
+        }
+        self.set_filter_cutoff(self.filter_cutoff);
+    }
+
+    pub fn set_filter_resonance(&mut self, resonance: f32) {
+        self.filter_resonance = resonance.clamp(0.0, 1.0);
+        for voice in &mut self.voices {
+            voice.filter_resonance = self.filter_resonance;
+        }
+    }
+
+    /// Set filter response (low-pass, high-pass or band-pass)
+    pub fn set_filter_type(&mut self, filter_type: FilterType) {
+        for voice in &mut self.voices {
+            voice.filter.filter_type = filter_type;
+        }
+    }
+
+    /// Set filter slope (6/12/24 dB/oct)
+    pub fn set_filter_slope(&mut self, slope: FilterSlope) {
+        for voice in &mut self.voices {
+            voice.filter.set_slope(slope);
+        }
+    }
+
+    /// Set filter input drive (1.0 = unity, higher saturates)
+    pub fn set_filter_drive(&mut self, drive: f32) {
+        for voice in &mut self.voices {
+            voice.filter.drive = drive.clamp(1.0, 8.0);
+        }
+    }
+
+    /// Set vibrato depth in cents (0-100), ramped while notes sound
+    pub fn set_vibrato_depth(&mut self, depth: f32) {
+        self.vibrato_depth = depth.clamp(0.0, 100.0);
+        self.vibrato_depth_smoother.set_target(self.vibrato_depth);
+        if self.active_voice_count() == 0 {
+            self.settle_vibrato();
+        }
+    }
+
+    /// Set vibrato rate in Hz (0.1-20), ramped while notes sound
+    pub fn set_vibrato_rate(&mut self, rate: f32) {
+        self.vibrato_rate_smoother.set_target(rate.clamp(0.1, 20.0));
+        if self.active_voice_count() == 0 {
+            self.settle_vibrato();
+        }
+    }
+
+    /// Ramp time for vibrato depth and rate changes in milliseconds (0 = jump)
+    pub fn set_vibrato_smoothing_ms(&mut self, ms: f32) {
+        self.vibrato_depth_smoother.set_time_ms(ms.clamp(0.0, 1000.0));
+        self.vibrato_rate_smoother.set_time_ms(ms.clamp(0.0, 1000.0));
+    }
+
+    /// Vibrato LFO shape (sample & hold gives stepped random pitch)
+    pub fn set_vibrato_waveform(&mut self, waveform: LfoWaveform) {
+        self.vibrato_lfo.waveform = waveform;
+    }
+
+    /// Clock one vibrato cycle (one S&H step) to `division` at the host tempo `bpm`;
+    /// with no division or no tempo the LFO runs free at the vibrato rate
+    pub fn set_vibrato_sync(&mut self, division: Option<NoteDivision>, bpm: Option<f32>) {
+        match (division, bpm) {
+            (Some(division), Some(bpm)) if bpm > 0.0 => {
+                self.vibrato_synced = true;
+                self.vibrato_lfo.sync_to_tempo(bpm, division.beats());
+            }
+            _ => self.vibrato_synced = false,
+        }
+    }
+
+    /// Advance the LFO and vibrato depth/rate ramps; returns the vibrato offset in
+    /// cents and the raw LFO value (-1.0 - 1.0) for amplitude modulation
+    fn tick_vibrato(&mut self) -> (f32, f32) {
+        let rate = self.vibrato_rate_smoother.tick();
+        if !self.vibrato_synced && rate != self.vibrato_lfo.frequency {
+            self.vibrato_lfo.set_frequency(rate);
+        }
+        // The LFO always runs, so raising the depth picks up mid-cycle
+        let depth = self.vibrato_depth_smoother.tick();
+        let lfo = self.vibrato_lfo.tick();
+        (lfo * depth, lfo)
+    }
+
+    /// Global amplitude modulation depth from the vibrato LFO (0-1, DX7 AMD)
+    pub fn set_am_depth(&mut self, depth: f32) {
+        self.am_depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Jump the depth/rate ramps to their targets (nothing is sounding)
+    fn settle_vibrato(&mut self) {
+        self.vibrato_depth_smoother.reset(self.vibrato_depth);
+        let rate = self.vibrato_rate_smoother.target();
+        if !self.vibrato_synced && rate != self.vibrato_lfo.frequency {
+            self.vibrato_rate_smoother.reset(rate);
+            self.vibrato_lfo.set_frequency(rate);
+        }
+    }
+
+    /// Set master volume (0.0-1.0), ramped while notes sound
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        self.volume_smoother.set_target(self.master_volume);
+    }
+
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.set_master_volume(db_to_gain(db));
+    }
+
+    /// Set master pan (-1 = left, 0 = centre, 1 = right, equal-power)
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.master_pan = pan.clamp(-1.0, 1.0);
+    }
+
+    /// Write (L+R)/2 to both channels after all stereo processing
+    pub fn set_mono_sum(&mut self, enabled: bool) {
+        self.mono_sum = enabled;
+    }
+
+    /// Pan each voice by its note (low left, high right); negative reverses (-1.0 - 1.0)
+    pub fn set_key_pan_amount(&mut self, amount: f32) {
+        self.key_pan_amount = amount.clamp(-1.0, 1.0);
+    }
+
+    /// Scale each voice's vibrato depth by its velocity (0 = off, 1 = silent vibrato at velocity 0)
+    pub fn set_velocity_to_vibrato(&mut self, amount: f32) {
+        self.velocity_to_vibrato = amount.clamp(0.0, 1.0);
+    }
+
+    /// Set pitch bend (-1 to 1, where 1 = +pitch_bend_range semitones), ramped over 5 ms
+    pub fn set_pitch_bend(&mut self, value: f32) {
+        self.pitch_bend.set_target(value.clamp(-1.0, 1.0));
+    }
+
+    /// Set pitch bend range in semitones (typically 2, 12, or 24)
+    pub fn set_pitch_bend_range(&mut self, semitones: f32) {
+        self.pitch_bend_range = semitones.clamp(0.0, 48.0);
+    }
+
+    /// Number of detuned copies started per note (1 = off, up to 8)
+    pub fn set_unison_voices(&mut self, voices: usize) {
+        self.unison_voices = voices.clamp(1, 8);
+    }
+
+    /// Spread between the lowest and highest unison copy in cents (0 - 100)
+    pub fn set_unison_detune(&mut self, cents: f32) {
+        self.unison_detune = cents.clamp(0.0, 100.0);
+    }
+
+    /// Stereo spread of the unison copies (0 = mono, 1 = full width)
+    pub fn set_unison_width(&mut self, width: f32) {
+        self.unison_width = width.clamp(0.0, 1.0);
+    }
+
+    /// Decorrelate unison copies with per-copy start phases (on by default)
+    pub fn set_unison_random_phase(&mut self, enabled: bool) {
+        self.unison_random_phase = enabled;
+    }
+
+    /// Allpass phase dispersion on the output (0 = off): softens the attack
+    /// transient of bright patches without changing their spectrum
+    pub fn set_dispersion(&mut self, amount: f32) {
+        self.dispersion.set_amount(amount);
+    }
+
+    /// Lo-fi bit depth (1 - 24)
+    pub fn set_lofi_bit_depth(&mut self, bits: u32) {
+        self.lofi.set_bit_depth(bits);
+    }
+
+    /// Lo-fi sample-and-hold decimation factor (1 = off)
+    pub fn set_lofi_downsample(&mut self, factor: usize) {
+        self.lofi.set_downsample(factor);
+    }
+
+    /// Lo-fi dry/wet (0 = bypass)
+    pub fn set_lofi_mix(&mut self, mix: f32) {
+        self.lofi.set_mix(mix);
+    }
+
+    // Debug getters
+    pub fn get_op_level(&self, op_index: usize) -> f32 {
+        if op_index < 6 && !self.voices.is_empty() {
+            self.voices[0].operators[op_index].level
+        } else {
+            0.0
+        }
+    }
+
+    pub fn get_op_ratio(&self, op_index: usize) -> f32 {
+        if op_index < 6 && !self.voices.is_empty() {
+            self.voices[0].operators[op_index].ratio
+        } else {
+            1.0
+        }
+    }
+
+    pub fn get_algorithm(&self) -> u8 {
+        if self.voices.is_empty() {
+            0
+        } else {
+            self.voices[0].algorithm as u8
+        }
+    }
+}
+
+// Legacy 2-op FM for backwards compatibility
+/// FM Algorithm types (simplified for 2-op)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FmAlgorithm2Op {
+    /// Op2 -> Op1 (classic 2-op FM)
+    Stack,
+    /// Op1 + Op2 (parallel, additive)
+    Parallel,
+    /// Op2 -> Op1, with Op2 feedback
+    StackFeedback,
+}
+
+impl Default for FmAlgorithm2Op {
+    fn default() -> Self {
+        Self::Stack
+    }
+}
+
+/// 2-Operator FM Synthesizer (legacy)
+#[derive(Debug, Clone)]
+pub struct FmSynth {
+    pub carrier: FmOscillator,
+    pub modulator: FmOscillator,
+    pub mod_ratio: f32,
+    pub mod_depth: f32,
+    pub feedback: f32,
+    pub algorithm: FmAlgorithm2Op,
+    /// Detune between the left and right carriers in cents (`tick_stereo` only, 0 = identical channels)
+    pub stereo_spread: f32,
+    /// Right-channel copy of the carrier for `tick_stereo`
+    carrier_right: FmOscillator,
+    feedback_sample: f32,
+    sample_rate: f32,
+}
+
+impl FmSynth {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            carrier: FmOscillator::new(sample_rate),
+            modulator: FmOscillator::new(sample_rate),
+            mod_ratio: 1.0,
+            mod_depth: 0.0,
+            feedback: 0.0,
+            algorithm: FmAlgorithm2Op::default(),
+            stereo_spread: 0.0,
+            carrier_right: FmOscillator::new(sample_rate),
+            feedback_sample: 0.0,
+            sample_rate,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.carrier.set_sample_rate(sample_rate);
+        self.carrier_right.set_sample_rate(sample_rate);
+        self.modulator.set_sample_rate(sample_rate);
+    }
+
+    pub fn tick(&mut self, frequency: f32) -> f32 {
+        self.carrier.set_frequency(frequency);
+        self.modulator.set_frequency(frequency * self.mod_ratio);
+
+        let mod_out = self.tick_modulator();
+        Self::tick_carrier(&mut self.carrier, self.algorithm, mod_out, self.mod_depth)
+    }
+
+    /// Stereo tick: both channels share the modulator, the carriers are detuned by `stereo_spread`
+    pub fn tick_stereo(&mut self, frequency: f32) -> (f32, f32) {
+        let spread = (2.0_f32).powf(self.stereo_spread / 2400.0);
+        self.carrier.set_frequency(frequency / spread);
+        self.carrier_right.set_frequency(frequency * spread);
+        self.modulator.set_frequency(frequency * self.mod_ratio);
+
+        let mod_out = self.tick_modulator();
+        let left = Self::tick_carrier(&mut self.carrier, self.algorithm, mod_out, self.mod_depth);
+        let right = Self::tick_carrier(&mut self.carrier_right, self.algorithm, mod_out, self.mod_depth);
+        (left, right)
+    }
+
+    fn tick_modulator(&mut self) -> f32 {
+        match self.algorithm {
+            FmAlgorithm2Op::Stack | FmAlgorithm2Op::Parallel => self.modulator.tick(0.0),
+            FmAlgorithm2Op::StackFeedback => {
+                let feedback_mod = self.feedback_sample * self.feedback * PI;
+                let mod_out = self.modulator.tick(feedback_mod);
+                self.feedback_sample = mod_out;
+                mod_out
+            }
+        }
+    }
+
+    fn tick_carrier(carrier: &mut FmOscillator, algorithm: FmAlgorithm2Op, mod_out: f32, mod_depth: f32) -> f32 {
+        match algorithm {
+            FmAlgorithm2Op::Parallel => (carrier.tick(0.0) + mod_out * mod_depth) * 0.5,
+            FmAlgorithm2Op::Stack | FmAlgorithm2Op::StackFeedback => carrier.tick(mod_out * mod_depth * PI),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.carrier.reset();
+        self.carrier_right.reset();
+        self.modulator.reset();
+        self.feedback_sample = 0.0;
+    }
+}
+
+// Re-export for backwards compatibility
+pub type Fm4OpSynth = Fm4OpVoice;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::alias_ratio;
+    use crate::envelope::EnvelopeStage;
+    use crate::smoother::PANIC_FADE_MS;
+    use crate::voice::pitch_bend_from_normalized;
+
+    #[test]
+    fn test_fm_operator() {
+        let mut op = FmOperator::new(44100.0);
+        op.set_note_frequency(440.0);
+        op.trigger(1.0);
+
+        let mut samples = Vec::new();
+        for _ in 0..1000 {
+            samples.push(op.tick(0.0));
+        }
+
+        assert!(samples.iter().all(|s| s.is_finite()));
+        assert!(samples.iter().any(|s| *s != 0.0));
+    }
+
+    #[test]
+    fn test_env_velocity() {
+        let plateau = |velocity: f32| {
+            let mut op = FmOperator::new(44100.0);
+            op.velocity_sens = 0.0;
+            op.env_velocity = 1.0;
+            op.envelope.sustain = 0.8;
+            op.set_note_frequency(440.0);
+            op.trigger(velocity);
+            let mut peak: f32 = 0.0;
+            for i in 0..22050 {
+                let sample = op.tick(0.0);
+                if i > 20000 {
+                    peak = peak.max(sample.abs());
+                }
+            }
+            peak
+        };
+        let soft = plateau(0.2);
+        let hard = plateau(1.0);
+        assert!(hard > soft * 2.0, "hard {} soft {}", hard, soft);
+    }
+
+    #[test]
+    fn test_fm_4op_voice() {
+        let mut voice = Fm4OpVoice::new(44100.0);
+        voice.note_on(60, 0.8);
+
+        let mut samples = Vec::new();
+        for _ in 0..1000 {
+            samples.push(voice.tick());
+        }
+
+        assert!(samples.iter().all(|s| s.is_finite()));
+        assert!(voice.is_active());
+    }
+
+    #[test]
+    fn test_all_algorithms() {
+        for algo_idx in 0..8 {
+            let mut voice = Fm4OpVoice::new(44100.0);
+            voice.algorithm = FmAlgorithm::from_u8(algo_idx);
+            voice.note_on(60, 1.0);
+
+            for _ in 0..100 {
+                let sample = voice.tick();
+                assert!(sample.is_finite(), "Algorithm {} produced NaN", algo_idx);
+            }
+        }
+    }
+
+    /// Largest sample-to-sample jump right after switching algorithm mid-note
+    fn algorithm_switch_jump(crossfade_ms: f32) -> f32 {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_algorithm_crossfade_ms(crossfade_ms);
+        manager.set_algorithm(Dx7Algorithm::Algo1);
+        manager.note_on(48, 1.0);
+        let mut prev = 0.0;
+        for _ in 0..4410 {
+            prev = manager.tick();
+        }
+        manager.set_algorithm(Dx7Algorithm::Algo32);
+        let mut max_jump: f32 = 0.0;
+        for _ in 0..64 {
+            let sample = manager.tick();
+            max_jump = max_jump.max((sample - prev).abs());
+            prev = sample;
+        }
+        max_jump
+    }
+
+    #[test]
+    fn test_velocity_layers() {
+        let mut soft = Fm6OpParams::default();
+        let mut hard = Fm6OpParams::default();
+        for op in 0..6 {
+            soft.operators[op].level = 0.1;
+            hard.operators[op].level = 0.9;
+        }
+
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_velocity_layers(Some((soft, hard)));
+
+        manager.note_on(60, 0.1);
+        assert!((manager.get_op_level(0) - 0.18).abs() < 1e-4);
+
+        manager.panic_now();
+        manager.note_on(60, 0.95);
+        assert!((manager.get_op_level(0) - 0.86).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_velocity_layers_keep_patch() {
+        let mut soft = Fm6OpParams::default();
+        let mut hard = Fm6OpParams::default();
+        soft.algorithm = Dx7Algorithm::Algo5;
+        hard.algorithm = Dx7Algorithm::Algo5;
+        for op in 0..6 {
+            soft.operators[op].level = 0.1;
+            hard.operators[op].level = 0.9;
+        }
+        soft.filter_cutoff = 1000.0;
+        hard.filter_cutoff = 1000.0;
+
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_op_ratio(1, 3.0);
+        manager.set_brightness(0.5);
+        let patch = manager.snapshot();
+        manager.set_velocity_layers(Some((soft, hard)));
+        manager.note_on(60, 0.5);
+
+        // The voice plays the layer, with the cutoff still following brightness
+        let voice = &manager.voices[0];
+        assert_eq!(voice.algorithm, Dx7Algorithm::Algo5);
+        assert!((voice.operators[0].level - 0.5).abs() < 1e-4);
+        assert_eq!(voice.filter_cutoff, 500.0);
+
+        // ...while the saved patch stays the user's
+        assert_eq!(manager.snapshot(), patch);
+        manager.set_velocity_layers(None);
+        assert_eq!(manager.voices[0].operators[1].ratio, 3.0);
+        assert_eq!(manager.voices[0].algorithm, Dx7Algorithm::Algo1);
+    }
+
+    #[test]
+    fn test_brightness_scales_modulators() {
+        let render = |brightness: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_brightness(brightness);
+            manager.note_on(48, 1.0);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            let sum: f32 = buffer.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+            (manager, sum.sqrt())
+        };
+        let (dull, dull_hf) = render(0.5);
+        let (bright, bright_hf) = render(1.5);
+        assert!(bright_hf > dull_hf);
+
+        // Stored patch levels are untouched; only the carrier keeps unity scale
+        assert_eq!(dull.get_op_level(1), bright.get_op_level(1));
+        assert_eq!(bright.voices[0].operators[0].level_scale, 1.0);
+        assert_eq!(bright.voices[0].operators[1].level_scale, 1.5);
+    }
+
+    #[test]
+    fn test_vintage_mode() {
+        let render = |vintage: Option<bool>| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            if let Some(enabled) = vintage {
+                manager.set_vintage_mode(enabled);
+            }
+            manager.note_on(57, 0.8);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            buffer
+        };
+        let clean = render(None);
+        assert_eq!(render(Some(false)), clean);
+
+        let vintage = render(Some(true));
+        let diff: f32 = clean.iter().zip(&vintage).map(|(a, b)| (a - b).powi(2)).sum();
+        let diff_rms = (diff / clean.len() as f32).sqrt();
+        assert!(diff_rms > 1e-5 && diff_rms < 0.1, "difference rms {}", diff_rms);
+
+        // Saturation only ever pulls the peaks in
+        let peak = |b: &[f32]| b.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        assert!(peak(&vintage) <= peak(&clean) + 1e-3);
+    }
+
+    #[test]
+    fn test_idle_manager_is_silent() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.set_vibrato_depth(20.0);
+        let mut buffer = vec![1.0; 256];
+        manager.process(&mut buffer);
+        assert!(buffer.iter().all(|&s| s == 0.0));
+
+        manager.note_on(60, 1.0);
+        manager.process(&mut buffer);
+        assert!(buffer.iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn test_master_pan_hard_left() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.set_master_pan(-1.0);
+        manager.note_on(60, 1.0);
+
+        let mut left = vec![0.0; 1024];
+        let mut right = vec![0.0; 1024];
+        manager.process_stereo(&mut left, &mut right);
+        assert!(left.iter().any(|&s| s != 0.0));
+        assert!(right.iter().all(|&s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_zero_velocity_note_on_releases() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.note_on(60, 1.0);
+        manager.note_on(60, 0.0);
+        assert_eq!(manager.active_voice_count(), 1);
+        for _ in 0..44100 {
+            manager.tick();
+        }
+        assert_eq!(manager.active_voice_count(), 0);
+    }
+
+    #[test]
+    fn test_algorithm_crossfade() {
+        let hard = algorithm_switch_jump(0.0);
+        let faded = algorithm_switch_jump(10.0);
+        assert!(faded < hard * 0.5, "crossfade jump {} vs hard switch {}", faded, hard);
+    }
+
+    #[test]
+    fn test_fixed_coarse_fine() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_op_fixed_coarse(1, 100.0);
+        manager.set_op_fixed_fine(1, 4.40);
+
+        for note in [36, 60, 84] {
+            manager.note_on(note, 1.0);
+            let freq = manager.voices[0].operators[1].oscillator.frequency;
+            assert!((freq - 440.0).abs() < 1e-3, "note {} gave {} Hz", note, freq);
+            manager.panic_now();
+        }
+
+        // Ratio-mode operators still track the note
+        manager.note_on(69, 1.0);
+        assert!((manager.voices[0].operators[0].oscillator.frequency - 440.0).abs() < 1e-3);
+        manager.set_op_fixed_mode(1, false);
+        manager.note_on(57, 1.0);
+        assert!(manager.voices[0].operators[1].fixed_freq.is_none());
+    }
+
+    #[test]
+    fn test_carrier_mask() {
+        assert_eq!(Dx7Algorithm::Algo32.carrier_mask(), 0b11_1111);
+        assert_eq!(Dx7Algorithm::Algo1.carrier_mask(), 0b00_0101);
+        assert_eq!(Dx7Algorithm::Algo16.carrier_mask(), 0b00_0001);
+        assert_eq!(FmAlgorithm::Algo8Additive.carrier_mask(), 0b1111);
+        assert_eq!(FmAlgorithm::Algo1Serial.carrier_mask(), 0b0001);
+    }
+
+    #[test]
+    fn test_hf_damping() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_hf_damping(0.8);
+
+        manager.note_on(48, 1.0);
+        let low = manager.voices[0].operators[1].level_scale;
+        manager.panic_now();
+        manager.note_on(96, 1.0);
+        let high = manager.voices[0].operators[1].level_scale;
+
+        assert_eq!(low, 1.0);
+        assert!(high < low);
+        // Carriers are never damped
+        assert_eq!(manager.voices[0].operators[0].level_scale, 1.0);
+    }
+
+    #[test]
+    fn test_deterministic_renders_match() {
+        let render = |history: bool| {
+            let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+            manager.set_humanize_detune(20.0);
+            manager.set_vibrato_depth(30.0);
+            if history {
+                manager.note_on(40, 1.0);
+                let mut scratch = vec![0.0; 1000];
+                manager.process(&mut scratch);
+                manager.panic_now();
+            }
+            manager.set_deterministic(true);
+            manager.note_on(64, 0.9);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            buffer
+        };
+        assert_eq!(render(false), render(true));
+    }
+
+    #[test]
+    fn test_deterministic_renders_match_with_drift() {
+        let render = |history: bool| {
+            let mut manager = Fm4OpVoiceManager::new(2, 44100.0);
+            manager.set_operator_drift(5.0);
+            if history {
+                manager.note_on(40, 1.0);
+                let mut scratch = vec![0.0; 30000];
+                manager.process(&mut scratch);
+                manager.panic_now();
+            }
+            manager.set_deterministic(true);
+            manager.note_on(64, 0.9);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            buffer
+        };
+        assert_eq!(render(false), render(true));
+
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_operator_drift(5.0);
+        manager.set_deterministic(true);
+        let mut take = || {
+            manager.note_on(64, 0.9);
+            let mut buffer = vec![0.0; 30000];
+            manager.process(&mut buffer);
+            manager.panic_now();
+            buffer
+        };
+        assert_eq!(take(), take());
+    }
+
+    #[test]
+    fn test_deterministic_chord_notes_get_different_detune() {
+        let mut manager = Fm6OpVoiceManager::new(3, 44100.0);
+        manager.set_humanize_detune(20.0);
+        manager.set_deterministic(true);
+        let mut take = || {
+            for note in [60, 64, 67] {
+                manager.note_on(note, 1.0);
+            }
+            let offsets: Vec<f32> = manager.voices.iter().map(|v| v.detune_offset).collect();
+            manager.panic_now();
+            offsets
+        };
+        let first = take();
+        assert_ne!(first[0], first[1]);
+        assert_ne!(first[1], first[2]);
+        // Each chord from silence still repeats exactly
+        assert_eq!(first, take());
+    }
+
+    #[test]
+    fn test_long_vibrato_hold_keeps_pitch() {
+        // Ten minutes at a low sample rate keeps the test fast
+        let sample_rate = 2000.0;
+        let mut manager = Fm6OpVoiceManager::new(1, sample_rate);
+        manager.set_op_sustain(0, 1.0);
+        manager.set_vibrato_depth(50.0);
+        manager.set_vibrato_rate(6.0);
+        manager.note_on(69, 1.0);
+        for _ in 0..(600.0 * sample_rate) as usize {
+            manager.tick();
+        }
+
+        // Let the depth ramp reach zero
+        manager.set_vibrato_depth(0.0);
+        for _ in 0..(0.05 * sample_rate) as usize {
+            manager.tick();
+        }
+        let osc = &manager.voices[0].operators[0].oscillator;
+        let freq = osc.phase_increment * sample_rate;
+        let cents = 1200.0 * (freq / 440.0).log2();
+        assert!(cents.abs() < 1.0, "drifted {} cents", cents);
+    }
+
+    #[test]
+    fn test_velocity_to_fm() {
+        let render = |amount: Option<f32>, velocity: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            if let Some(amount) = amount {
+                manager.set_velocity_to_fm(amount);
+            }
+            manager.note_on(48, velocity);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            buffer
+        };
+        // High-frequency content relative to level
+        let brightness = |buffer: &[f32]| {
+            let diff: f32 = buffer.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+            let level: f32 = buffer.iter().map(|x| x * x).sum();
+            (diff / level).sqrt()
+        };
+
+        assert_eq!(render(Some(0.0), 0.3), render(None, 0.3));
+        let soft = brightness(&render(Some(1.0), 0.2));
+        let hard = brightness(&render(Some(1.0), 1.0));
+        assert!(hard > soft * 1.2, "hard {} soft {}", hard, soft);
+    }
+
+    #[test]
+    fn test_note_on_freq() {
+        let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+        let freq = 311.7;
+        let id = manager.note_on_freq(freq, 1.0);
+
+        let voice = manager.voices.iter().find(|v| v.id() == Some(id)).unwrap();
+        // OP1 runs at ratio 1.0 with no detune
+        assert_eq!(voice.operators[0].oscillator.frequency, freq);
+
+        manager.note_off_id(id);
+        let voice = manager.voices.iter().find(|v| v.id() == Some(id)).unwrap();
+        let released = |op: &FmOperator| op.envelope.stage() == EnvelopeStage::Release;
+        assert!(voice.operators.iter().all(released));
+    }
+
+    #[test]
+    fn test_op_enable_fades() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        // Plain sine carrier so any jump stands out against the waveform slope
+        for op in 1..6 {
+            manager.set_op_level(op, 0.0);
+        }
+        manager.set_op_sustain(0, 1.0);
+        manager.note_on(45, 1.0);
+
+        let mut buffer = vec![0.0; 8820];
+        for (i, sample) in buffer.iter_mut().enumerate() {
+            // Mid-cycle, away from a zero crossing
+            if i == 4510 {
+                manager.set_op_enabled(0, false);
+            }
+            *sample = manager.tick();
+        }
+        let max_step = |samples: &[f32]| {
+            samples.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0, f32::max)
+        };
+        let steady = max_step(&buffer[..4510]);
+        let toggled = max_step(&buffer[4500..]);
+        assert!(toggled <= steady * 1.1, "toggled {} steady {}", toggled, steady);
+    }
+
+    #[test]
+    fn test_skip_silent_operators() {
+        let render = |skip: bool| {
+            let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+            manager.set_skip_silent_operators(skip);
+            for op in 2..6 {
+                manager.set_op_level(op, 0.0);
+            }
+            manager.note_on(57, 0.9);
+            manager.note_on(64, 0.7);
+            let mut buffer = vec![0.0; 4410];
+            manager.process(&mut buffer);
+            buffer
+        };
+        assert_eq!(render(true), render(false));
+
+        // Algo1 with OP3 silent: OP4-OP6 only reach OP3, so all four are skipped
+        let mut voice = Fm6OpVoice::new(44100.0);
+        voice.operators[2].level = 0.0;
+        assert_eq!(
+            skipped_operators(Dx7Algorithm::Algo1.routing(), &voice.operators),
+            [false, false, true, true, true, true]
+        );
+        // A feedback loop or a high-pass keeps its operator running
+        voice.operators[5].feedback = 0.5;
+        voice.operators[3].hpf.set_cutoff(200.0);
+        assert_eq!(
+            skipped_operators(Dx7Algorithm::Algo1.routing(), &voice.operators),
+            [false, false, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_skip_silent_operators_mid_note() {
+        // Operators come out of silence mid-note with the same feedback, filter and phase state
+        let render = |skip: bool| {
+            let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+            manager.set_skip_silent_operators(skip);
+            manager.set_op_feedback(5, 0.7);
+            manager.set_op_hpf(2, 300.0);
+            manager.note_on(57, 0.9);
+            manager.note_on(64, 0.7);
+            let mut buffer = vec![0.0; 6615];
+            manager.process(&mut buffer[..2205]);
+            manager.set_op_level(2, 0.0);
+            manager.set_op_level(5, 0.0);
+            manager.process(&mut buffer[2205..4410]);
+            manager.set_op_level(2, 0.8);
+            manager.set_op_level(5, 0.6);
+            manager.process(&mut buffer[4410..]);
+            buffer
+        };
+        let (skipped, full) = (render(true), render(false));
+        assert!(full.iter().any(|s| s.abs() > 0.01));
+        for (a, b) in skipped.iter().zip(&full) {
+            assert!((a - b).abs() < 1e-6, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_latch() {
+        let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+        manager.set_latch(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        assert!(manager.voices[0].is_active());
+        assert!(!manager.voices[0].is_released());
+
+        manager.note_on(60, 1.0);
+        assert!(manager.voices[0].is_released());
+        assert_eq!(manager.active_voice_count(), 1);
+    }
+
+    #[test]
+    fn test_voice_states() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.note_on(48, 1.0);
+        manager.tick();
+        let states = manager.voice_states();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].note, 48);
+        assert_eq!(states[0].stage, EnvelopeStage::Attack);
+        assert!(states[0].level > 0.0);
+
+        manager.note_off(48);
+        assert_eq!(manager.voice_states()[0].stage, EnvelopeStage::Release);
+    }
+
+    #[test]
+    fn test_op_key_delay() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        // OP1 carrier alone, entering 50 ms late
+        for op in 1..6 {
+            manager.set_op_level(op, 0.0);
+        }
+        manager.set_op_attack(0, 0.02);
+        manager.set_op_sustain(0, 1.0);
+        manager.set_op_key_delay(0, 0.05);
+        manager.note_on(57, 1.0);
+
+        let delay = (0.05 * 44100.0) as usize;
+        let mut buffer = vec![0.0; delay + 2205];
+        manager.process(&mut buffer);
+        assert!(buffer[..delay].iter().all(|&s| s == 0.0));
+        assert!(manager.voices[0].is_active());
+
+        // Then it ramps in from silence through the attack
+        let first_peak = buffer[delay..delay + 100].iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        let later_peak = buffer[delay + 1000..].iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!(first_peak > 0.0 && first_peak < later_peak, "{} {}", first_peak, later_peak);
+    }
+
+    #[test]
+    fn test_detune_mode() {
+        let note_freq = 220.0;
+        let mut op = FmOperator::new(44100.0);
+        op.detune = 10.0;
+
+        // Absolute: the cent offset from the harmonic is the same at every ratio
+        for ratio in [1.0, 3.0, 7.0] {
+            op.ratio = ratio;
+            op.set_note_frequency(note_freq);
+            let cents = 1200.0 * (op.oscillator.frequency / (note_freq * ratio)).log2();
+            assert!((cents - 10.0).abs() < 1e-3, "ratio {}: {} cents", ratio, cents);
+        }
+
+        // Proportional: the frequency stays the ratio plus a fixed offset, times the note
+        op.detune_mode = DetuneMode::Proportional;
+        let offset = 2.0_f32.powf(10.0 / 1200.0) - 1.0;
+        for ratio in [1.0, 3.0, 7.0] {
+            op.ratio = ratio;
+            op.set_note_frequency(note_freq);
+            let effective_ratio = op.oscillator.frequency / note_freq;
+            assert!((effective_ratio - (ratio + offset)).abs() < 1e-5, "ratio {}", ratio);
+        }
+    }
+
+    #[test]
+    fn test_quality_reduces_fm_aliasing() {
+        // Bright patch: integer ratios and no feedback, so anything
+        // off the harmonic series is aliasing
+        let render = |quality: Quality| {
+            let mut voice = Fm6OpVoice::new(44100.0);
+            for (i, op) in voice.operators.iter_mut().enumerate() {
+                op.ratio = (i + 1) as f32;
+                op.level = 0.5;
+                op.feedback = 0.0;
+                op.envelope.sustain = 1.0;
+            }
+            voice.set_oversample(quality.operator_oversample());
+            voice.filter.oversample = quality.filter_oversample();
+            voice.note_on_freq(1, 1230.0, 1.0);
+            let buffer: Vec<f32> = (0..441 + 4410).map(|_| voice.tick()).collect();
+            alias_ratio(&buffer[441..], 1230.0, 44100.0)
+        };
+
+        let normal = render(Quality::Normal);
+        let high = render(Quality::High);
+        let ultra = render(Quality::Ultra);
+        assert!(high < normal * 0.5, "normal {} high {}", normal, high);
+        assert!(ultra <= high, "high {} ultra {}", high, ultra);
+
+        // Same for the 4-op engine through its manager
+        let render = |quality: Quality| {
+            let mut manager = Fm4OpVoiceManager::new(1, 44100.0);
+            manager.set_quality(quality);
+            for op in 0..4 {
+                manager.set_op_ratio(op, (op + 1) as f32);
+                manager.set_op_level(op, 0.5);
+                manager.set_op_sustain(op, 1.0);
+            }
+            manager.note_on_freq(1230.0, 1.0);
+            let mut buffer = vec![0.0; 441 + 4410];
+            manager.process(&mut buffer);
+            alias_ratio(&buffer[441..], 1230.0, 44100.0)
+        };
+        let normal = render(Quality::Normal);
+        let high = render(Quality::High);
+        assert!(high < normal * 0.5, "4-op normal {} high {}", normal, high);
+    }
+
+    #[test]
+    fn test_persisted_patch_round_trip() {
+        // Settings with no plugin param, e.g. fixed-frequency operators
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_algorithm(Dx7Algorithm::Algo5);
+        manager.set_op_fixed_coarse(2, 100.0);
+        manager.set_op_fixed_fine(2, 4.4);
+        manager.set_op_detune_mode(1, DetuneMode::Proportional);
+        let saved = manager.snapshot();
+        assert!(saved.operators[2].fixed_freq.is_some());
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: Fm6OpParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, saved);
+
+        let mut restored = Fm6OpVoiceManager::new(1, 44100.0);
+        restored.apply_params(&loaded);
+        assert_eq!(restored.snapshot(), saved);
+
+        // Defaults describe a fresh voice; a manager without voices still snapshots
+        assert_eq!(Fm6OpVoiceManager::new(1, 44100.0).snapshot(), Fm6OpParams::default());
+        let mut empty = Fm6OpVoiceManager::new(0, 44100.0);
+        empty.set_master_volume(0.5);
+        assert_eq!(empty.snapshot().master_volume, 0.5);
+        assert_eq!(empty.snapshot().operators, Fm6OpParams::default().operators);
+    }
+
+    #[test]
+    fn test_pedal_stacks_repeated_notes() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        let sounding = |m: &Fm6OpVoiceManager| m.voices.iter().filter(|v| v.is_active() && v.note == 60).count();
+
+        manager.set_sustain_pedal(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        manager.note_on(60, 1.0);
+        assert_eq!(sounding(&manager), 2);
+        assert_eq!(manager.voice_states().iter().filter(|s| s.stage != EnvelopeStage::Release).count(), 2);
+
+        // Lifting the pedal releases only the key that is up
+        manager.set_sustain_pedal(false);
+        assert_eq!(manager.voice_states().iter().filter(|s| s.stage == EnvelopeStage::Release).count(), 1);
+
+        // With stacking off, the repeat retriggers the held voice
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.set_pedal_stacking(false);
+        manager.set_sustain_pedal(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        manager.note_on(60, 1.0);
+        assert_eq!(sounding(&manager), 1);
+    }
+
+    #[test]
+    fn test_fm4op_sustain_pedal() {
+        let mut manager = Fm4OpVoiceManager::new(4, 44100.0);
+        let released = |m: &Fm4OpVoiceManager| m.voice_states().iter().filter(|s| s.stage == EnvelopeStage::Release).count();
+
+        manager.set_sustain_pedal(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        assert_eq!(released(&manager), 0);
+        // Repeats stack while the pedal is down
+        manager.note_on(60, 1.0);
+        assert_eq!(manager.active_voice_count(), 2);
+
+        // Lifting the pedal releases only the key that is up
+        manager.set_sustain_pedal(false);
+        assert_eq!(released(&manager), 1);
+    }
+
+    #[test]
+    fn test_fm6_set_sample_rate() {
+        let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+        manager.set_op_ratio(1, 3.0);
+        manager.set_op_attack(2, 0.25);
+        manager.set_vibrato_depth(20.0);
+        let before = manager.snapshot();
+
+        manager.set_sample_rate(88200.0);
+        assert_eq!(manager.snapshot(), before);
+
+        // Operators advance half as far per sample at twice the rate
+        manager.note_on(69, 1.0);
+        let osc = &manager.voices[0].operators[1].oscillator;
+        assert!((osc.phase_increment - 440.0 * 3.0 / 88200.0).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_additive_clamp_mode() {
+        // Six in-phase carriers at full level
+        let render = |mode: AdditiveMode| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_algorithm(Dx7Algorithm::Algo32);
+            manager.set_additive_mode(mode);
+            for op in 0..6 {
+                manager.set_op_ratio(op, 1.0);
+                manager.set_op_level(op, 1.0);
+                manager.set_op_sustain(op, 1.0);
+            }
+            manager.note_on(57, 1.0);
+            (0..4410).map(|_| manager.voices[0].tick()).collect::<Vec<f32>>()
+        };
+        let rms = |buf: &[f32]| (buf.iter().map(|s| s * s).sum::<f32>() / buf.len() as f32).sqrt();
+
+        let normalized = render(AdditiveMode::Normalize);
+        let clamped = render(AdditiveMode::Clamp);
+        assert!(rms(&clamped) > rms(&normalized) * 1.2, "{} vs {}", rms(&clamped), rms(&normalized));
+        assert!(clamped.iter().all(|s| s.abs() <= 1.0));
+    }
+
+    #[test]
+    fn test_2op_to_4op_conversion() {
+        // 220 Hz fits 22 whole cycles in the window
+        let spectrum = |buffer: &[f32]| -> Vec<f64> {
+            (1..=12)
+                .map(|h| {
+                    let w = 2.0 * std::f64::consts::PI * 220.0 * h as f64 / 44100.0;
+                    let (re, im) = buffer.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, &s)| {
+                        (re + s as f64 * (w * i as f64).cos(), im - s as f64 * (w * i as f64).sin())
+                    });
+                    2.0 * (re * re + im * im).sqrt() / buffer.len() as f64
+                })
+                .collect()
+        };
+
+        for algorithm in [FmAlgorithm2Op::Stack, FmAlgorithm2Op::StackFeedback, FmAlgorithm2Op::Parallel] {
+            let mut legacy = FmSynth::new(44100.0);
+            legacy.algorithm = algorithm;
+            legacy.mod_ratio = 2.0;
+            legacy.mod_depth = 0.8;
+            legacy.feedback = 0.3;
+
+            let mut voice = Fm4OpVoice::new(44100.0);
+            voice.configure_from_2op(&legacy);
+            voice.note_on_freq(1, 220.0, 0.6);
+
+            let old: Vec<f32> = (0..4410).map(|_| legacy.tick(220.0)).collect();
+            let new: Vec<f32> = (0..4410).map(|_| voice.tick()).collect();
+            for (h, (a, b)) in spectrum(&old).iter().zip(spectrum(&new)).enumerate() {
+                assert!((a - b).abs() < 0.01, "{:?} harmonic {}: {} vs {}", algorithm, h + 1, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_pan() {
+        let render = |note: u8, amount: f32| {
+            let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+            manager.set_key_pan_amount(amount);
+            manager.note_on(note, 1.0);
+            let (mut left, mut right) = (0.0, 0.0);
+            for _ in 0..4410 {
+                let (l, r) = manager.tick_stereo();
+                left += l * l;
+                right += r * r;
+            }
+            (left, right)
+        };
+
+        let (left, right) = render(36, 1.0);
+        assert!(left > right * 1.5, "low note: {} {}", left, right);
+        let (left, right) = render(84, 1.0);
+        assert!(right > left * 1.5, "high note: {} {}", left, right);
+        let (left, right) = render(36, 0.0);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_velocity_to_vibrato() {
+        // Pitch swing of OP1 over one vibrato cycle
+        let swing = |velocity: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_vibrato_depth(50.0);
+            manager.set_velocity_to_vibrato(1.0);
+            manager.note_on(69, velocity);
+            let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+            for _ in 0..44100 / 5 {
+                manager.tick();
+                let inc = manager.voices[0].operators[0].oscillator.phase_increment;
+                lo = lo.min(inc);
+                hi = hi.max(inc);
             }
-        }
+            hi / lo
+        };
+        assert!(swing(1.0) > swing(0.3) * 1.01, "{} {}", swing(1.0), swing(0.3));
+        assert!(swing(0.3) > 1.0);
     }
 
-    pub fn set_op_release(&mut self, op_index: usize, release: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].envelope.release = release.max(0.001);
+    #[test]
+    fn test_pitch_bend_center_and_extremes() {
+        // 14-bit center 8192 as a normalized host value
+        assert_eq!(pitch_bend_from_normalized(8192.0 / 16383.0), 0.0);
+        assert_eq!(pitch_bend_from_normalized(0.0), -1.0);
+        assert_eq!(pitch_bend_from_normalized(1.0), 1.0);
+
+        let ratio = |value: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.note_on(69, 1.0);
+            manager.tick();
+            let unbent = manager.voices[0].operators[0].oscillator.phase_increment;
+            manager.set_pitch_bend(pitch_bend_from_normalized(value));
+            // Ramps in over 5 ms
+            manager.tick();
+            let first = manager.voices[0].operators[0].oscillator.phase_increment;
+            for _ in 0..441 {
+                manager.tick();
             }
-        }
+            let settled = manager.voices[0].operators[0].oscillator.phase_increment;
+            if value != 8192.0 / 16383.0 {
+                assert!((first / unbent - 1.0).abs() < (settled / unbent - 1.0).abs());
+            }
+            settled / unbent
+        };
+        assert_eq!(ratio(8192.0 / 16383.0), 1.0);
+        let up = 2.0_f32.powf(2.0 / 12.0);
+        assert!((ratio(1.0) - up).abs() < 1e-4, "{}", ratio(1.0));
+        assert!((ratio(0.0) - 1.0 / up).abs() < 1e-4, "{}", ratio(0.0));
     }
 
-    pub fn set_op_feedback(&mut self, op_index: usize, feedback: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].feedback = feedback.clamp(0.0, 1.0);
+    #[test]
+    fn test_op_hpf_thins_low_modulator() {
+        // OP2 at 55 Hz modulating a 440 Hz OP1 (algorithm 1, OP3-6 silent):
+        // its sidebands are everything off the 440 Hz series
+        let render = |hpf: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            for op in 2..6 {
+                manager.set_op_level(op, 0.0);
             }
+            manager.set_op_ratio(1, 0.125);
+            manager.set_op_level(1, 0.3);
+            manager.set_op_sustain(0, 1.0);
+            manager.set_op_sustain(1, 1.0);
+            manager.set_op_hpf(1, hpf);
+            manager.note_on(69, 1.0);
+            for _ in 0..4410 {
+                manager.tick();
+            }
+            let buffer: Vec<f32> = (0..8820).map(|_| manager.tick()).collect();
+            alias_ratio(&buffer, 440.0, 44100.0)
+        };
+        let dry = render(0.0);
+        let thinned = render(2000.0);
+        assert!(dry > 0.05, "{}", dry);
+        assert!(thinned < dry * 0.1, "{} vs {}", thinned, dry);
+    }
+
+    #[test]
+    fn test_fm_unison() {
+        let mut manager = Fm6OpVoiceManager::new(8, 44100.0);
+        manager.set_unison_voices(3);
+        manager.set_unison_detune(20.0);
+        manager.note_on(69, 1.0);
+        let mut cents: Vec<f32> = manager
+            .voices
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| 1200.0 * (v.operators[0].oscillator.frequency / 440.0).log2())
+            .collect();
+        cents.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(cents.len(), 3);
+        for (c, expected) in cents.iter().zip([-10.0, 0.0, 10.0]) {
+            assert!((c - expected).abs() < 0.01, "{:?}", cents);
         }
+        manager.note_off(69);
+        assert!(manager.voices.iter().all(|v| !v.is_active() || v.is_released()));
+
+        // Stereo energy over one second
+        let render = |unison: usize, width: f32| {
+            let mut manager = Fm6OpVoiceManager::new(8, 44100.0);
+            manager.set_unison_voices(unison);
+            manager.set_unison_detune(50.0);
+            manager.set_unison_width(width);
+            manager.note_on(69, 1.0);
+            let (mut energy, mut mono) = (0.0, true);
+            for _ in 0..44100 {
+                let (left, right) = manager.tick_stereo();
+                energy += left * left + right * right;
+                mono &= left == right;
+            }
+            (energy, mono)
+        };
+        let (single, _) = render(1, 1.0);
+        let (wide, wide_mono) = render(3, 1.0);
+        let (narrow, narrow_mono) = render(3, 0.0);
+        assert!(!wide_mono);
+        assert!(narrow_mono);
+        assert!((narrow / wide - 1.0).abs() < 0.1, "{} vs {}", narrow, wide);
+        assert!((narrow / single - 1.0).abs() < 0.15, "{} vs {}", narrow, single);
     }
 
-    pub fn set_op_velocity_sens(&mut self, op_index: usize, sens: f32) {
-        if op_index < 6 {
-            for voice in &mut self.voices {
-                voice.operators[op_index].velocity_sens = sens.clamp(0.0, 1.0);
+    #[test]
+    fn test_vibrato_rate_ramps() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_vibrato_depth(30.0);
+        manager.set_vibrato_rate(2.0);
+        manager.note_on(60, 1.0);
+        manager.tick();
+        assert_eq!(manager.vibrato_lfo.frequency, 2.0);
+
+        // 20 ms default ramp: halfway after 10 ms, at the target after 20 ms
+        manager.set_vibrato_rate(10.0);
+        manager.tick();
+        let first = manager.vibrato_lfo.frequency;
+        assert!(first > 2.0 && first < 2.1, "{}", first);
+        for _ in 0..440 {
+            manager.tick();
+        }
+        let halfway = manager.vibrato_lfo.frequency;
+        assert!((halfway - 6.0).abs() < 0.1, "{}", halfway);
+        for _ in 0..441 {
+            manager.tick();
+        }
+        assert_eq!(manager.vibrato_lfo.frequency, 10.0);
+
+        // Configurable, 0 jumps straight to the target
+        manager.set_vibrato_smoothing_ms(0.0);
+        manager.set_vibrato_rate(3.0);
+        manager.tick();
+        assert_eq!(manager.vibrato_lfo.frequency, 3.0);
+    }
+
+    #[test]
+    fn test_4op_high_pass_filter() {
+        // A low sustained sine (modulators silent): high-pass at 2 kHz leaves little of it
+        let render = |filter_type: Option<FilterType>| {
+            let mut manager = Fm4OpVoiceManager::new(1, 44100.0);
+            manager.set_op_sustain(0, 1.0);
+            for op in 1..4 {
+                manager.set_op_level(op, 0.0);
+            }
+            if let Some(filter_type) = filter_type {
+                manager.set_filter_enabled(true);
+                manager.set_filter_type(filter_type);
+                manager.set_filter_slope(FilterSlope::Pole2);
+                manager.set_filter_cutoff(2000.0);
+            }
+            manager.note_on(36, 1.0);
+            for _ in 0..4410 {
+                manager.tick();
+            }
+            let sum: f32 = (0..4410).map(|_| manager.tick().powi(2)).sum();
+            (sum / 4410.0).sqrt()
+        };
+        let dry = render(None);
+        let low = render(Some(FilterType::LowPass));
+        let high = render(Some(FilterType::HighPass));
+        assert!(dry > 0.01, "{}", dry);
+        assert!(low > dry * 0.5, "{} vs {}", low, dry);
+        assert!(high < dry * 0.2, "{} vs {}", high, dry);
+    }
+
+    #[test]
+    fn test_level_curve_db() {
+        let peak = |level: f32, curve: LevelCurve| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            for op in 1..6 {
+                manager.set_op_level(op, 0.0);
+            }
+            manager.set_op_sustain(0, 1.0);
+            manager.set_op_level(0, level);
+            manager.set_op_level_curve(0, curve);
+            manager.note_on(69, 1.0);
+            for _ in 0..4410 {
+                manager.tick();
+            }
+            (0..4410).map(|_| manager.tick().abs()).fold(0.0, f32::max)
+        };
+        let full = peak(1.0, LevelCurve::Linear);
+        assert!((peak(1.0, LevelCurve::Db) - full).abs() < 1e-4);
+        assert!((peak(0.5, LevelCurve::Linear) / full - 0.5).abs() < 0.01);
+
+        // Halfway down the dB scale is half the range in dB, not half the amplitude
+        let db = 20.0 * (peak(0.5, LevelCurve::Db) / full).log10();
+        assert!((db + LEVEL_CURVE_RANGE_DB / 2.0).abs() < 0.1, "{} dB", db);
+        assert_eq!(LevelCurve::Db.gain(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_solo_operator() {
+        // OP2 at ratio 3 is a modulator in algorithm 1 and a carrier in 32
+        for algorithm in [Dx7Algorithm::Algo1, Dx7Algorithm::Algo32] {
+            assert_eq!(algorithm.carriers().contains(&1), algorithm == Dx7Algorithm::Algo32);
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_algorithm(algorithm);
+            manager.set_op_ratio(1, 3.0);
+            manager.set_op_sustain(1, 1.0);
+            manager.set_op_feedback(5, 0.5);
+            manager.set_solo_operator(Some(1));
+            manager.note_on(45, 1.0);
+            for _ in 0..4410 {
+                manager.tick();
             }
+            let buffer: Vec<f32> = (0..8820).map(|_| manager.tick()).collect();
+            assert!(buffer.iter().any(|s| s.abs() > 0.01), "{:?}", algorithm);
+            // Everything sits at 330 Hz (3 x 110 Hz) and its harmonics: a clean sine
+            assert!(alias_ratio(&buffer, 330.0, 44100.0) < 1e-3, "{:?}", algorithm);
         }
+
+        // None restores the algorithm
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_solo_operator(Some(1));
+        manager.set_solo_operator(None);
+        assert!(manager.voices.iter().all(|v| v.solo_operator.is_none()));
     }
 
-    pub fn set_filter_enabled(&mut self, enabled: bool) {
-        for voice in &mut self.voices {
-            voice.filter_enabled = enabled;
+    #[test]
+    fn test_unison_phase_decorrelation() {
+        // Identical sine copies (no detune, centred) so only the start phase differs
+        let render = |random_phase: bool| {
+            let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+            manager.set_unison_voices(4);
+            manager.set_unison_detune(0.0);
+            manager.set_unison_width(0.0);
+            manager.set_unison_random_phase(random_phase);
+            for op in 1..6 {
+                manager.set_op_level(op, 0.0);
+            }
+            manager.note_on(57, 1.0);
+            let phases: Vec<f32> = manager.voices.iter().map(|v| v.operators[0].oscillator.phase).collect();
+            let peak = (0..2205).map(|_| manager.tick().abs()).fold(0.0, f32::max);
+            (phases, peak)
+        };
+        let (coherent_phases, coherent_peak) = render(false);
+        assert!(coherent_phases.iter().all(|&p| p == 0.0));
+
+        let (phases, peak) = render(true);
+        for (i, a) in phases.iter().enumerate() {
+            assert!(phases[i + 1..].iter().all(|b| (a - b).abs() > 1e-3), "{:?}", phases);
         }
+        assert!(peak < coherent_peak * 0.9, "{} vs {}", peak, coherent_peak);
     }
 
-    pub fn set_filter_cutoff(&mut self, cutoff: f32) {
-        for voice in &mut self.voices {
-            voice.filter_cutoff = cutoff.clamp(20.0, 20000.0);
+    #[test]
+    fn test_op_am_sensitivity() {
+        // OP1 and OP3 are both carriers in algorithm 32; only OP1 is AM-sensitive
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_algorithm(Dx7Algorithm::Algo32);
+        manager.set_vibrato_rate(5.0);
+        manager.set_am_depth(1.0);
+        manager.set_op_am_sens(0, 3);
+        for op in 0..6 {
+            manager.set_op_sustain(op, 1.0);
+        }
+        manager.note_on(69, 1.0);
+        for _ in 0..4410 {
+            manager.tick();
         }
+
+        // Per-operator gain over one LFO cycle (200 ms at 5 Hz)
+        let (mut sensitive, mut steady) = (Vec::new(), Vec::new());
+        for _ in 0..8820 {
+            manager.tick();
+            sensitive.push(manager.voices[0].operators[0].am_gain);
+            steady.push(manager.voices[0].operators[2].am_gain);
+        }
+        let range = |gains: &[f32]| {
+            let max = gains.iter().cloned().fold(f32::MIN, f32::max);
+            let min = gains.iter().cloned().fold(f32::MAX, f32::min);
+            (min, max)
+        };
+        let (min, max) = range(&sensitive);
+        assert!(min < 0.05 && max > 0.95, "{} - {}", min, max);
+        assert_eq!(range(&steady), (1.0, 1.0));
     }
 
-    pub fn set_filter_resonance(&mut self, resonance: f32) {
-        for voice in &mut self.voices {
-            voice.filter_resonance = resonance.clamp(0.0, 1.0);
+    #[test]
+    fn test_6op_voice_ages_follow_note_on_order() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        for note in [48, 52, 55] {
+            manager.note_on(note, 1.0);
+            for _ in 0..50 {
+                manager.tick();
+            }
         }
+
+        let mut states = manager.voice_states();
+        states.sort_by_key(|s| std::cmp::Reverse(s.age));
+        let notes: Vec<u8> = states.iter().map(|s| s.note).collect();
+        assert_eq!(notes, vec![48, 52, 55]);
+        assert!(states.windows(2).all(|w| w[0].age > w[1].age));
     }
 
-    pub fn set_vibrato_depth(&mut self, depth: f32) {
-        self.vibrato_depth = depth.clamp(0.0, 100.0);
+    #[test]
+    fn test_vibrato_depth_raise_is_continuous() {
+        // One manager has vibrato on throughout, the other raises it from zero mid-note
+        let mut always = Fm6OpVoiceManager::new(1, 44100.0);
+        let mut raised = Fm6OpVoiceManager::new(1, 44100.0);
+        for manager in [&mut always, &mut raised] {
+            manager.set_vibrato_smoothing_ms(0.0);
+            manager.set_vibrato_rate(5.0);
+            manager.note_on(60, 1.0);
+        }
+        always.set_vibrato_depth(50.0);
+
+        for _ in 0..3000 {
+            always.tick();
+            raised.tick();
+        }
+        raised.set_vibrato_depth(50.0);
+
+        // The LFO kept its phase at zero depth, so both follow the same curve
+        let mut previous = always.tick_vibrato().0;
+        assert_eq!(raised.tick_vibrato().0, previous);
+        for _ in 0..4410 {
+            let cents = raised.tick_vibrato().0;
+            assert_eq!(cents, always.tick_vibrato().0);
+            assert!((cents - previous).abs() < 0.1, "jump {} -> {}", previous, cents);
+            previous = cents;
+        }
     }
 
-    pub fn set_vibrato_rate(&mut self, rate: f32) {
-        self.vibrato_lfo.set_frequency(rate.clamp(0.1, 20.0));
+    #[test]
+    fn test_choke_fades_previous_note() {
+        let fade = (CHOKE_FADE_MS * 0.001 * 44100.0) as usize;
+        let previous_note_after_next = |choke: bool| {
+            let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+            manager.set_choke(choke);
+            for op in 0..6 {
+                manager.set_op_sustain(op, 1.0);
+            }
+            manager.note_on(60, 1.0);
+            for _ in 0..2000 {
+                manager.tick();
+            }
+            manager.note_on(67, 1.0);
+
+            // Render the first voice alone through the fade
+            let mut peak_late = 0.0f32;
+            for i in 0..fade + 10 {
+                let out = manager.voices[0].tick().abs();
+                if i > fade * 9 / 10 {
+                    peak_late = peak_late.max(out);
+                }
+            }
+            (peak_late, manager.voices[0].is_active(), manager.voices[1].note())
+        };
+
+        let (choked_peak, choked_active, new_note) = previous_note_after_next(true);
+        let (open_peak, open_active, _) = previous_note_after_next(false);
+        assert!(choked_peak < open_peak * 0.15, "choked {} vs open {}", choked_peak, open_peak);
+        assert!(!choked_active);
+        assert_eq!(new_note, 67);
+        assert!(open_active);
     }
 
-    pub fn set_master_volume(&mut self, volume: f32) {
-        self.master_volume = volume.clamp(0.0, 1.0);
+    #[test]
+    fn test_detune_mode_frequency() {
+        assert_eq!(DetuneMode::Absolute.frequency(440.0, 2.0, 0.0), 880.0);
+        // +1200 cents doubles the whole product, or adds one to the ratio
+        assert!((DetuneMode::Absolute.frequency(440.0, 3.0, 1200.0) - 2640.0).abs() < 1e-2);
+        assert!((DetuneMode::Proportional.frequency(440.0, 3.0, 1200.0) - 1760.0).abs() < 1e-2);
+        // 100 cents on A4 at ratio 0.5 lands on A#3
+        let freq = DetuneMode::Absolute.frequency(440.0, 0.5, 100.0);
+        assert!((freq - 233.08).abs() < 0.01, "{}", freq);
     }
 
-    // Debug getters
-    pub fn get_op_level(&self, op_index: usize) -> f32 {
-        if op_index < 6 && !self.voices.is_empty() {
-            self.voices[0].operators[op_index].level
-        } else {
-            0.0
+    #[test]
+    fn test_operator_drift() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.note_on(60, 1.0);
+        for _ in 0..4410 {
+            manager.tick();
+        }
+        assert!(manager.voices[0].operators.iter().all(|op| op.oscillator.drift == 1.0));
+
+        // Each operator wanders on its own, never beyond the depth
+        manager.set_operator_drift(5.0);
+        let bound = (2.0_f32).powf(5.0 / 1200.0);
+        let mut min = [f32::MAX; 6];
+        let mut max = [f32::MIN; 6];
+        for _ in 0..3 * 44100 {
+            manager.tick();
+            for (op, o) in manager.voices[0].operators.iter().enumerate() {
+                let drift = o.oscillator.drift;
+                assert!(drift <= bound && drift >= 1.0 / bound, "op {} drift {}", op, drift);
+                min[op] = min[op].min(drift);
+                max[op] = max[op].max(drift);
+            }
         }
+        for op in 0..6 {
+            assert!(max[op] - min[op] > 0.0005, "op {} barely moved", op);
+        }
+        let drifts: Vec<f32> = manager.voices[0].operators.iter().map(|o| o.oscillator.drift).collect();
+        assert!(drifts.windows(2).all(|pair| pair[0] != pair[1]), "{:?}", drifts);
+
+        // Turning it off snaps back to exact pitch
+        manager.set_operator_drift(0.0);
+        manager.tick();
+        assert!(manager.voices[0].operators.iter().all(|op| op.oscillator.drift == 1.0));
     }
 
-    pub fn get_op_ratio(&self, op_index: usize) -> f32 {
-        if op_index < 6 && !self.voices.is_empty() {
-            self.voices[0].operators[op_index].ratio
-        } else {
-            1.0
+    #[test]
+    fn test_tuning_shifts_base_frequency() {
+        let op_freqs = |tuning: Tuning| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_op_ratio(1, 2.0);
+            manager.set_op_ratio(2, 3.5);
+            manager.set_tuning(tuning);
+            manager.note_on(57, 1.0);
+            let ops = &manager.voices[0].operators;
+            [ops[0].oscillator.frequency, ops[1].oscillator.frequency, ops[2].oscillator.frequency]
+        };
+
+        let standard = op_freqs(Tuning::default());
+        assert!((standard[0] - 220.0).abs() < 1e-3);
+
+        // +12 doubles every operator, 432 Hz scales them all; ratios survive both
+        let up = op_freqs(Tuning::default().with_transpose(12));
+        let low_a = op_freqs(Tuning::default().with_reference(432.0));
+        for op in 0..3 {
+            assert!((up[op] / standard[op] - 2.0).abs() < 1e-5, "op {} {}", op, up[op]);
+            assert!((low_a[op] / standard[op] - 432.0 / 440.0).abs() < 1e-5, "op {} {}", op, low_a[op]);
+        }
+        for freqs in [up, low_a] {
+            assert!((freqs[1] / freqs[0] - 2.0).abs() < 1e-5);
+            assert!((freqs[2] / freqs[0] - 3.5).abs() < 1e-5);
         }
     }
 
-    pub fn get_algorithm(&self) -> u8 {
-        if self.voices.is_empty() {
-            0
-        } else {
-            self.voices[0].algorithm as u8
+    #[test]
+    fn test_4op_vibrato_off_restores_exact_pitch() {
+        let sample_rate = 44100.0;
+        let mut manager = Fm4OpVoiceManager::new(1, sample_rate);
+        manager.note_on(57, 1.0);
+        manager.tick();
+        let increments = |manager: &Fm4OpVoiceManager| {
+            manager.voices[0].operators.iter().map(|op| op.oscillator.phase_increment).collect::<Vec<f32>>()
+        };
+        let original = increments(&manager);
+
+        manager.set_vibrato_depth(50.0);
+        for _ in 0..sample_rate as usize {
+            manager.tick();
         }
-    }
-}
+        assert_ne!(increments(&manager), original);
 
-// Legacy 2-op FM for backwards compatibility
-/// FM Algorithm types (simplified for 2-op)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum FmAlgorithm2Op {
-    /// Op2 -> Op1 (classic 2-op FM)
-    Stack,
-    /// Op1 + Op2 (parallel, additive)
-    Parallel,
-    /// Op2 -> Op1, with Op2 feedback
-    StackFeedback,
-}
+        // With the depth ramped back to zero the stored frequency is untouched
+        manager.set_vibrato_depth(0.0);
+        for _ in 0..4410 {
+            manager.tick();
+        }
+        assert_eq!(increments(&manager), original);
+    }
 
-impl Default for FmAlgorithm2Op {
-    fn default() -> Self {
-        Self::Stack
+    #[test]
+    fn test_load_dx7_voice() {
+        let mut voice = Dx7VoiceData { algorithm: 4, feedback: 7, ..Default::default() };
+        voice.operators[0].coarse = 2;
+        voice.operators[0].output_level = 99;
+        voice.operators[0].rates = [99; 4];
+        voice.operators[0].levels = [99; 4];
+        voice.operators[1].coarse = 14;
+        voice.operators[1].output_level = 0;
+
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.load_dx7_voice(&voice);
+        let params = manager.snapshot();
+        assert_eq!(params.algorithm, Dx7Algorithm::Algo5);
+        assert_eq!(params.operators[0].ratio, 2.0);
+        assert_eq!(params.operators[1].ratio, 14.0);
+        assert_eq!(params.operators[0].level, 1.0);
+        assert_eq!(params.operators[1].level, 0.0);
+        assert_eq!(params.operators[0].level_curve, LevelCurve::Db);
+        // Algorithm 5 feeds back on OP6 only
+        assert_eq!(params.operators[5].feedback, 1.0);
+        assert_eq!(params.operators[0].feedback, 0.0);
+
+        // A loaded voice still plays
+        manager.note_on(60, 1.0);
+        let mut buffer = vec![0.0; 2205];
+        manager.process(&mut buffer);
+        assert!(buffer.iter().any(|s| s.abs() > 0.01));
     }
-}
 
-/// 2-Operator FM Synthesizer (legacy)
-#[derive(Debug, Clone)]
-pub struct FmSynth {
-    pub carrier: FmOscillator,
-    pub modulator: FmOscillator,
-    pub mod_ratio: f32,
-    pub mod_depth: f32,
-    pub feedback: f32,
-    pub algorithm: FmAlgorithm2Op,
-    feedback_sample: f32,
-    sample_rate: f32,
-}
+    #[test]
+    fn test_fm_synth_stereo_spread() {
+        let render = |spread: f32| {
+            let mut synth = FmSynth::new(44100.0);
+            synth.mod_depth = 0.5;
+            synth.stereo_spread = spread;
+            (0..4410).map(|_| synth.tick_stereo(220.0)).collect::<Vec<(f32, f32)>>()
+        };
 
-impl FmSynth {
-    pub fn new(sample_rate: f32) -> Self {
-        Self {
-            carrier: FmOscillator::new(sample_rate),
-            modulator: FmOscillator::new(sample_rate),
-            mod_ratio: 1.0,
-            mod_depth: 0.0,
-            feedback: 0.0,
-            algorithm: FmAlgorithm2Op::default(),
-            feedback_sample: 0.0,
-            sample_rate,
+        // No spread: both channels match the mono output exactly
+        let mut mono = FmSynth::new(44100.0);
+        mono.mod_depth = 0.5;
+        for (left, right) in render(0.0) {
+            assert_eq!(left, right);
+            assert_eq!(left, mono.tick(220.0));
         }
-    }
 
-    pub fn set_sample_rate(&mut self, sample_rate: f32) {
-        self.sample_rate = sample_rate;
-        self.carrier.set_sample_rate(sample_rate);
-        self.modulator.set_sample_rate(sample_rate);
+        let spread = render(10.0);
+        let difference: f32 = spread.iter().map(|(l, r)| (l - r).abs()).sum();
+        assert!(difference > 10.0, "{}", difference);
     }
 
-    pub fn tick(&mut self, frequency: f32) -> f32 {
-        self.carrier.set_frequency(frequency);
-        self.modulator.set_frequency(frequency * self.mod_ratio);
-
-        match self.algorithm {
-            FmAlgorithm2Op::Stack => {
-                let mod_out = self.modulator.tick(0.0);
-                let phase_mod = mod_out * self.mod_depth * PI;
-                self.carrier.tick(phase_mod)
-            }
-            FmAlgorithm2Op::Parallel => {
-                let carrier_out = self.carrier.tick(0.0);
-                let mod_out = self.modulator.tick(0.0);
-                (carrier_out + mod_out * self.mod_depth) * 0.5
+    #[test]
+    fn test_dx7_routing_carriers_are_summed() {
+        for value in 0..32 {
+            let algorithm = Dx7Algorithm::from_u8(value);
+            let routing = algorithm.routing();
+            for op in 0..6 {
+                let modulates = routing.edges.iter().any(|&(modulator, _)| modulator == op);
+                assert_eq!(routing.carriers.contains(&op), !modulates, "algorithm {}", value + 1);
             }
-            FmAlgorithm2Op::StackFeedback => {
-                let feedback_mod = self.feedback_sample * self.feedback * PI;
-                let mod_out = self.modulator.tick(feedback_mod);
-                self.feedback_sample = mod_out;
-                let phase_mod = mod_out * self.mod_depth * PI;
-                self.carrier.tick(phase_mod)
+            assert!(routing.edges.iter().all(|&(modulator, target)| modulator > target));
+
+            // Only a carrier is heard when it is the sole operator with any level
+            for solo in 0..6 {
+                let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+                manager.set_algorithm(algorithm);
+                for op in 0..6 {
+                    manager.set_op_level(op, if op == solo { 1.0 } else { 0.0 });
+                    manager.set_op_sustain(op, 1.0);
+                }
+                manager.note_on(60, 1.0);
+                let mut buffer = vec![0.0; 512];
+                manager.process(&mut buffer);
+                let audible = buffer.iter().any(|s| s.abs() > 1e-4);
+                assert_eq!(audible, routing.carriers.contains(&solo), "algorithm {} op {}", value + 1, solo + 1);
             }
         }
     }
 
-    pub fn reset(&mut self) {
-        self.carrier.reset();
-        self.modulator.reset();
-        self.feedback_sample = 0.0;
+    #[test]
+    fn test_master_volume_db() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_master_volume_db(0.0);
+        assert_eq!(manager.snapshot().master_volume, 1.0);
+        manager.set_master_volume_db(-6.0);
+        assert!((manager.snapshot().master_volume - 0.5).abs() < 0.005);
+        manager.set_master_volume_db(12.0);
+        assert_eq!(manager.snapshot().master_volume, 1.0);
+        manager.set_master_volume_db(f32::NEG_INFINITY);
+        assert_eq!(manager.snapshot().master_volume, 0.0);
+
+        let mut synth = crate::synth::Synth::new(44100.0, 1);
+        synth.set_master_volume_db(-6.0);
+        assert!((synth.params().master_volume - 0.5).abs() < 0.005);
+
+        // A change mid-note ramps instead of stepping, then settles at the new gain
+        let render = |db: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_master_volume_db(0.0);
+            manager.note_on(69, 1.0);
+            let mut buffer = vec![0.0; 8820];
+            manager.process(&mut buffer[..4410]);
+            manager.set_master_volume_db(db);
+            manager.process(&mut buffer[4410..]);
+            buffer
+        };
+        let open = render(0.0);
+        let quiet = render(-40.0);
+        let gain = |i: usize| quiet[i] / open[i];
+        let index = |from: usize| (from..8820).find(|&i| open[i].abs() > 0.01).unwrap();
+        assert!(gain(index(4411)) > 0.5, "{}", gain(index(4411)));
+        assert!((gain(index(6000)) - 0.01).abs() < 1e-4, "{}", gain(index(6000)));
     }
-}
 
-// Re-export for backwards compatibility
-pub type Fm4OpSynth = Fm4OpVoice;
+    #[test]
+    fn test_op_fixed_freq_ignores_note() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_op_fixed_freq(1, Some(100.0));
+        let frequencies = |manager: &Fm6OpVoiceManager| {
+            let ops = &manager.voices[0].operators;
+            (ops[0].oscillator.frequency, ops[1].oscillator.frequency)
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        manager.note_on(48, 1.0);
+        let (low, fixed) = frequencies(&manager);
+        assert_eq!(fixed, 100.0);
+        manager.note_off(48);
+        manager.note_on(72, 1.0);
+        let (high, fixed_again) = frequencies(&manager);
+        assert_eq!(fixed_again, 100.0);
+        assert!(high > low * 3.9);
+
+        manager.set_op_fixed_freq(1, None);
+        manager.note_on(72, 1.0);
+        let ratio = manager.snapshot().operators[1].ratio / manager.snapshot().operators[0].ratio;
+        assert!((frequencies(&manager).1 - high * ratio).abs() < 1e-3);
+    }
 
     #[test]
-    fn test_fm_operator() {
-        let mut op = FmOperator::new(44100.0);
-        op.set_note_frequency(440.0);
-        op.trigger(1.0);
-
-        let mut samples = Vec::new();
-        for _ in 0..1000 {
-            samples.push(op.tick(0.0));
+    fn test_pitch_envelope() {
+        let sample_rate = 44100.0;
+        let mut manager = Fm6OpVoiceManager::new(1, sample_rate);
+        manager.set_pitch_env_depth(12.0);
+        manager.set_pitch_env_adsr(0.001, 0.05, 0.0, 0.1);
+        manager.set_vibrato_depth(20.0);
+        manager.note_on(57, 1.0);
+        let frequency = |manager: &Fm6OpVoiceManager| manager.voices[0].operators[0].oscillator.phase_increment * sample_rate;
+
+        // Peak of the attack: an octave up (give or take the vibrato)
+        for _ in 0..45 {
+            manager.tick();
         }
+        let start = frequency(&manager);
+        for _ in 0..22050 {
+            manager.tick();
+        }
+        let sustain = frequency(&manager);
+        assert!((start / sustain - 2.0).abs() < 0.05, "{} vs {}", start, sustain);
+        // Vibrato still rides on the settled pitch
+        assert!((sustain / 220.0 - 1.0).abs() < 0.02, "{}", sustain);
+    }
 
-        assert!(samples.iter().all(|s| s.is_finite()));
-        assert!(samples.iter().any(|s| *s != 0.0));
+    #[test]
+    fn test_panic_fades_out() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_op_attack(0, 0.001);
+        manager.set_op_sustain(0, 1.0);
+        manager.note_on(69, 1.0);
+        let mut buffer = vec![0.0; 4410];
+        manager.process(&mut buffer);
+        let sustain_peak = buffer.iter().fold(0.0_f32, |a, s| a.max(s.abs()));
+
+        // 5 ms default fade: the first cycle after panic is still sounding, the last one
+        // is nearly silent, then the voices are stopped
+        manager.panic();
+        let fade = (PANIC_FADE_MS * 0.001 * 44100.0) as usize;
+        let mut buffer = vec![0.0; fade + 100];
+        manager.process(&mut buffer);
+        let early = buffer[..100].iter().fold(0.0_f32, |a, s| a.max(s.abs()));
+        let late = buffer[fade - 20..fade].iter().fold(0.0_f32, |a, s| a.max(s.abs()));
+        assert!(early > sustain_peak * 0.5, "{} vs {}", early, sustain_peak);
+        assert!(late < sustain_peak * 0.1, "{} vs {}", late, sustain_peak);
+        assert!(buffer[fade..].iter().all(|s| *s == 0.0));
+        assert_eq!(manager.active_voice_count(), 0);
+
+        // Instant mode still cuts immediately
+        manager.set_panic_fade_ms(0.0);
+        manager.note_on(69, 1.0);
+        manager.panic();
+        assert_eq!(manager.active_voice_count(), 0);
     }
 
     #[test]
-    fn test_fm_4op_voice() {
-        let mut voice = Fm4OpVoice::new(44100.0);
-        voice.note_on(60, 0.8);
+    fn test_op_level_scaling() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_op_level(1, 0.5);
+        manager.set_op_level_scaling(1, 60, 0.0, 6.0);
+
+        manager.note_on(36, 1.0);
+        let low = manager.voices[0].operators[1].effective_level();
+        manager.panic_now();
+        manager.note_on(84, 1.0);
+        let high = manager.voices[0].operators[1].effective_level();
+
+        // Flat below the breakpoint, +6 dB per octave above it, scaled by the level knob
+        assert!((low - 0.5).abs() < 1e-6, "{}", low);
+        assert!((high - 0.5 * db_to_gain(12.0)).abs() < 1e-4, "{}", high);
+    }
 
-        let mut samples = Vec::new();
-        for _ in 0..1000 {
-            samples.push(voice.tick());
+    #[test]
+    fn test_op_rate_scaling() {
+        let samples_to_sustain = |note: u8| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            manager.set_op_attack(0, 0.05);
+            manager.set_op_decay(0, 0.05);
+            manager.set_op_sustain(0, 0.5);
+            manager.set_op_rate_scaling(0, 1.0);
+            manager.note_on(note, 1.0);
+            let mut samples = 0;
+            while manager.voices[0].operators[0].envelope.stage() != EnvelopeStage::Sustain {
+                manager.tick();
+                samples += 1;
+            }
+            samples
+        };
+        let (middle, octave_up) = (samples_to_sustain(60), samples_to_sustain(72));
+        // 2^(-12/24): an octave up runs at about 71% of the time
+        let ratio = octave_up as f32 / middle as f32;
+        assert!((ratio - 0.707).abs() < 0.01, "{} vs {}", octave_up, middle);
+    }
+
+    #[test]
+    fn test_dispersion_keeps_spectrum() {
+        let render = |dispersion: f32| {
+            let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+            for op in 0..6 {
+                manager.set_op_ratio(op, (op + 1) as f32);
+                manager.set_op_attack(op, 0.001);
+                manager.set_op_sustain(op, 1.0);
+            }
+            manager.set_dispersion(dispersion);
+            manager.note_on(57, 1.0);
+            let mut buffer = vec![0.0; 44100];
+            manager.process(&mut buffer);
+            buffer
+        };
+        let (dry, wet) = (render(0.0), render(1.0));
+
+        // The waveform (and its attack) changes...
+        let diff = dry[..2000].iter().zip(&wet[..2000]).fold(0.0_f32, |a, (d, w)| a.max((d - w).abs()));
+        assert!(diff > 0.05, "{}", diff);
+
+        // ...but the steady-state magnitude spectrum does not
+        let dry_spectrum = crate::analysis::spectrum_of(&dry[22050..], 44100.0, 48);
+        let wet_spectrum = crate::analysis::spectrum_of(&wet[22050..], 44100.0, 48);
+        for (d, w) in dry_spectrum.iter().zip(&wet_spectrum) {
+            assert!((d - w).abs() < 0.02 + 0.05 * d, "{} vs {}", d, w);
         }
+    }
 
-        assert!(samples.iter().all(|s| s.is_finite()));
-        assert!(voice.is_active());
+    #[test]
+    fn test_note_during_panic_fade_survives() {
+        let mut manager = Fm6OpVoiceManager::new(4, 44100.0);
+        manager.set_op_sustain(0, 1.0);
+        manager.note_on(60, 1.0);
+        let mut buffer = vec![0.0; 441];
+        manager.process(&mut buffer);
+
+        // A note played mid-fade keeps sounding after the fade stops the old one
+        manager.panic();
+        manager.process(&mut buffer[..50]);
+        manager.note_on(64, 1.0);
+        let mut buffer = vec![0.0; 44100];
+        manager.process(&mut buffer);
+
+        let states = manager.voice_states();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].note, 64);
+        assert!(buffer[44000..].iter().any(|s| s.abs() > 1e-3));
     }
 
     #[test]
-    fn test_all_algorithms() {
-        for algo_idx in 0..8 {
-            let mut voice = Fm4OpVoice::new(44100.0);
-            voice.algorithm = FmAlgorithm::from_u8(algo_idx);
-            voice.note_on(60, 1.0);
+    fn test_vibrato_tempo_sync() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.set_vibrato_rate(1.0);
+        manager.set_vibrato_waveform(LfoWaveform::SampleAndHold);
+        manager.set_vibrato_sync(Some(NoteDivision::Eighth), Some(120.0));
+        manager.note_on(60, 1.0);
+        manager.tick();
+        // One S&H step per eighth at 120 BPM
+        assert_eq!(manager.vibrato_lfo.frequency, 4.0);
+
+        // No tempo from the host: back to the free rate
+        manager.set_vibrato_sync(Some(NoteDivision::Eighth), None);
+        manager.tick();
+        assert_eq!(manager.vibrato_lfo.frequency, 1.0);
+    }
+
+    #[test]
+    fn test_fm4op_steals_released_voice_first() {
+        let mut manager = Fm4OpVoiceManager::new(2, 44100.0);
+        manager.note_on(60, 1.0);
+        manager.note_on(62, 1.0);
+        manager.note_off(62);
+        manager.note_on(64, 1.0);
+        let mut notes: Vec<u8> = manager.voice_states().iter().map(|s| s.note).collect();
+        notes.sort();
+        assert_eq!(notes, [60, 64]);
+    }
 
+    #[test]
+    fn test_fm6op_steal_order() {
+        let mut manager = Fm6OpVoiceManager::new(3, 44100.0);
+        let play = |manager: &mut Fm6OpVoiceManager, note: u8, release: bool| {
+            manager.note_on(note, 1.0);
+            if release {
+                manager.note_off(note);
+            }
             for _ in 0..100 {
-                let sample = voice.tick();
-                assert!(sample.is_finite(), "Algorithm {} produced NaN", algo_idx);
+                manager.tick();
             }
+        };
+        let notes = |manager: &Fm6OpVoiceManager| {
+            let mut notes: Vec<u8> = manager.voice_states().iter().map(|s| s.note).collect();
+            notes.sort();
+            notes
+        };
+
+        play(&mut manager, 60, false);
+        play(&mut manager, 62, true);
+        manager.set_sustain_pedal(true);
+        play(&mut manager, 64, true);
+
+        // Released first, then the pedal-held note, and the oldest held key last
+        play(&mut manager, 65, false);
+        assert_eq!(notes(&manager), [60, 64, 65]);
+        play(&mut manager, 67, false);
+        assert_eq!(notes(&manager), [60, 65, 67]);
+        play(&mut manager, 69, false);
+        assert_eq!(notes(&manager), [65, 67, 69]);
+    }
+
+    #[test]
+    fn test_fm6op_steals_released_voice_with_idle_op1() {
+        let mut manager = Fm6OpVoiceManager::new(2, 44100.0);
+        manager.set_algorithm(Dx7Algorithm::Algo32);
+        for op in 0..6 {
+            manager.set_op_attack(op, 0.001);
+        }
+        // OP1 finishes its release long before OP2
+        manager.set_op_release(0, 0.005);
+        manager.set_op_release(1, 5.0);
+
+        manager.note_on(60, 1.0);
+        manager.note_on(62, 1.0);
+        for _ in 0..441 {
+            manager.tick();
         }
+        manager.note_off(62);
+        for _ in 0..4410 {
+            manager.tick();
+        }
+        let released = manager.voices.iter().find(|v| v.note() == 62).unwrap();
+        assert!(released.is_active());
+        assert_eq!(released.operators[0].envelope.stage(), EnvelopeStage::Idle);
+        assert!(released.is_released());
+
+        // The released note goes before the older held one
+        manager.note_on(64, 1.0);
+        let mut notes: Vec<u8> = manager.voice_states().iter().map(|s| s.note).collect();
+        notes.sort();
+        assert_eq!(notes, [60, 64]);
+    }
+
+    #[test]
+    fn test_meter_mono_process() {
+        let mut manager = Fm6OpVoiceManager::new(1, 44100.0);
+        manager.note_on(60, 1.0);
+        let mut buffer = vec![0.0; 512];
+        manager.process(&mut buffer);
+        let peak = buffer.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.0);
+        assert_eq!(manager.meter().peak(), peak);
+        assert!(manager.meter().rms() > 0.0);
     }
 }
@@ -0,0 +1,291 @@
+//! DX7 SysEx voice import: single voices (VCED, 155 bytes) and 32-voice banks (VMEM, 32 x 128 bytes)
+
+use std::fmt;
+
+use super::{Dx7Algorithm, Fm6OpParams, LevelCurve};
+
+/// Unpacked single-voice data length
+pub const VOICE_SIZE: usize = 155;
+/// Packed voice length inside a bank
+pub const PACKED_VOICE_SIZE: usize = 128;
+/// Voices in a bank
+pub const BANK_VOICES: usize = 32;
+
+/// Bytes per operator in the unpacked format
+const OP_SIZE: usize = 21;
+/// Bytes per operator in the packed format
+const PACKED_OP_SIZE: usize = 17;
+/// SysEx wrapper: F0 43 0n (format) (count MSB) (count LSB) ... checksum F7
+const SYSEX_OVERHEAD: usize = 8;
+const FORMAT_VOICE: u8 = 0;
+const FORMAT_BANK: u8 = 9;
+
+/// Envelope time at rate 0 and rate 99 (seconds), interpolated exponentially in between
+const RATE_SLOWEST_SECONDS: f32 = 38.0;
+const RATE_FASTEST_SECONDS: f32 = 0.003;
+/// Approximate pitch change per detune step (detune is 0-14, 7 = centered)
+const DETUNE_CENTS_PER_STEP: f32 = 1.0;
+
+/// Why SysEx data could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SysexError {
+    /// Neither raw data nor a SysEx message of the expected size
+    Length { expected: usize, found: usize },
+    /// Not a Yamaha message of the expected format
+    Header,
+    /// Checksum byte does not match the data
+    Checksum,
+}
+
+impl fmt::Display for SysexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length { expected, found } => write!(f, "expected {} data bytes, found {}", expected, found),
+            Self::Header => write!(f, "not a DX7 voice SysEx message"),
+            Self::Checksum => write!(f, "SysEx checksum mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for SysexError {}
+
+/// One DX7 operator, in the DX7's own 0-99 (etc.) units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dx7OperatorData {
+    pub rates: [u8; 4],
+    pub levels: [u8; 4],
+    pub output_level: u8,
+    /// false = ratio, true = fixed frequency
+    pub fixed: bool,
+    pub coarse: u8,
+    pub fine: u8,
+    /// 0-14, 7 = centered
+    pub detune: u8,
+    /// Key velocity sensitivity (0-7)
+    pub velocity_sens: u8,
+    /// Amplitude modulation sensitivity (0-3)
+    pub am_sens: u8,
+}
+
+impl Dx7OperatorData {
+    /// Frequency ratio in ratio mode (coarse 0 = 0.5)
+    pub fn ratio(&self) -> f32 {
+        let coarse = if self.coarse == 0 { 0.5 } else { self.coarse as f32 };
+        coarse * (1.0 + self.fine as f32 / 100.0)
+    }
+
+    /// Fixed-mode (coarse decade, fine multiplier)
+    pub fn fixed_coarse_fine(&self) -> (f32, f32) {
+        let coarse = 10.0_f32.powi((self.coarse & 3) as i32);
+        let fine = 10.0_f32.powf(self.fine as f32 / 100.0);
+        (coarse, fine)
+    }
+
+    fn unpack(packed: &[u8]) -> Self {
+        Self {
+            rates: [packed[0], packed[1], packed[2], packed[3]],
+            levels: [packed[4], packed[5], packed[6], packed[7]],
+            am_sens: packed[13] & 0x03,
+            velocity_sens: (packed[13] >> 2) & 0x07,
+            output_level: packed[14],
+            fixed: packed[15] & 0x01 != 0,
+            coarse: (packed[15] >> 1) & 0x1F,
+            fine: packed[16],
+            detune: (packed[12] >> 3) & 0x0F,
+        }
+    }
+
+    fn from_unpacked(data: &[u8]) -> Self {
+        Self {
+            rates: [data[0], data[1], data[2], data[3]],
+            levels: [data[4], data[5], data[6], data[7]],
+            am_sens: data[14] & 0x03,
+            velocity_sens: data[15] & 0x07,
+            output_level: data[16],
+            fixed: data[17] & 0x01 != 0,
+            coarse: data[18] & 0x1F,
+            fine: data[19],
+            detune: data[20] & 0x0F,
+        }
+    }
+}
+
+/// A decoded DX7 voice
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Dx7VoiceData {
+    /// Operators in OP1..OP6 order (the SysEx stores them OP6 first)
+    pub operators: [Dx7OperatorData; 6],
+    /// 0-31
+    pub algorithm: u8,
+    /// 0-7
+    pub feedback: u8,
+    /// Transpose in semitones relative to C3 (24 in the SysEx)
+    pub transpose: i8,
+    pub name: String,
+}
+
+impl Dx7VoiceData {
+    /// Decode the voice-wide fields that follow the operators
+    fn with_common(operators: [Dx7OperatorData; 6], algorithm: u8, feedback: u8, transpose: u8, name: &[u8]) -> Self {
+        Self {
+            operators,
+            algorithm: algorithm & 0x1F,
+            feedback: feedback & 0x07,
+            transpose: transpose.min(48) as i8 - 24,
+            name: name
+                .iter()
+                .map(|&c| if (32..127).contains(&c) { c as char } else { ' ' })
+                .collect::<String>()
+                .trim_end()
+                .to_string(),
+        }
+    }
+
+    fn from_unpacked(data: &[u8]) -> Self {
+        let operators = std::array::from_fn(|op| Dx7OperatorData::from_unpacked(&data[(5 - op) * OP_SIZE..]));
+        Self::with_common(operators, data[134], data[135], data[144], &data[145..155])
+    }
+
+    fn unpack(packed: &[u8]) -> Self {
+        let operators = std::array::from_fn(|op| Dx7OperatorData::unpack(&packed[(5 - op) * PACKED_OP_SIZE..]));
+        Self::with_common(operators, packed[110], packed[111], packed[117], &packed[118..128])
+    }
+
+    /// Write this voice's algorithm and operator settings into a patch
+    pub fn apply_to(&self, params: &mut Fm6OpParams) {
+        params.algorithm = Dx7Algorithm::from_u8(self.algorithm.min(31));
+        let feedback_op = params.algorithm.feedback_operator();
+        for (index, (op, dx7)) in params.operators.iter_mut().zip(self.operators.iter()).enumerate() {
+            if dx7.fixed {
+                let (coarse, fine) = dx7.fixed_coarse_fine();
+                op.fixed_coarse = coarse;
+                op.fixed_fine = fine;
+                op.fixed_freq = Some(coarse * fine);
+            } else {
+                op.ratio = dx7.ratio();
+                op.fixed_freq = None;
+            }
+            op.detune = (dx7.detune.min(14) as f32 - 7.0) * DETUNE_CENTS_PER_STEP;
+            op.level = dx7.output_level.min(99) as f32 / 99.0;
+            op.level_curve = LevelCurve::Db;
+            op.velocity_sens = dx7.velocity_sens.min(7) as f32 / 7.0;
+            op.am_sens = dx7.am_sens.min(3);
+            op.feedback = if index == feedback_op { self.feedback.min(7) as f32 / 7.0 } else { 0.0 };
+            op.attack = rate_seconds(dx7.rates[0]);
+            op.decay = rate_seconds(dx7.rates[1]) + rate_seconds(dx7.rates[2]);
+            op.sustain = LevelCurve::Db.gain(dx7.levels[2].min(99) as f32 / 99.0);
+            op.release = rate_seconds(dx7.rates[3]);
+        }
+    }
+}
+
+/// Rough time a DX7 envelope segment takes at a 0-99 rate
+fn rate_seconds(rate: u8) -> f32 {
+    let t = rate.min(99) as f32 / 99.0;
+    RATE_SLOWEST_SECONDS * (RATE_FASTEST_SECONDS / RATE_SLOWEST_SECONDS).powf(t)
+}
+
+/// Strip the SysEx wrapper (if any) and return the data bytes
+fn payload(bytes: &[u8], format: u8, size: usize) -> Result<&[u8], SysexError> {
+    if bytes.len() == size {
+        return Ok(bytes);
+    }
+    if bytes.len() != size + SYSEX_OVERHEAD {
+        return Err(SysexError::Length { expected: size, found: bytes.len() });
+    }
+    if bytes[0] != 0xF0 || bytes[1] != 0x43 || bytes[2] & 0xF0 != 0 || bytes[3] != format || bytes[size + 7] != 0xF7 {
+        return Err(SysexError::Header);
+    }
+    let data = &bytes[6..6 + size];
+    let sum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    if sum.wrapping_neg() & 0x7F != bytes[size + 6] {
+        return Err(SysexError::Checksum);
+    }
+    Ok(data)
+}
+
+/// Decode a single voice: 155 raw bytes or the 163-byte SysEx message
+pub fn parse_voice(bytes: &[u8]) -> Result<Dx7VoiceData, SysexError> {
+    payload(bytes, FORMAT_VOICE, VOICE_SIZE).map(Dx7VoiceData::from_unpacked)
+}
+
+/// Decode a 32-voice bank: 4096 raw bytes or the 4104-byte SysEx message
+pub fn parse_bank(bytes: &[u8]) -> Result<Vec<Dx7VoiceData>, SysexError> {
+    let data = payload(bytes, FORMAT_BANK, BANK_VOICES * PACKED_VOICE_SIZE)?;
+    Ok(data.chunks_exact(PACKED_VOICE_SIZE).map(Dx7VoiceData::unpack).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(format: u8, data: &[u8]) -> Vec<u8> {
+        let sum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        let mut bytes = vec![0xF0, 0x43, 0x00, format, (data.len() >> 7) as u8, (data.len() & 0x7F) as u8];
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&[sum.wrapping_neg() & 0x7F, 0xF7]);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_voice() {
+        let mut data = [0u8; VOICE_SIZE];
+        // OP1 is stored last: coarse 2, fine 50 -> ratio 3
+        data[5 * OP_SIZE + 18] = 2;
+        data[5 * OP_SIZE + 19] = 50;
+        data[5 * OP_SIZE + 16] = 99;
+        // OP6 is stored first: coarse 0 -> ratio 0.5
+        data[16] = 80;
+        data[134] = 21;
+        data[135] = 7;
+        data[144] = 24;
+        data[145..155].copy_from_slice(b"E.PIANO 1 ");
+
+        let voice = parse_voice(&data).unwrap();
+        assert_eq!(voice.algorithm, 21);
+        assert_eq!(voice.feedback, 7);
+        assert_eq!(voice.transpose, 0);
+        assert_eq!(voice.name, "E.PIANO 1");
+        assert_eq!(voice.operators[0].ratio(), 3.0);
+        assert_eq!(voice.operators[0].output_level, 99);
+        assert_eq!(voice.operators[5].ratio(), 0.5);
+        assert_eq!(voice.operators[5].output_level, 80);
+
+        assert_eq!(parse_voice(&wrap(FORMAT_VOICE, &data)).unwrap(), voice);
+        let mut corrupt = wrap(FORMAT_VOICE, &data);
+        corrupt[10] ^= 1;
+        assert_eq!(parse_voice(&corrupt), Err(SysexError::Checksum));
+        assert_eq!(parse_voice(&wrap(FORMAT_BANK, &data)), Err(SysexError::Header));
+        assert!(matches!(parse_voice(&data[..100]), Err(SysexError::Length { .. })));
+    }
+
+    #[test]
+    fn test_parse_bank() {
+        let mut data = vec![0u8; BANK_VOICES * PACKED_VOICE_SIZE];
+        let voice = &mut data[3 * PACKED_VOICE_SIZE..4 * PACKED_VOICE_SIZE];
+        // OP2 (second from the end): fixed mode, coarse 2 (100 Hz), detune +3, AMS 2, KVS 5
+        let op2 = 4 * PACKED_OP_SIZE;
+        voice[op2 + 12] = 10 << 3;
+        voice[op2 + 13] = 2 | (5 << 2);
+        voice[op2 + 15] = 1 | (2 << 1);
+        // OP1: coarse 1, fine 41 -> ratio 1.41
+        voice[5 * PACKED_OP_SIZE + 15] = 1 << 1;
+        voice[5 * PACKED_OP_SIZE + 16] = 41;
+        voice[110] = 31;
+        voice[111] = 5 | (1 << 3);
+        voice[117] = 36;
+
+        let bank = parse_bank(&wrap(FORMAT_BANK, &data)).unwrap();
+        assert_eq!(bank.len(), BANK_VOICES);
+        let voice = &bank[3];
+        assert_eq!(voice.algorithm, 31);
+        assert_eq!(voice.feedback, 5);
+        assert_eq!(voice.transpose, 12);
+        assert!((voice.operators[0].ratio() - 1.41).abs() < 1e-6);
+        let op2 = voice.operators[1];
+        assert!(op2.fixed);
+        assert_eq!((op2.detune, op2.am_sens, op2.velocity_sens), (10, 2, 5));
+        assert_eq!(op2.fixed_coarse_fine(), (100.0, 1.0));
+        assert_eq!(bank[0], Dx7VoiceData { transpose: -24, ..Default::default() });
+    }
+}
@@ -2,7 +2,11 @@ use std::f32::consts::PI;
 
 use serde::{Deserialize, Serialize};
 
+use crate::voice::offset_seed;
+
 const TWO_PI: f32 = 2.0 * PI;
+/// Initial state of the sample-and-hold random generator
+const LFO_SEED: u32 = 12345;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LfoWaveform {
@@ -35,6 +39,7 @@ pub struct Lfo {
 
     // Random state for S&H
     random_state: u32,
+    random_seed: u32,
 }
 
 impl Lfo {
@@ -47,7 +52,8 @@ impl Lfo {
             phase_increment: 0.0,
             sh_value: 0.0,
             sh_trigger: false,
-            random_state: 12345,
+            random_state: LFO_SEED,
+            random_seed: LFO_SEED,
         };
         lfo.update_phase_increment();
         lfo
@@ -72,6 +78,18 @@ impl Lfo {
         self.sh_trigger = false;
     }
 
+    /// Restart the sample-and-hold random sequence from its initial seed
+    pub fn reseed(&mut self) {
+        self.random_state = self.random_seed;
+        self.sh_value = 0.0;
+    }
+
+    /// Offset the sample-and-hold seed by `instance_seed` and restart from it
+    pub fn set_instance_seed(&mut self, instance_seed: u32) {
+        self.random_seed = offset_seed(LFO_SEED, instance_seed);
+        self.reseed();
+    }
+
     /// Sync LFO to tempo (beats per minute)
     pub fn sync_to_tempo(&mut self, bpm: f32, division: f32) {
         // division: 1.0 = quarter note, 0.5 = eighth, 2.0 = half, etc.
@@ -141,6 +159,7 @@ impl Lfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tempo::NoteDivision;
 
     #[test]
     fn test_lfo_range() {
@@ -173,4 +192,28 @@ mod tests {
         lfo.sync_to_tempo(120.0, 0.5); // Eighth note = 4 Hz
         assert!((lfo.frequency - 4.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_sample_and_hold_tempo_sync() {
+        let sample_rate = 44100.0;
+        let mut lfo = Lfo::new(sample_rate);
+        lfo.waveform = LfoWaveform::SampleAndHold;
+        lfo.sync_to_tempo(120.0, NoteDivision::Eighth.beats());
+
+        // A new value every 250 ms (an eighth at 120 BPM)
+        let mut changes = Vec::new();
+        let mut last = f32::NAN;
+        for i in 0..(1.1 * sample_rate) as usize {
+            let value = lfo.tick();
+            if value != last {
+                changes.push(i);
+                last = value;
+            }
+        }
+        assert_eq!(changes.len(), 5, "{:?}", changes);
+        for step in changes.windows(2) {
+            let ms = (step[1] - step[0]) as f32 / sample_rate * 1000.0;
+            assert!((ms - 250.0).abs() < 0.1, "{} ms", ms);
+        }
+    }
 }
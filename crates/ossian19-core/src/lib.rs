@@ -5,26 +5,55 @@
 //! - Filters (Moog-style ladder, State Variable)
 //! - Envelopes (ADSR)
 //! - LFOs for modulation
+//! - Parameter smoothing
+//! - Output dynamics (auto-level compressor)
+//! - Output metering (peak, RMS, clip)
+//! - Spectrum snapshots for editor previews
+//! - Tempo-relative note divisions
+//! - Global tuning (reference, transpose, microtuning)
+//! - Configurable MIDI CC mapping
+//! - A/B compare of parameter sets
 //! - FM Synthesis (2-op and 4-op)
+//! - DX7 SysEx voice and bank import
 //! - Polyphonic voice management
+//! - Parameter schema for generic editors
 //! - Main synth engine
 
+pub mod analysis;
+pub mod compare;
+pub mod effects;
 pub mod envelope;
 pub mod filter;
 pub mod fm;
 pub mod lfo;
+pub mod meter;
+pub mod midi;
 pub mod oscillator;
+pub mod schema;
+pub mod smoother;
 pub mod synth;
+pub mod tempo;
+pub mod tuning;
 pub mod voice;
 
 // Re-export main types
-pub use envelope::Envelope;
-pub use filter::{FilterType, FilterSlope, LadderFilter, StateVariableFilter};
+pub use analysis::{bin_frequency, spectrum_of};
+pub use compare::{AbCompare, CompareSlot, ParamSnapshot};
+pub use effects::{Compressor, DcBlocker, Dispersion, LoFi};
+pub use envelope::{Envelope, EnvelopeCurve, EnvelopeLoop};
+pub use filter::{FilterType, FilterSlope, FilterRouting, LadderFilter, LadderMode, OnePoleHighPass, StateVariableFilter};
 pub use fm::{
     FmSynth, Fm4OpSynth, Fm4OpVoice, Fm4OpVoiceManager, FmAlgorithm, FmOperator,
-    Fm6OpVoice, Fm6OpVoiceManager, Dx7Algorithm,
+    Fm6OpVoice, Fm6OpVoiceManager, Dx7Algorithm, Fm6OpParams, FmOperatorParams, DetuneMode, LevelCurve,
+    AdditiveMode,
 };
 pub use lfo::{Lfo, LfoWaveform};
-pub use oscillator::{Oscillator, Waveform, SubWaveform};
-pub use synth::{Synth, SynthParams};
-pub use voice::{Voice, VoiceManager, freq_to_midi, midi_to_freq};
+pub use meter::OutputMeter;
+pub use midi::{CcDestination, CcMap, CcOverrides};
+pub use oscillator::{Oscillator, Quality, Waveform, SubWaveform};
+pub use schema::{describe, Engine, ParamDescriptor};
+pub use smoother::{PanicFade, Smoother};
+pub use synth::{resonance_knob, Synth, SynthParams};
+pub use tempo::NoteDivision;
+pub use tuning::Tuning;
+pub use voice::{Voice, VoiceManager, VoiceState, db_to_gain, freq_to_midi, key_pan, midi_to_freq, next_instance_seed, pan_gains, pitch_bend_from_normalized};
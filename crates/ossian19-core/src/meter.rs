@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Output level of the last rendered block, readable from another thread
+#[derive(Debug, Default)]
+pub struct OutputMeter {
+    /// f32 bits of the block peak
+    peak: AtomicU32,
+    /// f32 bits of the block RMS
+    rms: AtomicU32,
+    /// Set when a sample exceeded ±1, cleared by `take_clip`
+    clipped: AtomicBool,
+}
+
+impl OutputMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Measure a rendered block (an empty `right` for mono output)
+    pub fn update(&self, left: &[f32], right: &[f32]) {
+        let mut peak: f32 = 0.0;
+        let mut sum = 0.0;
+        for &sample in left.iter().chain(right) {
+            peak = peak.max(sample.abs());
+            sum += sample * sample;
+        }
+        let count = left.len() + right.len();
+        let rms = if count > 0 { (sum / count as f32).sqrt() } else { 0.0 };
+
+        self.peak.store(peak.to_bits(), Ordering::Relaxed);
+        self.rms.store(rms.to_bits(), Ordering::Relaxed);
+        if peak > 1.0 {
+            self.clipped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Peak of the last block
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+
+    /// RMS of the last block
+    pub fn rms(&self) -> f32 {
+        f32::from_bits(self.rms.load(Ordering::Relaxed))
+    }
+
+    /// Whether the output exceeded ±1 since the last call (resets the flag)
+    pub fn take_clip(&self) -> bool {
+        self.clipped.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_block_levels() {
+        let meter = OutputMeter::new();
+        meter.update(&[0.5, -0.5], &[0.5, -0.5]);
+        assert_eq!(meter.peak(), 0.5);
+        assert_eq!(meter.rms(), 0.5);
+        assert!(!meter.take_clip());
+
+        meter.update(&[1.5, 0.0], &[0.0, 0.0]);
+        meter.update(&[0.1, 0.0], &[0.0, 0.0]);
+        // The clip latches across blocks until read
+        assert!(meter.take_clip());
+        assert!(!meter.take_clip());
+    }
+}
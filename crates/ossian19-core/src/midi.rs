@@ -0,0 +1,191 @@
+//! MIDI CC mapping (user-configurable, with MIDI learn)
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Parameter a MIDI CC can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum CcDestination {
+    FilterCutoff = 0,
+    FilterResonance = 1,
+    FilterEnvAmount = 2,
+    AmpAttack = 3,
+    AmpDecay = 4,
+    AmpRelease = 5,
+    VibratoDepth = 6,
+    VibratoRate = 7,
+    MasterVolume = 8,
+    MasterPan = 9,
+}
+
+impl CcDestination {
+    pub const COUNT: usize = 10;
+
+    pub const ALL: [CcDestination; Self::COUNT] = [
+        Self::FilterCutoff,
+        Self::FilterResonance,
+        Self::FilterEnvAmount,
+        Self::AmpAttack,
+        Self::AmpDecay,
+        Self::AmpRelease,
+        Self::VibratoDepth,
+        Self::VibratoRate,
+        Self::MasterVolume,
+        Self::MasterPan,
+    ];
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FilterCutoff => "Cutoff",
+            Self::FilterResonance => "Resonance",
+            Self::FilterEnvAmount => "Filter Env",
+            Self::AmpAttack => "Amp Attack",
+            Self::AmpDecay => "Amp Decay",
+            Self::AmpRelease => "Amp Release",
+            Self::VibratoDepth => "Vibrato Depth",
+            Self::VibratoRate => "Vibrato Rate",
+            Self::MasterVolume => "Volume",
+            Self::MasterPan => "Pan",
+        }
+    }
+
+    /// Engine value for a controller position (0.0 - 1.0)
+    pub fn value(&self, normalized: f32) -> f32 {
+        let n = normalized.clamp(0.0, 1.0);
+        match self {
+            Self::FilterCutoff => 100.0 + n * 19900.0,
+            Self::FilterResonance | Self::FilterEnvAmount | Self::MasterVolume => n,
+            Self::AmpAttack | Self::AmpDecay => n * 2.0,
+            Self::AmpRelease => n * 3.0,
+            Self::VibratoDepth => n * 100.0,
+            Self::VibratoRate => 0.1 + n * 19.9,
+            Self::MasterPan => n * 2.0 - 1.0,
+        }
+    }
+}
+
+/// CC number to destination table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CcMap {
+    mappings: BTreeMap<u8, CcDestination>,
+}
+
+impl Default for CcMap {
+    /// The classic fixed assignments (mod wheel and brightness on cutoff, sound controllers on the envelope)
+    fn default() -> Self {
+        let mut map = Self::empty();
+        map.set(1, CcDestination::FilterCutoff);
+        map.set(74, CcDestination::FilterCutoff);
+        map.set(71, CcDestination::FilterResonance);
+        map.set(73, CcDestination::AmpAttack);
+        map.set(75, CcDestination::AmpDecay);
+        map.set(72, CcDestination::AmpRelease);
+        map
+    }
+}
+
+impl CcMap {
+    /// A map with no assignments
+    pub fn empty() -> Self {
+        Self { mappings: BTreeMap::new() }
+    }
+
+    /// Whether a CC can be mapped (sustain and channel mode messages are reserved)
+    pub fn is_assignable(cc: u8) -> bool {
+        cc < 120 && cc != 64
+    }
+
+    /// Bind a CC to a destination (several CCs may share one); reserved CCs are ignored
+    pub fn set(&mut self, cc: u8, destination: CcDestination) {
+        if Self::is_assignable(cc) {
+            self.mappings.insert(cc, destination);
+        }
+    }
+
+    /// Remove a CC's binding
+    pub fn clear(&mut self, cc: u8) {
+        self.mappings.remove(&cc);
+    }
+
+    /// Remove every CC bound to a destination
+    pub fn clear_destination(&mut self, destination: CcDestination) {
+        self.mappings.retain(|_, d| *d != destination);
+    }
+
+    /// MIDI learn: make `cc` the only controller for `destination`
+    pub fn learn(&mut self, cc: u8, destination: CcDestination) {
+        if Self::is_assignable(cc) {
+            self.clear_destination(destination);
+            self.set(cc, destination);
+        }
+    }
+
+    pub fn get(&self, cc: u8) -> Option<CcDestination> {
+        self.mappings.get(&cc).copied()
+    }
+
+    /// First CC bound to a destination
+    pub fn cc_for(&self, destination: CcDestination) -> Option<u8> {
+        self.mappings.iter().find(|(_, d)| **d == destination).map(|(cc, _)| *cc)
+    }
+}
+
+/// CC-driven values layered over host parameters: each holds until the host
+/// value it replaced changes
+#[derive(Debug, Clone, Default)]
+pub struct CcOverrides {
+    /// (CC value, host value when captured) per destination
+    values: [Option<(f32, f32)>; CcDestination::COUNT],
+}
+
+impl CcOverrides {
+    /// Record a CC value for a destination currently at `host`
+    pub fn capture(&mut self, destination: CcDestination, value: f32, host: f32) {
+        self.values[destination as usize] = Some((value, host));
+    }
+
+    /// Value to use: the CC value while the host hasn't moved, else the host value
+    pub fn resolve(&mut self, destination: CcDestination, host: f32) -> f32 {
+        let slot = &mut self.values[destination as usize];
+        match *slot {
+            Some((value, captured)) if captured == host => value,
+            _ => {
+                *slot = None;
+                host
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.values = [None; CcDestination::COUNT];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learn_and_overrides() {
+        let mut map = CcMap::default();
+        map.learn(20, CcDestination::FilterCutoff);
+        assert_eq!(map.get(20), Some(CcDestination::FilterCutoff));
+        assert_eq!(map.get(1), None);
+        assert_eq!(map.cc_for(CcDestination::FilterCutoff), Some(20));
+        map.learn(64, CcDestination::MasterVolume);
+        assert_eq!(map.get(64), None);
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<CcMap>(&json).unwrap(), map);
+
+        // A CC value holds until the host parameter moves
+        let mut overrides = CcOverrides::default();
+        overrides.capture(CcDestination::MasterVolume, 0.2, 0.7);
+        assert_eq!(overrides.resolve(CcDestination::MasterVolume, 0.7), 0.2);
+        assert_eq!(overrides.resolve(CcDestination::MasterVolume, 0.5), 0.5);
+        assert_eq!(overrides.resolve(CcDestination::MasterVolume, 0.7), 0.7);
+    }
+}
@@ -10,6 +10,8 @@ pub enum Waveform {
     Saw,
     Square,
     Triangle,
+    /// Band-limited impulse train (buzzy/reed). `pulse_width` away from 0.5 widens the pulse.
+    Blit,
 }
 
 impl Default for Waveform {
@@ -31,6 +33,49 @@ impl Default for SubWaveform {
     }
 }
 
+/// Anti-aliasing quality, trading CPU for less aliasing across the engines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[repr(u8)]
+pub enum Quality {
+    /// Naive oscillators, no oversampling (cheapest)
+    Draft = 0,
+    /// PolyBLEP saw/pulse, no oversampling
+    #[default]
+    Normal = 1,
+    /// Adds PolyBLAMP triangle and 2x filter/operator oversampling
+    High = 2,
+    /// PolyBLAMP triangle, 2x filters and 4x operator oversampling
+    Ultra = 3,
+}
+
+impl Quality {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Draft,
+            2 => Self::High,
+            3 => Self::Ultra,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Oversampling factor for FM operators
+    pub fn operator_oversample(&self) -> usize {
+        match self {
+            Self::Draft | Self::Normal => 1,
+            Self::High => 2,
+            Self::Ultra => 4,
+        }
+    }
+
+    /// Oversampling factor for ladder filters
+    pub fn filter_oversample(&self) -> usize {
+        match self {
+            Self::Draft | Self::Normal => 1,
+            Self::High | Self::Ultra => 2,
+        }
+    }
+}
+
 /// Band-limited oscillator using PolyBLEP for anti-aliasing
 #[derive(Debug, Clone)]
 pub struct Oscillator {
@@ -39,6 +84,8 @@ pub struct Oscillator {
     pub detune: f32, // cents
     pub phase: f32,
     pub pulse_width: f32, // 0.0 to 1.0, default 0.5 for square
+    /// Which anti-aliasing corrections are applied
+    pub quality: Quality,
     sample_rate: f32,
     phase_increment: f32,
 }
@@ -51,6 +98,7 @@ impl Oscillator {
             detune: 0.0,
             phase: 0.0,
             pulse_width: 0.5, // Default to square
+            quality: Quality::default(),
             sample_rate,
             phase_increment: 0.0,
         };
@@ -102,26 +150,38 @@ impl Oscillator {
             Waveform::Sine => (modulated_phase * TWO_PI).sin(),
             Waveform::Saw => {
                 let mut s = 2.0 * modulated_phase - 1.0;
-                s -= self.poly_blep_at(modulated_phase);
+                if self.quality > Quality::Draft {
+                    s -= self.poly_blep_at(modulated_phase);
+                }
                 s
             }
             Waveform::Square => {
                 // PWM: use pulse_width instead of fixed 0.5
                 let pw = self.pulse_width;
                 let mut s = if modulated_phase < pw { 1.0 } else { -1.0 };
-                s += self.poly_blep_at(modulated_phase);
-                s -= self.poly_blep_at((modulated_phase + (1.0 - pw)) % 1.0);
+                if self.quality > Quality::Draft {
+                    s += self.poly_blep_at(modulated_phase);
+                    s -= self.poly_blep_at((modulated_phase + (1.0 - pw)) % 1.0);
+                }
                 s
             }
             Waveform::Triangle => {
-                if modulated_phase < 0.25 {
+                let mut s = if modulated_phase < 0.25 {
                     4.0 * modulated_phase
                 } else if modulated_phase < 0.75 {
                     2.0 - 4.0 * modulated_phase
                 } else {
                     4.0 * modulated_phase - 4.0
+                };
+                if self.quality >= Quality::High {
+                    // Slope flips by -8 at the top corner and +8 at the bottom one
+                    let dt = self.phase_increment;
+                    s -= 8.0 * dt * self.poly_blamp_at((modulated_phase + 0.75) % 1.0);
+                    s += 8.0 * dt * self.poly_blamp_at((modulated_phase + 0.25) % 1.0);
                 }
+                s
             }
+            Waveform::Blit => self.blit_at(modulated_phase),
         };
 
         // Advance phase (0.0 to 1.0 range)
@@ -133,6 +193,42 @@ impl Oscillator {
         sample
     }
 
+    /// Band-limited impulse train: equal-amplitude cosine harmonics up to Nyquist,
+    /// summed in closed form (Dirichlet kernel) and normalized to a peak of 1.
+    /// Pulse width away from 0.5 lowers the harmonic limit, widening the pulse.
+    fn blit_at(&self, t: f32) -> f32 {
+        let nyquist_harmonics = (0.5 / self.phase_increment.abs().max(1e-6)).floor().max(1.0);
+        let width = (self.pulse_width - 0.5).abs() * 2.0;
+        let harmonics = if width > 0.0 {
+            nyquist_harmonics.min((1.0 / width).floor().max(1.0))
+        } else {
+            nyquist_harmonics
+        };
+
+        let denom = (PI * t).sin();
+        let sum = if denom.abs() < 1e-6 {
+            harmonics
+        } else {
+            ((2.0 * harmonics + 1.0) * PI * t).sin() / (2.0 * denom) - 0.5
+        };
+        sum / harmonics
+    }
+
+    /// PolyBLAMP (integrated PolyBLEP) for slope discontinuities at phase 0
+    fn poly_blamp_at(&self, t: f32) -> f32 {
+        let dt = self.phase_increment;
+
+        if t < dt {
+            let t = t / dt - 1.0;
+            -t * t * t / 3.0
+        } else if t > 1.0 - dt {
+            let t = (t - 1.0) / dt + 1.0;
+            t * t * t / 3.0
+        } else {
+            0.0
+        }
+    }
+
     /// PolyBLEP at a specific phase (for phase-modulated waveforms)
     fn poly_blep_at(&self, t: f32) -> f32 {
         let dt = self.phase_increment;
@@ -207,6 +303,7 @@ impl Oscillator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::alias_ratio;
 
     #[test]
     fn test_oscillator_basic() {
@@ -233,4 +330,56 @@ mod tests {
         let expected = 880.0 / 44100.0;
         assert!((osc.phase_increment - expected).abs() < 0.0001);
     }
+
+    #[test]
+    fn test_quality_reduces_aliasing() {
+        let render = |waveform: Waveform, quality: Quality| {
+            let mut osc = Oscillator::new(44100.0);
+            osc.waveform = waveform;
+            osc.quality = quality;
+            osc.set_frequency(1230.0);
+            (0..4410).map(|_| osc.tick()).collect::<Vec<f32>>()
+        };
+        let alias = |waveform, quality| alias_ratio(&render(waveform, quality), 1230.0, 44100.0);
+
+        assert!(alias(Waveform::Saw, Quality::Normal) < alias(Waveform::Saw, Quality::Draft) * 0.5);
+        assert!(alias(Waveform::Square, Quality::Normal) < alias(Waveform::Square, Quality::Draft) * 0.5);
+        assert!(alias(Waveform::Triangle, Quality::High) < alias(Waveform::Triangle, Quality::Normal) * 0.5);
+    }
+
+    #[test]
+    fn test_blit_flat_and_band_limited() {
+        // 441 Hz = exactly 100 samples per period
+        let mut osc = Oscillator::new(44100.0);
+        osc.waveform = Waveform::Blit;
+        osc.set_frequency(441.0);
+        let buffer: Vec<f32> = (0..4410).map(|_| osc.tick()).collect();
+        let harmonic = |k: f64| {
+            let w = 2.0 * std::f64::consts::PI * 441.0 * k / 44100.0;
+            let (re, im) = buffer.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, &s)| {
+                (re + s as f64 * (w * i as f64).cos(), im - s as f64 * (w * i as f64).sin())
+            });
+            2.0 * (re * re + im * im).sqrt() / buffer.len() as f64
+        };
+        let first = harmonic(1.0);
+        for k in 2..=49 {
+            let amp = harmonic(k as f64);
+            assert!((amp / first - 1.0).abs() < 0.2, "harmonic {} at {}", k, amp / first);
+        }
+
+        // High notes keep only the harmonics below Nyquist
+        let mut osc = Oscillator::new(44100.0);
+        osc.waveform = Waveform::Blit;
+        osc.set_frequency(5000.0);
+        let buffer: Vec<f32> = (0..4410).map(|_| osc.tick()).collect();
+        assert!(alias_ratio(&buffer, 5000.0, 44100.0) < 1e-3);
+
+        // Moving the pulse width away from 0.5 widens the pulse (fewer harmonics)
+        let mut osc = Oscillator::new(44100.0);
+        osc.waveform = Waveform::Blit;
+        osc.set_pulse_width(0.3);
+        osc.set_frequency(441.0);
+        let peak_width = (0..100).map(|_| osc.tick()).filter(|&s| s > 0.5).count();
+        assert!(peak_width > 1, "{}", peak_width);
+    }
 }
@@ -0,0 +1,148 @@
+//! Machine-readable parameter descriptions for generic editors and automation
+//!
+//! Independent of any plugin framework; ranges match the plugin parameters
+//! and defaults come from the engines themselves.
+
+use serde::Serialize;
+
+use crate::fm::Fm6OpParams;
+use crate::synth::{resonance_knob, SynthParams};
+
+/// Synthesis engine to describe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Subtractive,
+    Fm,
+}
+
+/// One automatable parameter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParamDescriptor {
+    /// Stable identifier (matches the plugin parameter id)
+    pub id: String,
+    /// Display name
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    /// Display unit ("" for plain numbers)
+    pub unit: &'static str,
+}
+
+fn d(id: impl Into<String>, name: impl Into<String>, min: f32, max: f32, default: f32, unit: &'static str) -> ParamDescriptor {
+    ParamDescriptor { id: id.into(), name: name.into(), min, max, default, unit }
+}
+
+/// List every parameter of an engine with its range, default and unit
+pub fn describe(engine: Engine) -> Vec<ParamDescriptor> {
+    match engine {
+        Engine::Subtractive => describe_subtractive(),
+        Engine::Fm => describe_fm(),
+    }
+}
+
+fn describe_subtractive() -> Vec<ParamDescriptor> {
+    let p = SynthParams::default();
+    vec![
+        d("osc1_level", "OSC1 Level", 0.0, 1.0, p.osc1_level, ""),
+        d("osc2_level", "OSC2 Level", 0.0, 1.0, p.osc2_level, ""),
+        d("osc2_detune", "OSC2 Detune", -100.0, 100.0, p.osc2_detune, "cents"),
+        d("sub_level", "Sub Level", 0.0, 1.0, p.sub_level, ""),
+        d("sub_oct", "Sub Octave", -2.0, -1.0, p.sub_octave as f32, "oct"),
+        d("noise", "Noise", 0.0, 1.0, p.noise_level, ""),
+        d("pw", "Pulse Width", 0.01, 0.99, p.pulse_width, ""),
+        d("pwm_depth", "PWM Depth", 0.0, 1.0, p.pwm_depth, ""),
+        d("pwm_rate", "PWM Rate", 0.1, 20.0, p.pwm_rate, "Hz"),
+        d("fm_amt", "FM Amount", 0.0, 1.0, p.fm_amount, ""),
+        d("fm_ratio", "FM Ratio", 0.25, 8.0, p.fm_ratio, ""),
+        d("cutoff", "Cutoff", 20.0, 20000.0, p.filter_cutoff, "Hz"),
+        d("reso", "Resonance", 0.0, 1.0, resonance_knob(p.filter_resonance), ""),
+        d("flt_env", "Filter Env", 0.0, 1.0, p.filter_env_amount, ""),
+        d("hpf", "HPF", 20.0, 2000.0, p.hpf_cutoff, "Hz"),
+        d("flt2_cutoff", "Filter 2 Cutoff", 20.0, 20000.0, p.filter2_cutoff, "Hz"),
+        d("amp_a", "Amp Attack", 0.001, 5.0, p.amp_attack, "s"),
+        d("amp_d", "Amp Decay", 0.001, 5.0, p.amp_decay, "s"),
+        d("amp_s", "Amp Sustain", 0.0, 1.0, p.amp_sustain, ""),
+        d("amp_r", "Amp Release", 0.001, 10.0, p.amp_release, "s"),
+        d("flt_a", "Filter Attack", 0.001, 5.0, p.filter_attack, "s"),
+        d("flt_d", "Filter Decay", 0.001, 5.0, p.filter_decay, "s"),
+        d("flt_s", "Filter Sustain", 0.0, 1.0, p.filter_sustain, ""),
+        d("flt_r", "Filter Release", 0.001, 10.0, p.filter_release, "s"),
+        d("volume", "Volume", 0.0, 1.0, p.master_volume, ""),
+        d("pan", "Pan", -1.0, 1.0, p.master_pan, ""),
+    ]
+}
+
+fn describe_fm() -> Vec<ParamDescriptor> {
+    let p = Fm6OpParams::default();
+    let mut params = vec![d("algorithm", "Algorithm", 0.0, 31.0, p.algorithm as u8 as f32, "")];
+
+    for (i, op) in p.operators.iter().enumerate() {
+        let id = |name: &str| format!("op{}_{}", i + 1, name);
+        let name = |name: &str| format!("OP{} {}", i + 1, name);
+        params.extend([
+            d(id("ratio"), name("Ratio"), 0.125, 16.0, op.ratio, ""),
+            d(id("level"), name("Level"), 0.0, 1.0, op.level, ""),
+            d(id("detune"), name("Detune"), -100.0, 100.0, op.detune, "cents"),
+            d(id("attack"), name("Attack"), 0.001, 5.0, op.attack, "s"),
+            d(id("decay"), name("Decay"), 0.001, 5.0, op.decay, "s"),
+            d(id("sustain"), name("Sustain"), 0.0, 1.0, op.sustain, ""),
+            d(id("release"), name("Release"), 0.001, 10.0, op.release, "s"),
+            d(id("feedback"), name("Feedback"), 0.0, 1.0, op.feedback, ""),
+            d(id("vel_sens"), name("Vel Sens"), 0.0, 1.0, op.velocity_sens, ""),
+            d(id("key_delay"), name("Key Delay"), 0.0, 5.0, op.key_delay, "s"),
+        ]);
+    }
+
+    params.extend([
+        d("flt_on", "Filter", 0.0, 1.0, p.filter_enabled as u8 as f32, ""),
+        d("cutoff", "Cutoff", 20.0, 20000.0, p.filter_cutoff, "Hz"),
+        d("reso", "Resonance", 0.0, 1.0, p.filter_resonance, ""),
+        d("vib_depth", "Vibrato Depth", 0.0, 100.0, p.vibrato_depth, "cents"),
+        d("vib_rate", "Vibrato Rate", 0.1, 20.0, p.vibrato_rate, "Hz"),
+        d("volume", "Volume", 0.0, 1.0, p.master_volume, ""),
+    ]);
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fm_schema_covers_all_operators() {
+        let schema = describe(Engine::Fm);
+        let find = |id: &str| schema.iter().find(|p| p.id == id).unwrap_or_else(|| panic!("missing {}", id));
+
+        for op in 1..=6 {
+            for (name, min, max) in [
+                ("ratio", 0.125, 16.0),
+                ("level", 0.0, 1.0),
+                ("detune", -100.0, 100.0),
+                ("attack", 0.001, 5.0),
+                ("decay", 0.001, 5.0),
+                ("sustain", 0.0, 1.0),
+                ("release", 0.001, 10.0),
+                ("feedback", 0.0, 1.0),
+                ("vel_sens", 0.0, 1.0),
+                ("key_delay", 0.0, 5.0),
+            ] {
+                let param = find(&format!("op{}_{}", op, name));
+                assert_eq!((param.min, param.max), (min, max), "{}", param.id);
+                assert!(param.default >= min && param.default <= max, "{}", param.id);
+            }
+        }
+        assert!(find("algorithm").max == 31.0);
+
+        // Ids are unique and every range is well-formed
+        for (i, param) in schema.iter().enumerate() {
+            assert!(param.min < param.max, "{}", param.id);
+            assert!(schema[i + 1..].iter().all(|p| p.id != param.id), "duplicate {}", param.id);
+        }
+        let sub = describe(Engine::Subtractive);
+        assert!(sub.iter().all(|p| p.default >= p.min && p.default <= p.max));
+        // The resonance knob default matches the plugin's, not the raw engine value
+        let reso = sub.iter().find(|p| p.id == "reso").unwrap();
+        assert!((reso.default - 0.163).abs() < 1e-3, "{}", reso.default);
+    }
+}
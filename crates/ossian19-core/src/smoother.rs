@@ -0,0 +1,167 @@
+/// Linear parameter smoother - ramps to a new target over a fixed time
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: usize,
+    time_ms: f32,
+    sample_rate: f32,
+}
+
+impl Smoother {
+    pub fn new(value: f32, time_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+            time_ms: time_ms.max(0.0),
+            sample_rate,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Set the ramp time in milliseconds (0 = jump immediately)
+    pub fn set_time_ms(&mut self, time_ms: f32) {
+        self.time_ms = time_ms.max(0.0);
+    }
+
+    /// Start ramping towards a new target (no-op if unchanged)
+    pub fn set_target(&mut self, target: f32) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        let samples = (self.time_ms * 0.001 * self.sample_rate) as usize;
+        if samples == 0 {
+            self.current = target;
+            self.remaining = 0;
+        } else {
+            self.step = (target - self.current) / samples as f32;
+            self.remaining = samples;
+        }
+    }
+
+    /// Jump straight to a value without ramping
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.remaining = 0;
+    }
+
+    /// Advance one sample and return the smoothed value
+    #[inline]
+    pub fn tick(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.target
+            } else {
+                self.current + self.step
+            };
+        }
+        self.current
+    }
+
+    /// Current value without advancing
+    pub fn value(&self) -> f32 {
+        self.current
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+/// Default length of the panic fade in milliseconds
+pub(crate) const PANIC_FADE_MS: f32 = 5.0;
+
+/// Linear fade to silence for `panic`: the engine scales the voices that were
+/// sounding at panic time by `gain()` and stops them when `tick` reports the end
+#[derive(Debug, Clone)]
+pub struct PanicFade {
+    time_ms: f32,
+    gain: f32,
+    step: f32,
+}
+
+impl Default for PanicFade {
+    fn default() -> Self {
+        Self {
+            time_ms: PANIC_FADE_MS,
+            gain: 1.0,
+            step: 0.0,
+        }
+    }
+}
+
+impl PanicFade {
+    /// Set the fade length in milliseconds (0 = panic stops at once)
+    pub fn set_time_ms(&mut self, time_ms: f32) {
+        self.time_ms = time_ms.clamp(0.0, 100.0);
+    }
+
+    /// Start fading; false when the fade is 0 and the caller should stop at once.
+    /// A fade already running keeps its pace.
+    pub fn start(&mut self, sample_rate: f32) -> bool {
+        if self.time_ms <= 0.0 {
+            return false;
+        }
+        if self.step == 0.0 {
+            self.step = 1.0 / (self.time_ms * 0.001 * sample_rate).max(1.0);
+        }
+        true
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.step > 0.0
+    }
+
+    /// Gain for the fading voices (1.0 when not fading)
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Advance one sample; true on the sample the fade reaches zero
+    pub fn tick(&mut self) -> bool {
+        if self.step == 0.0 {
+            return false;
+        }
+        self.gain = (self.gain - self.step).max(0.0);
+        self.gain == 0.0
+    }
+
+    /// Drop a pending fade and return to full gain
+    pub fn cancel(&mut self) {
+        self.gain = 1.0;
+        self.step = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smoother_ramp() {
+        let mut smoother = Smoother::new(0.0, 10.0, 1000.0); // 10 samples
+        smoother.set_target(1.0);
+        for _ in 0..5 {
+            smoother.tick();
+        }
+        assert!((smoother.value() - 0.5).abs() < 1e-6);
+        for _ in 0..5 {
+            smoother.tick();
+        }
+        assert_eq!(smoother.value(), 1.0);
+        assert!(!smoother.is_smoothing());
+    }
+}
@@ -1,8 +1,26 @@
 use serde::{Deserialize, Serialize};
 
-use crate::filter::{FilterType, FilterSlope};
-use crate::oscillator::{Waveform, SubWaveform};
-use crate::voice::VoiceManager;
+use crate::effects::{Compressor, DcBlocker, LoFi};
+use crate::envelope::EnvelopeCurve;
+use crate::meter::OutputMeter;
+use crate::midi::{CcDestination, CcMap};
+use crate::filter::{FilterRouting, FilterType, FilterSlope, LadderMode};
+use crate::oscillator::{Quality, Waveform, SubWaveform};
+use crate::smoother::{PanicFade, Smoother};
+use crate::tuning::Tuning;
+use crate::voice::{db_to_gain, key_pan, pan_gains, VoiceManager, VoiceState};
+
+/// Map a resonance knob position (0-1) to engine resonance (0-1).
+/// Quadratic ease-out: more knob travel near self-oscillation.
+pub fn resonance_curve(knob: f32) -> f32 {
+    let k = knob.clamp(0.0, 1.0);
+    1.0 - (1.0 - k) * (1.0 - k)
+}
+
+/// Knob position that `resonance_curve` maps to `resonance` (its inverse)
+pub fn resonance_knob(resonance: f32) -> f32 {
+    1.0 - (1.0 - resonance.clamp(0.0, 1.0)).sqrt()
+}
 
 /// Main synthesizer parameters (serializable for presets)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,9 +43,15 @@ pub struct SynthParams {
     pub sub_level: f32,
     pub sub_waveform: SubWaveform, // Sine or Square
     pub sub_octave: i8,            // -1 or -2
+    #[serde(default)]
+    pub sub_free_phase: bool,      // Sub keeps running across notes
+    #[serde(default = "default_pre_filter")]
+    pub sub_pre_filter: bool,      // false = sub bypasses the filter
 
     // Noise
     pub noise_level: f32,
+    #[serde(default = "default_pre_filter")]
+    pub noise_pre_filter: bool,    // false = noise bypasses the filter
 
     // FM Synthesis
     pub fm_amount: f32,  // 0 = off (subtractive), 1 = full FM
@@ -39,15 +63,29 @@ pub struct SynthParams {
     // Low-pass filter
     pub filter_type: FilterType,
     pub filter_slope: FilterSlope,  // 6/12/24 dB/oct
+    #[serde(default)]
+    pub filter_character: LadderMode, // Vintage or Clean
     pub filter_cutoff: f32,
     pub filter_resonance: f32,
     pub filter_env_amount: f32,
 
+    // Second filter
+    #[serde(default)]
+    pub filter_routing: FilterRouting, // Single, Series or Parallel
+    #[serde(default = "default_filter2_cutoff")]
+    pub filter2_cutoff: f32,
+    #[serde(default = "default_filter2_type")]
+    pub filter2_type: FilterType,
+
     // Amp envelope
+    #[serde(default)]
+    pub amp_delay: f32,
     pub amp_attack: f32,
     pub amp_decay: f32,
     pub amp_sustain: f32,
     pub amp_release: f32,
+    #[serde(default)]
+    pub amp_curve: EnvelopeCurve,
 
     // Filter envelope
     pub filter_attack: f32,
@@ -57,6 +95,20 @@ pub struct SynthParams {
 
     // Master
     pub master_volume: f32,
+    #[serde(default)]
+    pub master_pan: f32, // -1 (left) to 1 (right)
+}
+
+fn default_pre_filter() -> bool {
+    true
+}
+
+fn default_filter2_cutoff() -> f32 {
+    200.0
+}
+
+fn default_filter2_type() -> FilterType {
+    FilterType::HighPass
 }
 
 impl Default for SynthParams {
@@ -75,25 +127,35 @@ impl Default for SynthParams {
             sub_level: 0.0,    // Off by default
             sub_waveform: SubWaveform::Square,
             sub_octave: -1,    // One octave below
+            sub_free_phase: false,
+            sub_pre_filter: true,
             noise_level: 0.0,  // Off by default
+            noise_pre_filter: true,
             fm_amount: 0.0,    // FM off by default (subtractive mode)
             fm_ratio: 2.0,     // Classic 2:1 ratio
             // HPF (Juno-6 style)
             hpf_cutoff: 20.0,  // Essentially off (lowest)
             filter_type: FilterType::LowPass,
             filter_slope: FilterSlope::Pole4,  // 24 dB/oct (classic Moog)
+            filter_character: LadderMode::Vintage,
             filter_cutoff: 5000.0,
             filter_resonance: 0.3,
             filter_env_amount: 0.5,
+            filter_routing: FilterRouting::Single,
+            filter2_cutoff: default_filter2_cutoff(),
+            filter2_type: default_filter2_type(),
+            amp_delay: 0.0,
             amp_attack: 0.01,
             amp_decay: 0.1,
             amp_sustain: 0.7,
             amp_release: 0.3,
+            amp_curve: EnvelopeCurve::Linear,
             filter_attack: 0.01,
             filter_decay: 0.2,
             filter_sustain: 0.3,
             filter_release: 0.3,
             master_volume: 0.7,
+            master_pan: 0.0,
         }
     }
 }
@@ -103,13 +165,49 @@ pub struct Synth {
     voice_manager: VoiceManager,
     params: SynthParams,
     sample_rate: f32,
+    /// Glides the base filter cutoff towards `params.filter_cutoff`
+    cutoff_smoother: Smoother,
+    /// Ramps pitch bend changes (-1 to 1) to avoid zipper steps
+    bend_smoother: Smoother,
+    /// Ramps the applied volume towards `params.master_volume`
+    volume_smoother: Smoother,
+    /// Evens out chord vs single-note levels when enabled
+    compressor: Compressor,
+    auto_level: bool,
+    /// Sum the stereo output to mono on both channels (compatibility check)
+    mono_sum: bool,
+    /// Level of the last `process` or `process_stereo` block
+    meter: OutputMeter,
+    /// Bit crusher / sample-rate reducer on the stereo output
+    lofi: LoFi,
+    /// Which CCs drive which parameters
+    cc_map: CcMap,
+    /// Removes DC from the output unless `dc_coupled` is set
+    dc_blocker: DcBlocker,
+    /// Pass the raw signal, DC included (for CV-style uses)
+    dc_coupled: bool,
+    /// Fade applied by `panic` before the hard reset
+    panic_fade: PanicFade,
 }
 
 impl Synth {
     pub fn new(sample_rate: f32, num_voices: usize) -> Self {
+        let params = SynthParams::default();
         let mut synth = Self {
             voice_manager: VoiceManager::new(num_voices, sample_rate),
-            params: SynthParams::default(),
+            cutoff_smoother: Smoother::new(params.filter_cutoff, 10.0, sample_rate),
+            bend_smoother: Smoother::new(0.0, 5.0, sample_rate),
+            volume_smoother: Smoother::new(params.master_volume, 10.0, sample_rate),
+            compressor: Compressor::new(sample_rate),
+            auto_level: false,
+            mono_sum: false,
+            meter: OutputMeter::new(),
+            lofi: LoFi::new(),
+            cc_map: CcMap::default(),
+            dc_blocker: DcBlocker::new(sample_rate),
+            dc_coupled: false,
+            panic_fade: PanicFade::default(),
+            params,
             sample_rate,
         };
         synth.apply_params();
@@ -119,6 +217,11 @@ impl Synth {
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
         self.voice_manager.set_sample_rate(sample_rate);
+        self.cutoff_smoother.set_sample_rate(sample_rate);
+        self.bend_smoother.set_sample_rate(sample_rate);
+        self.volume_smoother.set_sample_rate(sample_rate);
+        self.compressor.set_sample_rate(sample_rate);
+        self.dc_blocker.set_sample_rate(sample_rate);
     }
 
     /// Get current parameters
@@ -145,18 +248,32 @@ impl Synth {
         self.voice_manager.set_osc1_level(self.params.osc1_level);
         self.voice_manager.set_osc2_level(self.params.osc2_level);
         self.voice_manager.set_sub_level(self.params.sub_level);
+        self.voice_manager.set_sub_octave(self.params.sub_octave);
+        self.voice_manager.set_sub_free_phase(self.params.sub_free_phase);
+        self.voice_manager.set_sub_pre_filter(self.params.sub_pre_filter);
         self.voice_manager.set_noise_level(self.params.noise_level);
+        self.voice_manager.set_noise_pre_filter(self.params.noise_pre_filter);
         self.voice_manager.set_fm_amount(self.params.fm_amount);
         self.voice_manager.set_fm_ratio(self.params.fm_ratio);
+        self.voice_manager.set_hpf_cutoff(self.params.hpf_cutoff);
+        self.voice_manager.set_pulse_width(self.params.pulse_width);
+        self.voice_manager.set_pwm_depth(self.params.pwm_depth);
+        self.voice_manager.set_pwm_rate(self.params.pwm_rate);
         self.voice_manager.set_filter_resonance(self.params.filter_resonance);
         self.voice_manager.set_filter_slope(self.params.filter_slope);
+        self.voice_manager.set_filter_character(self.params.filter_character);
         self.voice_manager.set_filter_env_amount(self.params.filter_env_amount);
+        self.voice_manager.set_filter_routing(self.params.filter_routing);
+        self.voice_manager.set_filter2_cutoff(self.params.filter2_cutoff);
+        self.voice_manager.set_filter2_type(self.params.filter2_type);
         self.voice_manager.set_amp_envelope(
             self.params.amp_attack,
             self.params.amp_decay,
             self.params.amp_sustain,
             self.params.amp_release,
         );
+        self.voice_manager.set_amp_delay(self.params.amp_delay);
+        self.voice_manager.set_amp_curve(self.params.amp_curve);
         self.voice_manager.set_filter_envelope(
             self.params.filter_attack,
             self.params.filter_decay,
@@ -165,10 +282,10 @@ impl Synth {
         );
     }
 
-    /// Handle MIDI note on
-    pub fn note_on(&mut self, note: u8, velocity: u8) {
+    /// Handle MIDI note on (velocity 0 releases the note); returns the voice index
+    pub fn note_on(&mut self, note: u8, velocity: u8) -> Option<usize> {
         let vel = velocity as f32 / 127.0;
-        self.voice_manager.note_on(note, vel);
+        self.voice_manager.note_on(note, vel)
     }
 
     /// Handle MIDI note off
@@ -176,41 +293,66 @@ impl Synth {
         self.voice_manager.note_off(note);
     }
 
-    /// Handle MIDI CC
-    pub fn control_change(&mut self, cc: u8, value: u8) {
-        let normalized = value as f32 / 127.0;
+    /// Start a note at an arbitrary frequency (microtonal); returns an id for `note_off_id`
+    pub fn note_on_freq(&mut self, freq: f32, velocity: u8) -> u32 {
+        self.voice_manager.note_on_freq(freq, velocity as f32 / 127.0)
+    }
+
+    /// Release a note started with `note_on_freq`
+    pub fn note_off_id(&mut self, id: u32) {
+        self.voice_manager.note_off_id(id);
+    }
 
+    /// Handle MIDI CC: sustain and all-notes-off are fixed, the rest go through the CC map
+    pub fn control_change(&mut self, cc: u8, value: u8) {
         match cc {
-            1 => {
-                // Mod wheel -> filter cutoff
-                self.params.filter_cutoff = 100.0 + normalized * 19900.0;
-            }
-            74 => {
-                // Brightness -> filter cutoff
-                self.params.filter_cutoff = 100.0 + normalized * 19900.0;
-            }
-            71 => {
-                // Resonance
-                self.params.filter_resonance = normalized;
-                self.voice_manager.set_filter_resonance(normalized);
-            }
-            73 => {
-                // Attack
-                self.params.amp_attack = normalized * 2.0;
-            }
-            75 => {
-                // Decay
-                self.params.amp_decay = normalized * 2.0;
-            }
-            72 => {
-                // Release
-                self.params.amp_release = normalized * 3.0;
+            64 => self.set_sustain_pedal(value >= 64),
+            123 => self.voice_manager.all_notes_off(),
+            _ => {
+                if let Some(destination) = self.cc_map.get(cc) {
+                    self.apply_cc(destination, value as f32 / 127.0);
+                }
             }
-            123 => {
-                // All notes off
-                self.voice_manager.all_notes_off();
-            }
-            _ => {}
+        }
+    }
+
+    /// Bind a CC to a parameter (replaces the CC's previous binding)
+    pub fn set_cc_mapping(&mut self, cc: u8, destination: CcDestination) {
+        self.cc_map.set(cc, destination);
+    }
+
+    /// Unbind a CC
+    pub fn clear_cc_mapping(&mut self, cc: u8) {
+        self.cc_map.clear(cc);
+    }
+
+    pub fn cc_map(&self) -> &CcMap {
+        &self.cc_map
+    }
+
+    /// Replace the whole CC map (e.g. restoring saved state)
+    pub fn set_cc_map(&mut self, map: CcMap) {
+        self.cc_map = map;
+    }
+
+    fn apply_cc(&mut self, destination: CcDestination, normalized: f32) {
+        self.set_cc_destination(destination, destination.value(normalized));
+    }
+
+    /// Set whatever a CC destination drives to a value in engine units
+    pub fn set_cc_destination(&mut self, destination: CcDestination, value: f32) {
+        let p = &self.params;
+        match destination {
+            CcDestination::FilterCutoff => self.set_filter_cutoff(value),
+            CcDestination::FilterResonance => self.set_filter_resonance(value),
+            CcDestination::FilterEnvAmount => self.set_filter_env_amount(value),
+            CcDestination::AmpAttack => self.set_amp_adsr(value, p.amp_decay, p.amp_sustain, p.amp_release),
+            CcDestination::AmpDecay => self.set_amp_adsr(p.amp_attack, value, p.amp_sustain, p.amp_release),
+            CcDestination::AmpRelease => self.set_amp_adsr(p.amp_attack, p.amp_decay, p.amp_sustain, value),
+            CcDestination::MasterVolume => self.set_master_volume(value),
+            CcDestination::MasterPan => self.set_master_pan(value),
+            // No vibrato on the subtractive engine
+            CcDestination::VibratoDepth | CcDestination::VibratoRate => {}
         }
     }
 
@@ -219,9 +361,82 @@ impl Synth {
         self.voice_manager.all_notes_off();
     }
 
-    /// Panic - immediately stop all sound
+    /// Key split: only notes from `low` to `high` start voices
+    pub fn set_key_range(&mut self, low: u8, high: u8) {
+        self.voice_manager.set_key_range(low, high);
+    }
+
+    /// Latch mode: note-offs are ignored and a second press releases the note
+    pub fn set_latch(&mut self, enabled: bool) {
+        self.voice_manager.set_latch(enabled);
+    }
+
+    /// Sustain pedal: while down, released keys keep sounding until it is lifted
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        self.voice_manager.set_sustain_pedal(down);
+    }
+
+    /// With the pedal down, re-pressing a sounding note stacks a new voice instead of retriggering
+    pub fn set_pedal_stacking(&mut self, enabled: bool) {
+        self.voice_manager.set_pedal_stacking(enabled);
+    }
+
+    /// Reseed all randomness at note_on so renders are bit-stable (for tests)
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.voice_manager.set_deterministic(enabled);
+    }
+
+    /// Offset all randomness so layered instances decorrelate (0 = the fixed default seeds)
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.voice_manager.set_instance_seed(seed);
+    }
+
+    /// Panic - fade the sounding voices to zero over the panic fade, then stop them;
+    /// notes played during the fade are unaffected. Instant when the fade is 0,
+    /// nothing is sounding, or a fade is already running.
     pub fn panic(&mut self) {
+        if self.voice_manager.active_voice_count() == 0
+            || self.panic_fade.is_fading()
+            || !self.panic_fade.start(self.sample_rate)
+        {
+            self.panic_now();
+            return;
+        }
+        for voice in self.voice_manager.voices_mut() {
+            voice.panicking = voice.active;
+        }
+    }
+
+    /// Immediately stop all sound (emergency panic, no fade)
+    pub fn panic_now(&mut self) {
         self.voice_manager.panic();
+        self.dc_blocker.reset();
+        self.panic_fade.cancel();
+    }
+
+    /// Length of the fade `panic` applies before stopping the voices (0 = instant)
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.panic_fade.set_time_ms(ms);
+    }
+
+    /// Stop the voices a finished panic fade has silenced
+    fn finish_panic(&mut self) {
+        for voice in self.voice_manager.voices_mut().iter_mut().filter(|v| v.panicking) {
+            voice.reset();
+        }
+        self.panic_fade.cancel();
+    }
+
+    /// Clear all DSP state (voices, smoothers, effects) without touching the parameters
+    pub fn reset(&mut self) {
+        self.voice_manager.reset();
+        self.panic_fade.cancel();
+        self.cutoff_smoother.reset(self.params.filter_cutoff);
+        self.bend_smoother.reset(0.0);
+        self.volume_smoother.reset(self.params.master_volume);
+        self.compressor.reset();
+        self.lofi.reset();
+        self.dc_blocker.reset();
     }
 
     /// Get number of active voices
@@ -229,34 +444,125 @@ impl Synth {
         self.voice_manager.active_voice_count()
     }
 
+    /// Per-voice note, envelope stage and level for UI display
+    pub fn voice_states(&self) -> Vec<VoiceState> {
+        self.voice_manager.voice_states()
+    }
+
     /// Process a single sample
     pub fn tick(&mut self) -> f32 {
-        let cutoff = self.params.filter_cutoff;
-        let mut output = 0.0;
+        self.render(false).0
+    }
+
+    /// Sum the voices; with `stereo`, each voice is key-panned into (left, right),
+    /// otherwise both channels carry the plain sum
+    fn render(&mut self, stereo: bool) -> (f32, f32) {
+        // Nothing sounding: skip the voice loop entirely
+        if self.voice_manager.active_voice_count() == 0 {
+            self.settle();
+            return (0.0, 0.0);
+        }
+
+        self.cutoff_smoother.set_target(self.params.filter_cutoff);
+        let cutoff = self.cutoff_smoother.tick();
+        if self.bend_smoother.is_smoothing() {
+            self.voice_manager.set_pitch_bend(self.bend_smoother.tick());
+        }
+        let key_pan_amount = if stereo { self.voice_manager.key_pan_amount() } else { 0.0 };
+        let panic_done = self.panic_fade.tick();
+        let panic_gain = self.panic_fade.gain();
+        let (mut left, mut right) = (0.0, 0.0);
 
         for voice in self.voice_manager.voices_mut() {
             if voice.active {
-                output += voice.tick(cutoff);
+                let fade = if voice.panicking { panic_gain } else { 1.0 };
+                let sample = voice.tick(cutoff) * fade;
+                if key_pan_amount == 0.0 {
+                    left += sample;
+                    right += sample;
+                } else {
+                    let (left_gain, right_gain) = pan_gains(key_pan(voice.note, key_pan_amount));
+                    left += sample * left_gain;
+                    right += sample * right_gain;
+                }
             }
         }
 
-        output * self.params.master_volume
+        self.volume_smoother.set_target(self.params.master_volume);
+        if panic_done {
+            self.finish_panic();
+        }
+        let volume = self.volume_smoother.tick();
+        let (left, right) = (left * volume, right * volume);
+        let (left, right) = if self.auto_level {
+            self.compressor.tick_stereo(left, right)
+        } else {
+            (left, right)
+        };
+        if self.dc_coupled {
+            (left, right)
+        } else {
+            self.dc_blocker.tick(left, right)
+        }
+    }
+
+    /// Snap smoothed state while nothing is sounding
+    fn settle(&mut self) {
+        self.cutoff_smoother.reset(self.params.filter_cutoff);
+        self.volume_smoother.reset(self.params.master_volume);
+        if self.bend_smoother.is_smoothing() {
+            let bend = self.bend_smoother.target();
+            self.bend_smoother.reset(bend);
+            self.voice_manager.set_pitch_bend(bend);
+        }
+        self.compressor.reset();
+        self.dc_blocker.reset();
+        self.panic_fade.cancel();
+    }
+
+    /// Process a single stereo sample with master pan applied
+    pub fn tick_stereo(&mut self) -> (f32, f32) {
+        let (left, right) = self.render(true);
+        let (left_gain, right_gain) = pan_gains(self.params.master_pan);
+        let (left, right) = self.lofi.tick(left * left_gain, right * right_gain);
+        if self.mono_sum {
+            let mid = (left + right) * 0.5;
+            (mid, mid)
+        } else {
+            (left, right)
+        }
     }
 
     /// Process a buffer of samples (more efficient)
     pub fn process(&mut self, buffer: &mut [f32]) {
-        for sample in buffer.iter_mut() {
-            *sample = self.tick();
+        if self.voice_manager.active_voice_count() == 0 {
+            self.settle();
+            buffer.fill(0.0);
+        } else {
+            for sample in buffer.iter_mut() {
+                *sample = self.tick();
+            }
         }
+        self.meter.update(buffer, &[]);
     }
 
     /// Process stereo buffer
     pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
-        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
-            let sample = self.tick();
-            *l = sample;
-            *r = sample;
+        if self.voice_manager.active_voice_count() == 0 {
+            self.settle();
+            left.fill(0.0);
+            right.fill(0.0);
+        } else {
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                (*l, *r) = self.tick_stereo();
+            }
         }
+        self.meter.update(left, right);
+    }
+
+    /// Peak/RMS/clip of the last `process` or `process_stereo` block (single ticks aren't metered)
+    pub fn meter(&self) -> &OutputMeter {
+        &self.meter
     }
 
     // Parameter setters for real-time control
@@ -335,6 +641,24 @@ impl Synth {
         self.voice_manager.set_sub_octave(octave);
     }
 
+    /// Let the sub oscillator run freely instead of resetting with osc1/osc2
+    pub fn set_sub_free_phase(&mut self, free: bool) {
+        self.params.sub_free_phase = free;
+        self.voice_manager.set_sub_free_phase(free);
+    }
+
+    /// Route the sub oscillator through the filter (true) or straight to the amp (false)
+    pub fn set_sub_pre_filter(&mut self, pre: bool) {
+        self.params.sub_pre_filter = pre;
+        self.voice_manager.set_sub_pre_filter(pre);
+    }
+
+    /// Route the noise through the filter (true) or straight to the amp (false)
+    pub fn set_noise_pre_filter(&mut self, pre: bool) {
+        self.params.noise_pre_filter = pre;
+        self.voice_manager.set_noise_pre_filter(pre);
+    }
+
     // === Juno-6 style HPF ===
 
     pub fn set_hpf_cutoff(&mut self, cutoff: f32) {
@@ -342,13 +666,25 @@ impl Synth {
         self.voice_manager.set_hpf_cutoff(cutoff);
     }
 
+    /// Set base filter cutoff; voices glide to it over the smoothing time
     pub fn set_filter_cutoff(&mut self, cutoff: f32) {
         self.params.filter_cutoff = cutoff.clamp(20.0, 20000.0);
     }
 
-    pub fn set_filter_resonance(&mut self, resonance: f32) {
-        self.params.filter_resonance = resonance.clamp(0.0, 1.0);
-        self.voice_manager.set_filter_resonance(resonance);
+    /// Set how long cutoff changes take to glide (0 = immediate)
+    pub fn set_cutoff_smoothing_ms(&mut self, ms: f32) {
+        self.cutoff_smoother.set_time_ms(ms.clamp(0.0, 1000.0));
+    }
+
+    /// Base cutoff currently applied to the voices (after smoothing)
+    pub fn current_cutoff(&self) -> f32 {
+        self.cutoff_smoother.value()
+    }
+
+    /// Set resonance from a knob position (0-1), remapped by `resonance_curve`
+    pub fn set_filter_resonance(&mut self, knob: f32) {
+        self.params.filter_resonance = resonance_curve(knob);
+        self.voice_manager.set_filter_resonance(self.params.filter_resonance);
     }
 
     pub fn set_filter_slope(&mut self, slope: FilterSlope) {
@@ -356,11 +692,65 @@ impl Synth {
         self.voice_manager.set_filter_slope(slope);
     }
 
+    /// Set anti-aliasing quality (oscillator BLEP detail and filter oversampling)
+    pub fn set_quality(&mut self, quality: Quality) {
+        self.voice_manager.set_quality(quality);
+    }
+
+    /// Choose Vintage (saturating) or Clean ladder filter character
+    pub fn set_filter_character(&mut self, mode: LadderMode) {
+        self.params.filter_character = mode;
+        self.voice_manager.set_filter_character(mode);
+    }
+
+    /// Choose how the two filters are combined
+    pub fn set_filter_routing(&mut self, routing: FilterRouting) {
+        self.params.filter_routing = routing;
+        self.voice_manager.set_filter_routing(routing);
+    }
+
+    /// Set the second filter's base cutoff in Hz
+    pub fn set_filter2_cutoff(&mut self, cutoff: f32) {
+        self.params.filter2_cutoff = cutoff.clamp(20.0, 20000.0);
+        self.voice_manager.set_filter2_cutoff(cutoff);
+    }
+
+    /// Set the second filter's response (LP/HP/BP)
+    pub fn set_filter2_type(&mut self, filter_type: FilterType) {
+        self.params.filter2_type = filter_type;
+        self.voice_manager.set_filter2_type(filter_type);
+    }
+
     pub fn set_filter_env_amount(&mut self, amount: f32) {
         self.params.filter_env_amount = amount;
         self.voice_manager.set_filter_env_amount(amount);
     }
 
+    /// Freeze the filter envelope at its current level for sweeps (false resumes it)
+    pub fn set_filter_env_hold(&mut self, hold: bool) {
+        self.voice_manager.set_filter_env_hold(hold);
+    }
+
+    /// Open the filter in a short blip on note-off, for plucky release transients
+    pub fn set_filter_release_blip(&mut self, enabled: bool) {
+        self.voice_manager.set_filter_release_blip(enabled);
+    }
+
+    /// Highest cutoff the filter envelope sweeps to in Hz (never above 0.45 x sample rate)
+    pub fn set_filter_env_ceiling(&mut self, cutoff: f32) {
+        self.voice_manager.set_filter_env_ceiling(cutoff);
+    }
+
+    /// Reference pitch, transpose, fine tune and scale for MIDI notes (from the next note_on)
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.voice_manager.set_tuning(tuning);
+    }
+
+    /// Longest any envelope release may take in seconds
+    pub fn set_max_release(&mut self, seconds: f32) {
+        self.voice_manager.set_max_release(seconds);
+    }
+
     pub fn set_amp_adsr(&mut self, a: f32, d: f32, s: f32, r: f32) {
         self.params.amp_attack = a;
         self.params.amp_decay = d;
@@ -369,6 +759,19 @@ impl Synth {
         self.voice_manager.set_amp_envelope(a, d, s, r);
     }
 
+    /// Amp envelope with a delay (seconds) before the attack; `set_amp_adsr` keeps the delay
+    pub fn set_amp_dadsr(&mut self, delay: f32, a: f32, d: f32, s: f32, r: f32) {
+        self.params.amp_delay = delay.max(0.0);
+        self.voice_manager.set_amp_delay(self.params.amp_delay);
+        self.set_amp_adsr(a, d, s, r);
+    }
+
+    /// Linear or exponential amp envelope segments
+    pub fn set_amp_curve(&mut self, curve: EnvelopeCurve) {
+        self.params.amp_curve = curve;
+        self.voice_manager.set_amp_curve(curve);
+    }
+
     pub fn set_filter_adsr(&mut self, a: f32, d: f32, s: f32, r: f32) {
         self.params.filter_attack = a;
         self.params.filter_decay = d;
@@ -381,9 +784,59 @@ impl Synth {
         self.params.master_volume = volume.clamp(0.0, 1.0);
     }
 
-    /// Set pitch bend (-1 to 1, where 1 = +pitch_bend_range semitones)
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.set_master_volume(db_to_gain(db));
+    }
+
+    /// Set master pan (-1 = left, 0 = centre, 1 = right, equal-power)
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.params.master_pan = pan.clamp(-1.0, 1.0);
+    }
+
+    /// Write (L+R)/2 to both channels after all stereo processing
+    pub fn set_mono_sum(&mut self, enabled: bool) {
+        self.mono_sum = enabled;
+    }
+
+    /// Pan each voice by its note (low left, high right); negative reverses (-1.0 - 1.0)
+    pub fn set_key_pan_amount(&mut self, amount: f32) {
+        self.voice_manager.set_key_pan_amount(amount);
+    }
+
+    /// Lo-fi bit depth (1 - 24)
+    pub fn set_lofi_bit_depth(&mut self, bits: u32) {
+        self.lofi.set_bit_depth(bits);
+    }
+
+    /// Lo-fi sample-and-hold decimation factor (1 = off)
+    pub fn set_lofi_downsample(&mut self, factor: usize) {
+        self.lofi.set_downsample(factor);
+    }
+
+    /// Lo-fi dry/wet (0 = bypass)
+    pub fn set_lofi_mix(&mut self, mix: f32) {
+        self.lofi.set_mix(mix);
+    }
+
+    /// Pass DC through to the output (true) or block it (false, the default)
+    pub fn set_dc_coupling(&mut self, enabled: bool) {
+        self.dc_coupled = enabled;
+    }
+
+    /// Compress the summed output so chords and single notes sit at similar levels
+    pub fn set_auto_level(&mut self, enabled: bool) {
+        self.auto_level = enabled;
+    }
+
+    /// Auto-level threshold (dB), ratio, attack and release (ms)
+    pub fn set_auto_level_params(&mut self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.compressor.set_params(threshold_db, ratio, attack_ms, release_ms);
+    }
+
+    /// Set pitch bend (-1 to 1, where 1 = +pitch_bend_range semitones), ramped over 5 ms
     pub fn set_pitch_bend(&mut self, value: f32) {
-        self.voice_manager.set_pitch_bend(value);
+        self.bend_smoother.set_target(value.clamp(-1.0, 1.0));
     }
 
     /// Set pitch bend range in semitones (typically 2, 12, or 24)
@@ -427,4 +880,289 @@ mod tests {
         let loaded: SynthParams = serde_json::from_str(&json).unwrap();
         assert_eq!(params.filter_cutoff, loaded.filter_cutoff);
     }
+
+    #[test]
+    fn test_idle_process_is_silent() {
+        let mut synth = Synth::new(44100.0, 8);
+        let mut buffer = vec![1.0; 256];
+        synth.process(&mut buffer);
+        assert!(buffer.iter().all(|&s| s == 0.0));
+
+        synth.note_on(60, 100);
+        synth.process(&mut buffer);
+        assert!(buffer.iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn test_master_pan_hard_left() {
+        let mut synth = Synth::new(44100.0, 8);
+        synth.set_master_pan(-1.0);
+        synth.note_on(60, 100);
+
+        let mut left = vec![0.0; 1024];
+        let mut right = vec![0.0; 1024];
+        synth.process_stereo(&mut left, &mut right);
+        assert!(left.iter().any(|&s| s != 0.0));
+        assert!(right.iter().all(|&s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_cutoff_smoothing() {
+        let mut synth = Synth::new(44100.0, 8);
+        synth.set_filter_env_amount(0.0);
+        synth.set_cutoff_smoothing_ms(10.0);
+        synth.set_filter_cutoff(5000.0);
+        synth.note_on(48, 100);
+        let mut buffer = vec![0.0; 4410];
+        synth.process(&mut buffer);
+        let open_brightness = brightness(&buffer[2205..]);
+
+        // Halfway through the ramp the cutoff is between the two values
+        synth.set_filter_cutoff(500.0);
+        let mut buffer = vec![0.0; 220];
+        synth.process(&mut buffer);
+        let mid = synth.current_cutoff();
+        assert!(mid < 5000.0 && mid > 500.0, "cutoff {}", mid);
+        assert!((mid - 2750.0).abs() < 100.0);
+
+        // After the smoothing time it has arrived and the output is darker
+        let mut buffer = vec![0.0; 4410];
+        synth.process(&mut buffer);
+        assert_eq!(synth.current_cutoff(), 500.0);
+        assert!(brightness(&buffer[2205..]) < open_brightness * 0.5);
+    }
+
+    /// RMS of the first difference - a rough measure of high-frequency content
+    fn brightness(buffer: &[f32]) -> f32 {
+        let sum: f32 = buffer.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+        (sum / buffer.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_zero_velocity_note_on_is_note_off() {
+        let mut synth = Synth::new(44100.0, 8);
+        synth.note_on(60, 100);
+        synth.note_on(60, 0);
+        assert_eq!(synth.active_voice_count(), 1);
+
+        // The held voice is releasing rather than a new one being allocated
+        let mut buffer = vec![0.0; 44100];
+        synth.process(&mut buffer);
+        assert_eq!(synth.active_voice_count(), 0);
+    }
+
+    #[test]
+    fn test_deterministic_renders_match() {
+        let render = |history: bool| {
+            let mut synth = Synth::new(44100.0, 2);
+            synth.set_noise_level(0.5);
+            synth.voice_manager.set_humanize_detune(20.0);
+            if history {
+                synth.note_on(40, 100);
+                let mut scratch = vec![0.0; 1000];
+                synth.process(&mut scratch);
+                synth.panic_now();
+            }
+            synth.set_deterministic(true);
+            synth.note_on(64, 110);
+            let mut buffer = vec![0.0; 4410];
+            synth.process(&mut buffer);
+            buffer
+        };
+        assert_eq!(render(false), render(true));
+    }
+
+    #[test]
+    fn test_auto_level_evens_chords() {
+        let rms_with = |auto_level: bool, ratio: f32, notes: &[u8]| {
+            let mut synth = Synth::new(44100.0, 8);
+            synth.set_auto_level(auto_level);
+            synth.set_auto_level_params(-18.0, ratio, 5.0, 150.0);
+            for &note in notes {
+                synth.note_on(note, 100);
+            }
+            let mut buffer = vec![0.0; 22050];
+            synth.process(&mut buffer);
+            let tail = &buffer[11025..];
+            (tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32).sqrt()
+        };
+        let rms = |auto_level: bool, notes: &[u8]| rms_with(auto_level, 4.0, notes);
+        let chord = [48, 52, 55, 60, 64, 67];
+        let ratio_off = rms(false, &chord) / rms(false, &[60]);
+        let ratio_on = rms(true, &chord) / rms(true, &[60]);
+        assert!(ratio_on < ratio_off * 0.8, "on {} off {}", ratio_on, ratio_off);
+
+        // A 1:1 ratio leaves the output untouched
+        assert_eq!(rms_with(true, 1.0, &chord), rms(false, &chord));
+    }
+
+    #[test]
+    fn test_mono_sum() {
+        let render = |mono: bool| {
+            let mut synth = Synth::new(44100.0, 8);
+            synth.set_deterministic(true);
+            synth.set_master_pan(0.7);
+            synth.set_mono_sum(mono);
+            synth.note_on(60, 100);
+            let mut left = vec![0.0; 1024];
+            let mut right = vec![0.0; 1024];
+            synth.process_stereo(&mut left, &mut right);
+            (left, right)
+        };
+        let (wide_l, wide_r) = render(false);
+        let (mono_l, mono_r) = render(true);
+        assert!(wide_l.iter().zip(&wide_r).any(|(l, r)| l != r));
+        assert_eq!(mono_l, mono_r);
+        for i in 0..mono_l.len() {
+            assert_eq!(mono_l[i], (wide_l[i] + wide_r[i]) * 0.5);
+        }
+    }
+
+    #[test]
+    fn test_resonance_curve() {
+        let mut last = resonance_curve(0.0);
+        assert_eq!(last, 0.0);
+        for i in 1..=100 {
+            let value = resonance_curve(i as f32 / 100.0);
+            assert!(value > last);
+            last = value;
+        }
+        // The top half of the knob covers only the top quarter of the range
+        assert!(resonance_curve(0.5) == 0.75);
+        assert!((resonance_curve(resonance_knob(0.3)) - 0.3).abs() < 1e-6);
+
+        let mut synth = Synth::new(44100.0, 1);
+        synth.set_filter_resonance(1.0);
+        assert_eq!(synth.params().filter_resonance, 1.0);
+    }
+
+    #[test]
+    fn test_key_pan() {
+        let render = |note: u8, amount: f32| {
+            let mut synth = Synth::new(44100.0, 2);
+            synth.set_key_pan_amount(amount);
+            synth.note_on(note, 127);
+            let (mut left, mut right) = (0.0, 0.0);
+            for _ in 0..4410 {
+                let (l, r) = synth.tick_stereo();
+                left += l * l;
+                right += r * r;
+            }
+            (left, right)
+        };
+
+        let (left, right) = render(36, 1.0);
+        assert!(left > right * 1.5, "low note: {} {}", left, right);
+        let (left, right) = render(84, 1.0);
+        assert!(right > left * 1.5, "high note: {} {}", left, right);
+        let (left, right) = render(36, 0.0);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_cc_mapping() {
+        let mut synth = Synth::new(44100.0, 4);
+        synth.set_cc_mapping(20, CcDestination::FilterResonance);
+        synth.control_change(20, 127);
+        assert_eq!(synth.params().filter_resonance, 1.0);
+
+        // Unmapped CCs change nothing
+        let before = serde_json::to_string(synth.params()).unwrap();
+        synth.control_change(21, 0);
+        synth.clear_cc_mapping(20);
+        synth.control_change(20, 0);
+        assert_eq!(serde_json::to_string(synth.params()).unwrap(), before);
+
+        // Default map keeps the mod wheel on cutoff
+        synth.control_change(1, 0);
+        assert_eq!(synth.params().filter_cutoff, 100.0);
+    }
+
+    #[test]
+    fn test_dc_coupling() {
+        // A 10% pulse sits well below zero on average
+        let mean = |dc_coupled: bool| {
+            let mut synth = Synth::new(44100.0, 1);
+            synth.set_dc_coupling(dc_coupled);
+            synth.set_osc1_waveform(Waveform::Square);
+            synth.set_pulse_width(0.1);
+            synth.set_osc2_level(0.0);
+            synth.set_sub_level(0.0);
+            synth.set_noise_level(0.0);
+            synth.set_amp_adsr(0.001, 0.1, 1.0, 0.1);
+            synth.note_on(57, 100);
+            let mut buffer = vec![0.0; 44100];
+            synth.process(&mut buffer);
+            buffer[22050..].iter().sum::<f32>() / 22050.0
+        };
+
+        let coupled = mean(true);
+        let blocked = mean(false);
+        assert!(coupled < -0.1, "DC-coupled mean {}", coupled);
+        assert!(blocked.abs() < coupled.abs() * 0.02, "AC-coupled mean {}", blocked);
+    }
+
+    #[test]
+    fn test_instance_seed_decorrelates_noise() {
+        let render = |seed: u32| {
+            let mut synth = Synth::new(44100.0, 1);
+            synth.set_osc1_level(0.0);
+            synth.set_osc2_level(0.0);
+            synth.set_sub_level(0.0);
+            synth.set_noise_level(1.0);
+            synth.set_noise_pre_filter(false);
+            synth.set_instance_seed(seed);
+            synth.note_on(60, 127);
+            let mut buffer = vec![0.0; 4410];
+            synth.process(&mut buffer);
+            buffer
+        };
+        let correlation = |a: &[f32], b: &[f32]| {
+            let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+            let energy = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            dot / (energy(a) * energy(b))
+        };
+
+        let (a, b) = (render(1), render(2));
+        assert!(correlation(&a, &b).abs() < 0.2, "{}", correlation(&a, &b));
+        // The same seed still renders identically
+        assert_eq!(render(1), a);
+    }
+
+    #[test]
+    fn test_note_during_panic_fade_survives() {
+        let mut synth = Synth::new(44100.0, 4);
+        synth.set_amp_adsr(0.001, 0.1, 1.0, 0.1);
+        synth.note_on(60, 100);
+        let mut buffer = vec![0.0; 441];
+        synth.process(&mut buffer);
+
+        // A note played mid-fade keeps sounding after the fade stops the old one
+        synth.panic();
+        synth.process(&mut buffer[..50]);
+        synth.note_on(64, 100);
+        let mut buffer = vec![0.0; 44100];
+        synth.process(&mut buffer);
+
+        let states = synth.voice_states();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].note, 64);
+        assert!(buffer[44000..].iter().any(|s| s.abs() > 1e-3));
+    }
+
+    #[test]
+    fn test_meter_mono_process() {
+        let mut synth = Synth::new(44100.0, 1);
+        synth.note_on(60, 100);
+        let mut buffer = vec![0.0; 512];
+        synth.process(&mut buffer);
+        let peak = buffer.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.0);
+        assert_eq!(synth.meter().peak(), peak);
+
+        // Silence between notes reads as silence
+        synth.reset();
+        synth.process(&mut buffer);
+        assert_eq!(synth.meter().peak(), 0.0);
+    }
 }
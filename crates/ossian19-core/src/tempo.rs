@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// Musical note length for tempo-relative times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum NoteDivision {
+    Whole,
+    Half,
+    #[default]
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+    DottedQuarter,
+    DottedEighth,
+    TripletQuarter,
+    TripletEighth,
+}
+
+impl NoteDivision {
+    /// Length in quarter-note beats
+    pub fn beats(&self) -> f32 {
+        match self {
+            Self::Whole => 4.0,
+            Self::Half => 2.0,
+            Self::Quarter => 1.0,
+            Self::Eighth => 0.5,
+            Self::Sixteenth => 0.25,
+            Self::ThirtySecond => 0.125,
+            Self::DottedQuarter => 1.5,
+            Self::DottedEighth => 0.75,
+            Self::TripletQuarter => 2.0 / 3.0,
+            Self::TripletEighth => 1.0 / 3.0,
+        }
+    }
+
+    /// Length in seconds at the given tempo
+    pub fn to_seconds(&self, bpm: f32) -> f32 {
+        self.beats() * 60.0 / bpm.max(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_division_to_seconds() {
+        assert_eq!(NoteDivision::Quarter.to_seconds(120.0), 0.5);
+        assert_eq!(NoteDivision::DottedEighth.to_seconds(120.0), 0.375);
+        assert_eq!(NoteDivision::Whole.to_seconds(60.0), 4.0);
+    }
+}
@@ -0,0 +1,87 @@
+//! Global tuning: reference pitch, transpose, fine tune and a 12-note scale
+
+use serde::{Deserialize, Serialize};
+
+/// How MIDI notes map to frequencies (applied before any operator ratios)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tuning {
+    /// Frequency of A4 (MIDI note 69) in Hz
+    pub reference: f32,
+    /// Semitones added to every note
+    pub transpose: i8,
+    /// Cents added to every note
+    pub fine: f32,
+    /// Cents offset per pitch class of the sounding note (C = 0), for microtonal scales
+    pub scale: [f32; 12],
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            reference: 440.0,
+            transpose: 0,
+            fine: 0.0,
+            scale: [0.0; 12],
+        }
+    }
+}
+
+impl Tuning {
+    /// Set the A4 reference in Hz (builder style)
+    pub fn with_reference(mut self, reference: f32) -> Self {
+        self.reference = reference.clamp(400.0, 480.0);
+        self
+    }
+
+    /// Set the transpose in semitones (builder style)
+    pub fn with_transpose(mut self, semitones: i8) -> Self {
+        self.transpose = semitones.clamp(-48, 48);
+        self
+    }
+
+    /// Set the fine tune in cents (builder style)
+    pub fn with_fine(mut self, cents: f32) -> Self {
+        self.fine = cents.clamp(-100.0, 100.0);
+        self
+    }
+
+    /// Set the per-pitch-class cent offsets (builder style)
+    pub fn with_scale(mut self, cents: [f32; 12]) -> Self {
+        self.scale = cents;
+        self
+    }
+
+    /// Frequency of a MIDI note in Hz
+    pub fn frequency(&self, note: u8) -> f32 {
+        let note = note as i32 + self.transpose as i32;
+        let cents = self.fine + self.scale[note.rem_euclid(12) as usize];
+        self.reference * (2.0_f32).powf((note as f32 - 69.0 + cents / 100.0) / 12.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::midi_to_freq;
+
+    #[test]
+    fn test_tuning_frequency() {
+        // Standard tuning matches the plain conversion exactly
+        let standard = Tuning::default();
+        for note in 0..128 {
+            assert_eq!(standard.frequency(note), midi_to_freq(note));
+        }
+
+        assert_eq!(Tuning::default().with_reference(432.0).frequency(69), 432.0);
+        assert_eq!(Tuning::default().with_transpose(12).frequency(57), 440.0);
+        let fine = Tuning::default().with_fine(100.0).frequency(68);
+        assert!((fine - 440.0).abs() < 1e-3, "{}", fine);
+
+        // Just-intonation fifth above C: 702 cents instead of 700
+        let mut scale = [0.0; 12];
+        scale[7] = 2.0;
+        let just = Tuning::default().with_scale(scale);
+        let ratio = just.frequency(67) / just.frequency(60);
+        assert!((ratio - 1.5).abs() < 1e-4, "{}", ratio);
+    }
+}
@@ -1,16 +1,66 @@
-use crate::envelope::Envelope;
-use crate::filter::LadderFilter;
-use crate::oscillator::{Oscillator, Waveform};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::envelope::{Envelope, EnvelopeCurve, EnvelopeLoop, EnvelopeStage};
+use crate::filter::{FilterRouting, FilterType, LadderFilter, LadderMode, OnePoleHighPass};
+use crate::lfo::{Lfo, LfoWaveform};
+use crate::tuning::Tuning;
+use crate::oscillator::{Oscillator, Quality, Waveform};
+
+/// Resonance at which the ladder filter is treated as self-oscillating
+const SELF_OSC_RESONANCE: f32 = 0.9;
+/// Minimum fade-out time for self-oscillating voices on release (seconds)
+const SELF_OSC_RELEASE_FADE: f32 = 0.01;
+/// Lowest HPF cutoff in Hz; the filter is bypassed there
+const HPF_MIN_CUTOFF: f32 = 20.0;
+/// Decay time constant of the filter release blip (milliseconds)
+const FILTER_RELEASE_BLIP_MS: f32 = 30.0;
+
+/// Initial state of every `NoiseGen`
+const NOISE_SEED: u32 = 12345;
+
+/// Counter behind `next_instance_seed`
+static NEXT_INSTANCE_SEED: AtomicU32 = AtomicU32::new(1);
+
+/// A different seed on every call, for giving each engine instance its own randomness
+pub fn next_instance_seed() -> u32 {
+    NEXT_INSTANCE_SEED.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Offset a generator's base seed by an instance seed (0 keeps the base, never returns 0)
+pub fn offset_seed(base: u32, instance_seed: u32) -> u32 {
+    match base ^ instance_seed.wrapping_mul(2654435761) {
+        0 => base,
+        seed => seed,
+    }
+}
 
 /// Simple noise generator
 #[derive(Debug, Clone)]
 pub struct NoiseGen {
     state: u32,
+    /// State `reseed` restarts from
+    seed: u32,
 }
 
 impl NoiseGen {
     pub fn new() -> Self {
-        Self { state: 12345 }
+        Self::with_seed(NOISE_SEED)
+    }
+
+    /// Start from a custom seed (for independent sequences)
+    pub fn with_seed(seed: u32) -> Self {
+        Self { state: seed, seed }
+    }
+
+    /// Restart the sequence from the initial seed
+    pub fn reseed(&mut self) {
+        self.state = self.seed;
+    }
+
+    /// Restart from the default seed offset by `instance_seed`
+    pub fn set_instance_seed(&mut self, instance_seed: u32) {
+        *self = Self::with_seed(offset_seed(NOISE_SEED, instance_seed));
     }
 
     /// Generate white noise sample (-1 to 1)
@@ -36,19 +86,36 @@ pub struct Voice {
     pub osc2: Oscillator,
     pub sub_osc: Oscillator,  // Sub oscillator (octave below)
     pub noise: NoiseGen,
+    /// Juno-style non-resonant high-pass on the mixed oscillators, before the ladder
+    pub hpf: OnePoleHighPass,
     pub filter: LadderFilter,
+    /// Second filter, used by the Series and Parallel routings
+    pub filter2: LadderFilter,
+    pub filter_routing: FilterRouting,
+    /// Base cutoff of the second filter in Hz (the filter envelope applies to both)
+    pub filter2_cutoff: f32,
     pub amp_env: Envelope,
     pub filter_env: Envelope,
 
     /// MIDI note number (0-127)
     pub note: u8,
+    /// Unbent, undetuned frequency of the current note in Hz
+    pub base_freq: f32,
+    /// Id of a frequency-triggered note (None for MIDI notes)
+    pub id: Option<u32>,
     /// Velocity (0.0 - 1.0)
     pub velocity: f32,
+    /// Samples rendered since note-on
+    pub age: u64,
     /// Is this voice currently active?
     pub active: bool,
+    /// Key released while the sustain pedal was down
+    sustained: bool,
 
     // Filter envelope modulation amount
     pub filter_env_amount: f32,
+    /// Cutoff the filter envelope sweeps towards at full amount in Hz (capped at 0.45 x sample rate)
+    pub filter_env_ceiling: f32,
     // Oscillator levels (0.0 = off, 1.0 = full)
     pub osc1_level: f32,
     pub osc2_level: f32,
@@ -58,6 +125,36 @@ pub struct Voice {
     // FM synthesis parameters
     pub fm_amount: f32,    // 0.0 = no FM, 1.0 = full FM modulation
     pub fm_ratio: f32,     // Modulator frequency ratio (1.0 = same as carrier)
+
+    /// Sub oscillator octave below the note (-1 or -2)
+    pub sub_octave: i8,
+    /// Let the sub oscillator run freely instead of resetting at note_on
+    pub sub_free_phase: bool,
+    /// Freeze the filter envelope at its current level
+    pub filter_env_hold: bool,
+    /// Kick the filter envelope back up on note-off, decaying in a short blip (for plucks)
+    pub filter_release_blip: bool,
+    /// Send the sub oscillator through the filter (false = mix it in after the filter)
+    pub sub_pre_filter: bool,
+    /// Send the noise through the filter (false = mix it in after the filter)
+    pub noise_pre_filter: bool,
+
+    /// Per-note pitch offset in cents (humanize detune, set at note_on)
+    pub detune_offset: f32,
+
+    /// Pulse width of both oscillators before PWM (0.01 - 0.99)
+    pub pulse_width: f32,
+    /// How far the PWM LFO swings the pulse width (0 = off, 1 = +/-0.49)
+    pub pwm_depth: f32,
+    pub pwm_lfo: Lfo,
+
+    /// Output gain actually applied (follows the amp envelope, slew-limited when self-oscillating)
+    amp_level: f32,
+    /// Remaining release blip (1.0 at note-off, decays to 0)
+    release_blip: f32,
+    /// Sounding when `panic` started a fade; stopped when the fade ends
+    pub(crate) panicking: bool,
+    sample_rate: f32,
 }
 
 impl Voice {
@@ -70,29 +167,65 @@ impl Voice {
             osc2: Oscillator::new(sample_rate),
             sub_osc,
             noise: NoiseGen::new(),
+            hpf: OnePoleHighPass::new(sample_rate),
             filter: LadderFilter::new(sample_rate),
+            filter2: {
+                let mut filter2 = LadderFilter::new(sample_rate);
+                filter2.filter_type = FilterType::HighPass;
+                filter2
+            },
+            filter_routing: FilterRouting::Single,
+            filter2_cutoff: 200.0,
             amp_env: Envelope::new(sample_rate),
             filter_env: Envelope::new(sample_rate),
             note: 0,
+            base_freq: 440.0,
+            id: None,
             velocity: 0.0,
+            age: 0,
             active: false,
+            sustained: false,
             filter_env_amount: 0.5,
+            filter_env_ceiling: 20000.0,
             osc1_level: 1.0,
             osc2_level: 0.0,  // Off by default
             sub_level: 0.0,   // Off by default
             noise_level: 0.0, // Off by default
             fm_amount: 0.0,   // No FM by default
             fm_ratio: 2.0,    // Classic 2:1 ratio
+            sub_octave: -1,
+            sub_free_phase: false,
+            filter_env_hold: false,
+            filter_release_blip: false,
+            sub_pre_filter: true,
+            noise_pre_filter: true,
+            detune_offset: 0.0,
+            pulse_width: 0.5,
+            pwm_depth: 0.0,
+            pwm_lfo: {
+                // Juno-style triangle
+                let mut lfo = Lfo::new(sample_rate);
+                lfo.waveform = LfoWaveform::Triangle;
+                lfo
+            },
+            amp_level: 0.0,
+            release_blip: 0.0,
+            panicking: false,
+            sample_rate,
         }
     }
 
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
         self.osc1.set_sample_rate(sample_rate);
         self.osc2.set_sample_rate(sample_rate);
         self.sub_osc.set_sample_rate(sample_rate);
+        self.hpf.set_sample_rate(sample_rate);
         self.filter.set_sample_rate(sample_rate);
+        self.filter2.set_sample_rate(sample_rate);
         self.amp_env.set_sample_rate(sample_rate);
         self.filter_env.set_sample_rate(sample_rate);
+        self.pwm_lfo.set_sample_rate(sample_rate);
     }
 
     /// Start a note
@@ -102,35 +235,73 @@ impl Voice {
 
     /// Start a note with pitch bend applied
     pub fn note_on_with_bend(&mut self, note: u8, velocity: f32, bend_multiplier: f32) {
+        self.note_on_at(note, midi_to_freq(note), velocity, bend_multiplier);
+    }
+
+    /// Start a note sounding at `freq` (the note's tuned frequency) with pitch bend applied
+    pub fn note_on_at(&mut self, note: u8, freq: f32, velocity: f32, bend_multiplier: f32) {
         self.note = note;
+        self.id = None;
+        self.start(freq, velocity, bend_multiplier);
+    }
+
+    /// Start a note at an arbitrary frequency, tracked by `id` instead of note number
+    pub fn note_on_freq_with_bend(&mut self, id: u32, freq: f32, velocity: f32, bend_multiplier: f32) {
+        self.note = freq_to_midi(freq);
+        self.id = Some(id);
+        self.start(freq, velocity, bend_multiplier);
+    }
+
+    fn start(&mut self, freq: f32, velocity: f32, bend_multiplier: f32) {
+        self.base_freq = freq;
         self.velocity = velocity;
+        self.age = 0;
         self.active = true;
 
-        // Convert MIDI note to frequency with pitch bend
-        let base_freq = midi_to_freq(note);
-        let freq = base_freq * bend_multiplier;
-        self.osc1.set_frequency(freq);
-        // Osc2 frequency depends on FM mode
-        // In FM mode, fm_ratio controls modulator:carrier ratio
-        // In normal mode, osc2 uses same frequency (with detune applied separately)
-        self.osc2.set_frequency(freq * self.fm_ratio);
-        // Sub oscillator is one octave below
-        self.sub_osc.set_frequency(freq * 0.5);
+        self.update_frequencies(bend_multiplier);
 
         // Reset oscillator phases for consistent attack
         self.osc1.reset();
         self.osc2.reset();
-        self.sub_osc.reset();
+        if !self.sub_free_phase {
+            self.sub_osc.reset();
+        }
 
         // Trigger envelopes
         self.amp_env.trigger();
         self.filter_env.trigger();
+        self.release_blip = 0.0;
+        self.panicking = false;
+    }
+
+    /// Recompute oscillator frequencies from the note (never from the live frequency)
+    pub fn update_frequencies(&mut self, bend_multiplier: f32) {
+        let freq = self.base_freq * bend_multiplier * self.detune_multiplier();
+        self.osc1.set_frequency(freq);
+        // Osc2 frequency depends on FM mode
+        // In FM mode, fm_ratio controls modulator:carrier ratio
+        // In normal mode, osc2 uses same frequency (with detune applied separately)
+        self.osc2.set_frequency(freq * self.fm_ratio);
+        // Sub oscillator is one or two octaves below
+        self.sub_osc.set_frequency(freq * 0.5f32.powi(-self.sub_octave as i32));
+    }
+
+    /// Frequency multiplier for the per-note detune offset
+    fn detune_multiplier(&self) -> f32 {
+        if self.detune_offset != 0.0 {
+            (2.0_f32).powf(self.detune_offset / 1200.0)
+        } else {
+            1.0
+        }
     }
 
     /// Release a note
     pub fn note_off(&mut self) {
         self.amp_env.release();
         self.filter_env.release();
+        if self.filter_release_blip {
+            self.release_blip = 1.0;
+        }
     }
 
     /// Check if voice is finished and can be reused
@@ -138,13 +309,43 @@ impl Voice {
         self.amp_env.is_idle()
     }
 
+    /// Whether the note has been released (amp envelope in its release stage)
+    pub fn is_released(&self) -> bool {
+        self.amp_env.stage() == EnvelopeStage::Release
+    }
+
+    /// Stealing order: released (0), held by the sustain pedal (1), key still down (2)
+    fn steal_rank(&self) -> u8 {
+        if self.is_released() {
+            0
+        } else if self.sustained {
+            1
+        } else {
+            2
+        }
+    }
+
     /// Generate next sample
     pub fn tick(&mut self, base_cutoff: f32) -> f32 {
+        self.tick_with_cutoff_mod(base_cutoff, 0.0)
+    }
+
+    /// Generate next sample, adding `cutoff_mod` (Hz) to the envelope-modulated cutoff
+    pub fn tick_with_cutoff_mod(&mut self, base_cutoff: f32, cutoff_mod: f32) -> f32 {
         use std::f32::consts::PI;
 
         if !self.active {
             return 0.0;
         }
+        self.age += 1;
+
+        // PWM: the LFO keeps running at zero depth, which leaves the width untouched
+        let pwm = self.pwm_lfo.tick();
+        if self.pwm_depth > 0.0 {
+            let width = (self.pulse_width + pwm * self.pwm_depth * 0.49).clamp(0.01, 0.99);
+            self.osc1.set_pulse_width(width);
+            self.osc2.set_pulse_width(width);
+        }
 
         // FM synthesis: osc2 modulates osc1's phase
         let osc1_out;
@@ -174,30 +375,62 @@ impl Voice {
         let sub_out = self.sub_osc.tick() * self.sub_level;
         let noise_out = self.noise.tick() * self.noise_level;
 
-        // Mix all sources with proper gain staging
+        // Mix all sources with proper gain staging; post-filter sources skip the filter
         let total_level = self.osc1_level + self.osc2_level + self.sub_level + self.noise_level;
-        let osc_out = if total_level > 1.0 {
-            (osc1_out + osc2_out + sub_out + noise_out) / total_level
-        } else if total_level > 0.0 {
-            osc1_out + osc2_out + sub_out + noise_out
-        } else {
-            0.0
-        };
+        let gain = if total_level > 1.0 { 1.0 / total_level } else { 1.0 };
+        let mut pre = osc1_out + osc2_out;
+        let mut post = 0.0;
+        if self.sub_pre_filter { pre += sub_out } else { post += sub_out }
+        if self.noise_pre_filter { pre += noise_out } else { post += noise_out }
+        let osc_out = self.hpf.tick(pre * gain);
 
         // Filter envelope modulation
-        let filter_env_val = self.filter_env.tick();
-        let cutoff = base_cutoff + (20000.0 - base_cutoff) * filter_env_val * self.filter_env_amount;
-        self.filter.set_cutoff(cutoff);
-
-        // Apply filter
-        let filtered = self.filter.tick(osc_out);
+        let filter_env_val = if self.filter_env_hold {
+            self.filter_env.level()
+        } else {
+            self.filter_env.tick()
+        };
+        let filter_env_val = if self.release_blip > 1e-4 {
+            let val = filter_env_val + (1.0 - filter_env_val) * self.release_blip;
+            self.release_blip *= (-1000.0 / (FILTER_RELEASE_BLIP_MS * self.sample_rate)).exp();
+            val
+        } else {
+            filter_env_val
+        };
+        let env_mod = filter_env_val * self.filter_env_amount;
+        let ceiling = self.filter_env_ceiling.min(self.sample_rate * 0.45);
+        self.filter.set_cutoff(base_cutoff + (ceiling - base_cutoff).max(0.0) * env_mod + cutoff_mod);
+
+        // Apply filter(s)
+        let filtered = match self.filter_routing {
+            FilterRouting::Single => self.filter.tick(osc_out),
+            routing => {
+                self.filter2.set_cutoff(
+                    self.filter2_cutoff + (ceiling - self.filter2_cutoff).max(0.0) * env_mod + cutoff_mod,
+                );
+                if routing == FilterRouting::Series {
+                    let first = self.filter.tick(osc_out);
+                    self.filter2.tick(first)
+                } else {
+                    self.filter.tick(osc_out) + self.filter2.tick(osc_out)
+                }
+            }
+        };
 
         // Apply amplitude envelope and velocity
         let amp_env_val = self.amp_env.tick();
-        let output = filtered * amp_env_val * self.velocity;
+        self.amp_level = if self.filter.resonance >= SELF_OSC_RESONANCE {
+            // A self-oscillating filter rings on its own, so never let the amp
+            // close faster than the fade time or the resonant tone ends in a thump
+            let max_fall = 1.0 / (SELF_OSC_RELEASE_FADE * self.sample_rate);
+            amp_env_val.max(self.amp_level - max_fall)
+        } else {
+            amp_env_val
+        };
+        let output = (filtered + post * gain) * self.amp_level * self.velocity;
 
         // Check if voice is finished
-        if self.amp_env.is_idle() {
+        if self.amp_env.is_idle() && self.amp_level <= 0.0 {
             self.active = false;
         }
 
@@ -208,9 +441,14 @@ impl Voice {
         self.osc1.reset();
         self.osc2.reset();
         self.sub_osc.reset();
+        self.hpf.reset();
         self.filter.reset();
+        self.filter2.reset();
         self.amp_env.reset();
         self.filter_env.reset();
+        self.amp_level = 0.0;
+        self.release_blip = 0.0;
+        self.panicking = false;
         self.active = false;
         self.note = 0;
         self.velocity = 0.0;
@@ -222,11 +460,58 @@ pub fn midi_to_freq(note: u8) -> f32 {
     440.0 * (2.0_f32).powf((note as f32 - 69.0) / 12.0)
 }
 
+/// Map a normalized 14-bit pitch bend (0.0 - 1.0, as hosts deliver it) to -1.0 - 1.0.
+/// The raw center 8192 lands exactly on 0; each half is scaled separately so
+/// both extremes reach full range.
+pub fn pitch_bend_from_normalized(value: f32) -> f32 {
+    let raw = (value.clamp(0.0, 1.0) * 16383.0).round() - 8192.0;
+    if raw >= 0.0 {
+        raw / 8191.0
+    } else {
+        raw / 8192.0
+    }
+}
+
+/// Keyboard pan position for `note`: `amount` of full pan per 4 octaves from middle C
+pub fn key_pan(note: u8, amount: f32) -> f32 {
+    ((note as f32 - 60.0) / 48.0 * amount).clamp(-1.0, 1.0)
+}
+
+/// Equal-power pan gains (-1 = left, 1 = right), normalized so centre is unity
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    if pan == 0.0 {
+        return (1.0, 1.0);
+    }
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    (
+        angle.cos() * std::f32::consts::SQRT_2,
+        angle.sin() * std::f32::consts::SQRT_2,
+    )
+}
+
+/// Convert decibels to linear gain (0 dB = 1.0)
+pub fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
 /// Convert frequency to MIDI note number
 pub fn freq_to_midi(freq: f32) -> u8 {
     (12.0 * (freq / 440.0).log2() + 69.0).round() as u8
 }
 
+/// Snapshot of one sounding voice for UI display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceState {
+    pub note: u8,
+    pub velocity: f32,
+    /// Samples since note-on (higher = older)
+    pub age: u64,
+    /// Amplitude envelope stage
+    pub stage: EnvelopeStage,
+    /// Amplitude envelope level
+    pub level: f32,
+}
+
 /// Polyphonic voice manager
 pub struct VoiceManager {
     voices: Vec<Voice>,
@@ -235,6 +520,25 @@ pub struct VoiceManager {
     pitch_bend: f32,
     /// Pitch bend range in semitones (default: 2)
     pitch_bend_range: f32,
+    /// Maximum random detune applied per note in cents (0 = off)
+    humanize_detune: f32,
+    humanize_rng: NoiseGen,
+    /// Reseed all randomness at every note_on so renders are reproducible
+    deterministic: bool,
+    /// Id handed out by the next `note_on_freq`
+    next_note_id: u32,
+    /// Ignore note_off; a second press of a held note releases it
+    latch: bool,
+    /// Lowest and highest note that starts a voice (key split)
+    key_range: (u8, u8),
+    /// Sustain pedal (CC64) is down
+    sustain_pedal: bool,
+    /// With the pedal down, a repeated note gets a new voice instead of retriggering
+    pedal_stacking: bool,
+    /// Keyboard panning amount (0 = off)
+    key_pan_amount: f32,
+    /// Note to frequency mapping for MIDI notes
+    tuning: Tuning,
 }
 
 impl VoiceManager {
@@ -245,6 +549,16 @@ impl VoiceManager {
             sample_rate,
             pitch_bend: 0.0,
             pitch_bend_range: 2.0, // ±2 semitones default
+            humanize_detune: 0.0,
+            humanize_rng: NoiseGen::new(),
+            deterministic: false,
+            next_note_id: 1,
+            latch: false,
+            key_range: (0, 127),
+            sustain_pedal: false,
+            pedal_stacking: true,
+            key_pan_amount: 0.0,
+            tuning: Tuning::default(),
         }
     }
 
@@ -255,45 +569,200 @@ impl VoiceManager {
         }
     }
 
-    /// Find a free voice or steal the oldest one
-    fn allocate_voice(&mut self) -> Option<&mut Voice> {
+    /// Index of a free voice, or of the voice to steal
+    fn allocate_voice(&mut self) -> Option<usize> {
         // First, try to find an inactive voice by index
         let inactive_idx = self.voices.iter().position(|v| !v.active);
 
-        if let Some(idx) = inactive_idx {
-            return self.voices.get_mut(idx);
+        if inactive_idx.is_some() {
+            return inactive_idx;
         }
 
-        // Voice stealing: find the voice in release stage with lowest amplitude
-        // For simplicity, just take the first voice (round-robin stealing)
-        self.voices.first_mut()
+        // Voice stealing: released notes go first, then pedal-held ones, and held
+        // keys only when nothing else is left; the oldest voice in that group is taken
+        (0..self.voices.len()).min_by_key(|&i| (self.voices[i].steal_rank(), Reverse(self.voices[i].age)))
     }
 
-    /// Start a new note
-    pub fn note_on(&mut self, note: u8, velocity: f32) {
+    /// Start a new note; returns the index of the voice that plays it (None if no
+    /// voice started, e.g. outside the key range or a latch release)
+    pub fn note_on(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        // Velocity 0 note-on is a note-off by MIDI convention
+        if velocity <= 0.0 {
+            self.note_off(note);
+            return None;
+        }
+
+        if note < self.key_range.0 || note > self.key_range.1 {
+            return None;
+        }
+        if self.latch_release(note) {
+            return None;
+        }
+
         let bend_mult = self.pitch_bend_multiplier();
 
         // Check if this note is already playing, if so, retrigger
-        if let Some(voice) = self.voices.iter_mut().find(|v| v.active && v.note == note) {
-            voice.note_on_with_bend(note, velocity, bend_mult);
-            return;
+        // (unless the pedal is down and repeats stack)
+        let existing = if self.sustain_pedal && self.pedal_stacking {
+            None
+        } else {
+            self.voices.iter().position(|v| v.active && v.note == note && v.id.is_none())
+        };
+        let freq = self.tuning.frequency(note);
+        let index = self.prepare_voice(existing)?;
+        self.voices[index].note_on_at(note, freq, velocity, bend_mult);
+        Some(index)
+    }
+
+    /// Start a note at an arbitrary frequency (microtonal).
+    /// Returns an id for `note_off_id`; MIDI `note_off` does not release it.
+    pub fn note_on_freq(&mut self, freq: f32, velocity: f32) -> u32 {
+        let id = self.next_note_id;
+        self.next_note_id = self.next_note_id.wrapping_add(1);
+        if velocity > 0.0 {
+            let bend_mult = self.pitch_bend_multiplier();
+            if let Some(index) = self.prepare_voice(None) {
+                self.voices[index].note_on_freq_with_bend(id, freq.clamp(1.0, 20000.0), velocity, bend_mult);
+            }
         }
+        id
+    }
 
-        // Allocate a new voice
-        if let Some(voice) = self.allocate_voice() {
-            voice.note_on_with_bend(note, velocity, bend_mult);
+    /// Release a note started with `note_on_freq`
+    pub fn note_off_id(&mut self, id: u32) {
+        for voice in &mut self.voices {
+            if voice.active && voice.id == Some(id) {
+                voice.note_off();
+            }
         }
     }
 
-    /// Release a note
-    pub fn note_off(&mut self, note: u8) {
+    /// Pick a voice (retriggering `existing` or allocating) and apply per-note randomness
+    fn prepare_voice(&mut self, existing: Option<usize>) -> Option<usize> {
+        // Restart the humanize sequence only from silence, so chord notes still differ
+        if self.deterministic && self.active_voice_count() == 0 {
+            self.humanize_rng.reseed();
+        }
+        let detune_offset = self.next_humanize_offset();
+        let deterministic = self.deterministic;
+
+        let index = existing.or_else(|| self.allocate_voice())?;
+        let voice = self.voices.get_mut(index)?;
+        if deterministic {
+            voice.noise.reseed();
+        }
+        voice.detune_offset = detune_offset;
+        voice.sustained = false;
+        Some(index)
+    }
+
+    /// Reseed every random source at note_on so output is bit-stable (for tests)
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.deterministic = enabled;
+        if enabled {
+            self.humanize_rng.reseed();
+        }
+    }
+
+    /// Offset every random source so layered instances don't produce identical noise
+    /// and humanize sequences (0 = the fixed default seeds)
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.humanize_rng.set_instance_seed(seed);
         for voice in &mut self.voices {
-            if voice.active && voice.note == note {
+            voice.noise.set_instance_seed(seed);
+        }
+    }
+
+    /// Set random per-note detune in cents (0 = off)
+    pub fn set_humanize_detune(&mut self, cents: f32) {
+        self.humanize_detune = cents.clamp(0.0, 50.0);
+    }
+
+    /// Draw the detune offset for the next note
+    fn next_humanize_offset(&mut self) -> f32 {
+        if self.humanize_detune > 0.0 {
+            self.humanize_rng.tick() * self.humanize_detune
+        } else {
+            0.0
+        }
+    }
+
+    /// In latch mode, release `note` if it is still held. Returns true if it was.
+    fn latch_release(&mut self, note: u8) -> bool {
+        if !self.latch {
+            return false;
+        }
+        let held = self
+            .voices
+            .iter_mut()
+            .find(|v| v.active && v.note == note && v.id.is_none() && !v.is_released());
+        match held {
+            Some(voice) => {
                 voice.note_off();
+                true
             }
+            None => false,
         }
     }
 
+    /// Release a note (ignored in latch mode)
+    pub fn note_off(&mut self, note: u8) {
+        if self.latch {
+            return;
+        }
+        let pedal = self.sustain_pedal;
+        for voice in &mut self.voices {
+            if voice.active && voice.note == note && voice.id.is_none() {
+                if pedal {
+                    voice.sustained = true;
+                } else {
+                    voice.note_off();
+                }
+            }
+        }
+    }
+
+    /// Sustain pedal: while down, released keys keep sounding until it is lifted
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        if self.sustain_pedal && !down {
+            for voice in &mut self.voices {
+                if voice.sustained {
+                    voice.sustained = false;
+                    if voice.active {
+                        voice.note_off();
+                    }
+                }
+            }
+        }
+        self.sustain_pedal = down;
+    }
+
+    /// With the pedal down, re-pressing a sounding note stacks a new voice
+    /// instead of retriggering it (on by default)
+    pub fn set_pedal_stacking(&mut self, enabled: bool) {
+        self.pedal_stacking = enabled;
+    }
+
+    /// Respond only to notes from `low` to `high` (inclusive). Notes already
+    /// sounding still release normally.
+    pub fn set_key_range(&mut self, low: u8, high: u8) {
+        let (low, high) = (low.min(127), high.min(127));
+        self.key_range = (low.min(high), low.max(high));
+    }
+
+    /// Latch mode: note_off is ignored and pressing a held note again releases it.
+    /// Turning latch off releases every latched note.
+    pub fn set_latch(&mut self, enabled: bool) {
+        if self.latch && !enabled {
+            for voice in &mut self.voices {
+                if voice.active && voice.id.is_none() {
+                    voice.note_off();
+                }
+            }
+        }
+        self.latch = enabled;
+    }
+
     /// Release all notes
     pub fn all_notes_off(&mut self) {
         for voice in &mut self.voices {
@@ -308,11 +777,33 @@ impl VoiceManager {
         }
     }
 
+    /// Stop all voices and clear controller state (pitch bend, sustain pedal); settings are kept
+    pub fn reset(&mut self) {
+        self.panic();
+        self.pitch_bend = 0.0;
+        self.sustain_pedal = false;
+    }
+
     /// Get number of currently active voices
     pub fn active_voice_count(&self) -> usize {
         self.voices.iter().filter(|v| v.active).count()
     }
 
+    /// Note, velocity, age, envelope stage and level of every active voice
+    pub fn voice_states(&self) -> Vec<VoiceState> {
+        self.voices
+            .iter()
+            .filter(|v| v.active)
+            .map(|v| VoiceState {
+                note: v.note,
+                velocity: v.velocity,
+                age: v.age,
+                stage: v.amp_env.stage(),
+                level: v.amp_env.level(),
+            })
+            .collect()
+    }
+
     /// Apply settings to all voices
     pub fn set_osc1_waveform(&mut self, waveform: Waveform) {
         for voice in &mut self.voices {
@@ -359,12 +850,54 @@ impl VoiceManager {
     pub fn set_filter_resonance(&mut self, resonance: f32) {
         for voice in &mut self.voices {
             voice.filter.set_resonance(resonance);
+            voice.filter2.set_resonance(resonance);
         }
     }
 
     pub fn set_filter_slope(&mut self, slope: crate::filter::FilterSlope) {
         for voice in &mut self.voices {
             voice.filter.set_slope(slope);
+            voice.filter2.set_slope(slope);
+        }
+    }
+
+    /// Set anti-aliasing quality for oscillators and filters
+    pub fn set_quality(&mut self, quality: Quality) {
+        for voice in &mut self.voices {
+            voice.osc1.quality = quality;
+            voice.osc2.quality = quality;
+            voice.sub_osc.quality = quality;
+            voice.filter.oversample = quality.filter_oversample();
+            voice.filter2.oversample = quality.filter_oversample();
+        }
+    }
+
+    /// Choose Vintage (saturating) or Clean ladder filter character
+    pub fn set_filter_character(&mut self, mode: LadderMode) {
+        for voice in &mut self.voices {
+            voice.filter.set_mode(mode);
+            voice.filter2.set_mode(mode);
+        }
+    }
+
+    /// Choose how the two filters are combined
+    pub fn set_filter_routing(&mut self, routing: FilterRouting) {
+        for voice in &mut self.voices {
+            voice.filter_routing = routing;
+        }
+    }
+
+    /// Set the second filter's base cutoff in Hz
+    pub fn set_filter2_cutoff(&mut self, cutoff: f32) {
+        for voice in &mut self.voices {
+            voice.filter2_cutoff = cutoff.clamp(20.0, 20000.0);
+        }
+    }
+
+    /// Set the second filter's response (LP/HP/BP)
+    pub fn set_filter2_type(&mut self, filter_type: FilterType) {
+        for voice in &mut self.voices {
+            voice.filter2.filter_type = filter_type;
         }
     }
 
@@ -374,6 +907,34 @@ impl VoiceManager {
         }
     }
 
+    /// Freeze every voice's filter envelope at its current level (false resumes it)
+    pub fn set_filter_env_hold(&mut self, hold: bool) {
+        for voice in &mut self.voices {
+            voice.filter_env_hold = hold;
+        }
+    }
+
+    /// Let the filter envelope cycle attack/decay as a free-running modulation source
+    pub fn set_filter_env_loop(&mut self, loop_mode: EnvelopeLoop) {
+        for voice in &mut self.voices {
+            voice.filter_env.loop_mode = loop_mode;
+        }
+    }
+
+    /// Open the filter in a short blip on note-off instead of only closing it
+    pub fn set_filter_release_blip(&mut self, enabled: bool) {
+        for voice in &mut self.voices {
+            voice.filter_release_blip = enabled;
+        }
+    }
+
+    /// Highest cutoff the filter envelope sweeps to in Hz (never above 0.45 x sample rate)
+    pub fn set_filter_env_ceiling(&mut self, cutoff: f32) {
+        for voice in &mut self.voices {
+            voice.filter_env_ceiling = cutoff.clamp(20.0, 20000.0);
+        }
+    }
+
     pub fn set_amp_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
         for voice in &mut self.voices {
             voice.amp_env.attack = attack;
@@ -383,6 +944,28 @@ impl VoiceManager {
         }
     }
 
+    /// Time in seconds between note-on and the amp envelope's attack
+    pub fn set_amp_delay(&mut self, seconds: f32) {
+        for voice in &mut self.voices {
+            voice.amp_env.delay = seconds.max(0.0);
+        }
+    }
+
+    /// Shape of the amp envelope segments
+    pub fn set_amp_curve(&mut self, curve: EnvelopeCurve) {
+        for voice in &mut self.voices {
+            voice.amp_env.curve = curve;
+        }
+    }
+
+    /// Cap both envelopes' release time in seconds, so long releases can't hog voices
+    pub fn set_max_release(&mut self, seconds: f32) {
+        for voice in &mut self.voices {
+            voice.amp_env.max_release = seconds.max(0.0);
+            voice.filter_env.max_release = seconds.max(0.0);
+        }
+    }
+
     pub fn set_filter_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
         for voice in &mut self.voices {
             voice.filter_env.attack = attack;
@@ -404,12 +987,9 @@ impl VoiceManager {
     pub fn set_fm_ratio(&mut self, ratio: f32) {
         for voice in &mut self.voices {
             voice.fm_ratio = ratio.clamp(0.25, 8.0);
-            // Update frequency for active voices
-            if voice.active {
-                let freq = midi_to_freq(voice.note);
-                voice.osc2.set_frequency(freq * ratio);
-            }
         }
+        // Update frequency for active voices
+        self.update_voice_frequencies();
     }
 
     // === Juno-6 style PWM ===
@@ -418,21 +998,29 @@ impl VoiceManager {
     pub fn set_pulse_width(&mut self, width: f32) {
         let clamped = width.clamp(0.01, 0.99);
         for voice in &mut self.voices {
+            voice.pulse_width = clamped;
             voice.osc1.set_pulse_width(clamped);
             voice.osc2.set_pulse_width(clamped);
         }
     }
 
-    /// Set PWM LFO modulation depth (0.0 - 1.0)
-    pub fn set_pwm_depth(&mut self, _depth: f32) {
-        // TODO: Implement PWM LFO modulation in Voice tick()
-        // For now, this is a placeholder - actual PWM modulation
-        // would require an LFO per voice or global LFO
+    /// Set PWM LFO modulation depth (0.0 - 1.0); 0 returns to the plain pulse width
+    pub fn set_pwm_depth(&mut self, depth: f32) {
+        let depth = depth.clamp(0.0, 1.0);
+        for voice in &mut self.voices {
+            voice.pwm_depth = depth;
+            if depth == 0.0 {
+                voice.osc1.set_pulse_width(voice.pulse_width);
+                voice.osc2.set_pulse_width(voice.pulse_width);
+            }
+        }
     }
 
     /// Set PWM LFO rate in Hz
-    pub fn set_pwm_rate(&mut self, _rate: f32) {
-        // TODO: Implement PWM LFO rate
+    pub fn set_pwm_rate(&mut self, rate: f32) {
+        for voice in &mut self.voices {
+            voice.pwm_lfo.set_frequency(rate.clamp(0.01, 20.0));
+        }
     }
 
     // === Juno-6 style Sub oscillator ===
@@ -447,19 +1035,44 @@ impl VoiceManager {
         }
     }
 
+    /// Keep the sub oscillator phase running across note_ons
+    pub fn set_sub_free_phase(&mut self, free: bool) {
+        for voice in &mut self.voices {
+            voice.sub_free_phase = free;
+        }
+    }
+
+    /// Route the sub oscillator through the filter (true) or around it (false)
+    pub fn set_sub_pre_filter(&mut self, pre: bool) {
+        for voice in &mut self.voices {
+            voice.sub_pre_filter = pre;
+        }
+    }
+
+    /// Route the noise through the filter (true) or around it (false)
+    pub fn set_noise_pre_filter(&mut self, pre: bool) {
+        for voice in &mut self.voices {
+            voice.noise_pre_filter = pre;
+        }
+    }
+
     /// Set sub oscillator octave (-1 or -2)
     pub fn set_sub_octave(&mut self, octave: i8) {
-        let _clamped = octave.clamp(-2, -1);
-        // TODO: Store octave setting and apply in note_on/update_voice_frequencies
-        // For now, sub oscillator is always -1 octave (0.5 frequency multiplier)
+        for voice in &mut self.voices {
+            voice.sub_octave = octave.clamp(-2, -1);
+        }
+        self.update_voice_frequencies();
     }
 
     // === Juno-6 style HPF ===
 
     /// Set high-pass filter cutoff (20-2000 Hz, non-resonant)
-    pub fn set_hpf_cutoff(&mut self, _cutoff: f32) {
-        // TODO: Implement HPF in voice signal chain before LPF
-        // Would require adding an HPF filter to Voice struct
+    /// The 20 Hz minimum bypasses the filter, leaving the signal untouched
+    pub fn set_hpf_cutoff(&mut self, cutoff: f32) {
+        let cutoff = cutoff.clamp(HPF_MIN_CUTOFF, 2000.0);
+        for voice in &mut self.voices {
+            voice.hpf.set_cutoff(if cutoff <= HPF_MIN_CUTOFF { 0.0 } else { cutoff });
+        }
     }
 
     /// Set pitch bend value (-1 to 1, where 1 = +pitch_bend_range semitones)
@@ -468,6 +1081,15 @@ impl VoiceManager {
         self.update_voice_frequencies();
     }
 
+    /// Reference pitch, transpose, fine tune and scale for MIDI notes (from the next note_on)
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
     /// Set pitch bend range in semitones (typically 2, 12, or 24)
     pub fn set_pitch_bend_range(&mut self, semitones: f32) {
         self.pitch_bend_range = semitones.clamp(0.0, 48.0);
@@ -475,14 +1097,10 @@ impl VoiceManager {
 
     /// Update frequencies for all active voices (called when pitch bend changes)
     fn update_voice_frequencies(&mut self) {
-        let bend_multiplier = (2.0_f32).powf(self.pitch_bend / 12.0);
+        let bend_multiplier = self.pitch_bend_multiplier();
         for voice in &mut self.voices {
             if voice.active {
-                let base_freq = midi_to_freq(voice.note);
-                let bent_freq = base_freq * bend_multiplier;
-                voice.osc1.set_frequency(bent_freq);
-                voice.osc2.set_frequency(bent_freq * voice.fm_ratio);
-                voice.sub_osc.set_frequency(bent_freq * 0.5);
+                voice.update_frequencies(bend_multiplier);
             }
         }
     }
@@ -492,6 +1110,15 @@ impl VoiceManager {
         (2.0_f32).powf(self.pitch_bend / 12.0)
     }
 
+    /// Pan each voice by its note (low left, high right); negative reverses (-1.0 - 1.0)
+    pub fn set_key_pan_amount(&mut self, amount: f32) {
+        self.key_pan_amount = amount.clamp(-1.0, 1.0);
+    }
+
+    pub fn key_pan_amount(&self) -> f32 {
+        self.key_pan_amount
+    }
+
     /// Get mutable access to voices for processing
     pub fn voices_mut(&mut self) -> &mut [Voice] {
         &mut self.voices
@@ -529,4 +1156,567 @@ mod tests {
         vm.panic();
         assert_eq!(vm.active_voice_count(), 0);
     }
+
+    #[test]
+    fn test_self_oscillation_release_fade() {
+        let mut voice = Voice::new(44100.0);
+        voice.filter.set_resonance(1.0);
+        voice.filter_env_amount = 0.0;
+        voice.amp_env.attack = 0.001;
+        voice.amp_env.sustain = 1.0;
+        voice.amp_env.release = 0.0005;
+        voice.note_on(36, 1.0);
+
+        let mut prev = 0.0;
+        let mut sustain_jump: f32 = 0.0;
+        for _ in 0..8820 {
+            let sample = voice.tick(100.0);
+            sustain_jump = sustain_jump.max((sample - prev).abs());
+            prev = sample;
+        }
+
+        voice.note_off();
+        let mut release_jump: f32 = 0.0;
+        for _ in 0..441 {
+            let sample = voice.tick(100.0);
+            release_jump = release_jump.max((sample - prev).abs());
+            prev = sample;
+        }
+        assert!(release_jump <= sustain_jump * 1.1, "{} vs {}", release_jump, sustain_jump);
+    }
+
+    #[test]
+    fn test_humanize_detune() {
+        let mut vm = VoiceManager::new(1, 44100.0);
+        vm.note_on(69, 1.0);
+        assert_eq!(vm.voices_mut()[0].osc1.frequency, 440.0);
+
+        vm.set_humanize_detune(5.0);
+        let mut freqs = Vec::new();
+        for _ in 0..2 {
+            vm.panic();
+            vm.note_on(69, 1.0);
+            freqs.push(vm.voices_mut()[0].osc1.frequency);
+        }
+        assert_ne!(freqs[0], freqs[1]);
+        for freq in freqs {
+            let cents = 1200.0 * (freq / 440.0).log2();
+            assert!(cents.abs() <= 5.0 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_deterministic_humanize_varies_within_chord() {
+        let mut vm = VoiceManager::new(2, 44100.0);
+        vm.set_humanize_detune(5.0);
+        vm.set_deterministic(true);
+        vm.note_on(69, 1.0);
+        vm.note_on(69 + 12, 1.0);
+        let voices = vm.voices_mut();
+        assert_ne!(voices[0].osc1.frequency * 2.0, voices[1].osc1.frequency);
+    }
+
+    #[test]
+    fn test_long_bend_hold_keeps_pitch() {
+        let mut manager = VoiceManager::new(1, 44100.0);
+        manager.note_on(69, 1.0);
+
+        // Ten minutes of per-block bend updates (64-sample blocks)
+        let blocks = (600.0 * 44100.0 / 64.0) as usize;
+        for i in 0..blocks {
+            manager.set_pitch_bend((i as f32 * 0.01).sin());
+        }
+        manager.set_pitch_bend(0.0);
+
+        let freq = manager.voices[0].osc1.frequency;
+        let cents = 1200.0 * (freq / 440.0).log2();
+        assert!(cents.abs() < 1.0, "drifted {} cents", cents);
+    }
+
+    #[test]
+    fn test_note_on_freq() {
+        let mut manager = VoiceManager::new(2, 44100.0);
+        // A quarter-tone above A4, not reachable from any MIDI note
+        let freq = 440.0 * 2.0_f32.powf(0.5 / 12.0);
+        let id = manager.note_on_freq(freq, 1.0);
+
+        let voice = manager.voices.iter().find(|v| v.id == Some(id)).unwrap();
+        assert_eq!(voice.osc1.frequency, freq);
+
+        // MIDI note-off for the nearest note leaves it alone
+        manager.note_off(voice.note);
+        let voice = manager.voices.iter().find(|v| v.id == Some(id)).unwrap();
+        assert_ne!(voice.amp_env.stage(), crate::envelope::EnvelopeStage::Release);
+
+        manager.note_off_id(id);
+        let voice = manager.voices.iter().find(|v| v.id == Some(id)).unwrap();
+        assert_eq!(voice.amp_env.stage(), crate::envelope::EnvelopeStage::Release);
+    }
+
+    #[test]
+    fn test_sub_free_phase() {
+        let mut voice = Voice::new(44100.0);
+        voice.sub_free_phase = true;
+        voice.note_on(45, 1.0);
+        for _ in 0..1000 {
+            voice.tick(5000.0);
+        }
+        let sub_phase = voice.sub_osc.phase;
+        assert!(sub_phase > 0.0);
+
+        voice.note_on(45, 1.0);
+        assert_eq!(voice.sub_osc.phase, sub_phase);
+        assert_eq!(voice.osc1.phase, 0.0);
+
+        voice.sub_free_phase = false;
+        voice.note_on(45, 1.0);
+        assert_eq!(voice.sub_osc.phase, 0.0);
+    }
+
+    #[test]
+    fn test_sub_post_filter_bypasses_closed_filter() {
+        let rms = |pre_filter: bool, cutoff: f32| {
+            let mut voice = Voice::new(44100.0);
+            voice.osc1_level = 0.0;
+            voice.sub_level = 1.0;
+            voice.sub_osc.waveform = Waveform::Sine;
+            voice.filter_env_amount = 0.0;
+            voice.filter.resonance = 0.0;
+            voice.sub_pre_filter = pre_filter;
+            voice.note_on(45, 1.0);
+            let out: Vec<f32> = (0..13230).map(|_| voice.tick(cutoff)).collect();
+            (out[4410..].iter().map(|s| s * s).sum::<f32>() / 8820.0).sqrt()
+        };
+
+        // Through a 20 Hz cutoff the 55 Hz sub is mostly gone
+        let open = rms(true, 20000.0);
+        let closed = rms(true, 20.0);
+        assert!(closed < open * 0.5, "{} vs {}", closed, open);
+
+        // Routed around the filter the cutoff makes no difference
+        let bypassed = rms(false, 20.0);
+        assert_eq!(bypassed, rms(false, 20000.0));
+        assert!(bypassed > closed * 2.0, "{} vs {}", bypassed, closed);
+    }
+
+    #[test]
+    fn test_filter_env_hold() {
+        let mut manager = VoiceManager::new(1, 44100.0);
+        manager.set_filter_env_amount(1.0);
+        manager.set_filter_envelope(0.001, 1.0, 0.0, 0.5);
+        manager.note_on(48, 1.0);
+        let mut tick = |manager: &mut VoiceManager| {
+            manager.voices[0].tick(200.0);
+            manager.voices[0].filter.cutoff
+        };
+
+        // Mid-decay the cutoff is still falling
+        for _ in 0..8820 {
+            tick(&mut manager);
+        }
+        let falling = tick(&mut manager);
+        assert!(tick(&mut manager) < falling);
+
+        manager.set_filter_env_hold(true);
+        let held = tick(&mut manager);
+        for _ in 0..22050 {
+            assert_eq!(tick(&mut manager), held);
+        }
+
+        manager.set_filter_env_hold(false);
+        for _ in 0..100 {
+            tick(&mut manager);
+        }
+        assert!(tick(&mut manager) < held);
+    }
+
+    #[test]
+    fn test_key_range() {
+        let mut manager = VoiceManager::new(4, 44100.0);
+        manager.note_on(40, 1.0);
+        manager.set_key_range(48, 72);
+        manager.note_on(47, 1.0);
+        manager.note_on(73, 1.0);
+        assert_eq!(manager.active_voice_count(), 1);
+        manager.note_on(48, 1.0);
+        manager.note_on(72, 1.0);
+        assert_eq!(manager.active_voice_count(), 3);
+
+        // A note from before the split still releases
+        manager.note_off(40);
+        assert!(manager.voices.iter().any(|v| v.note == 40 && v.amp_env.stage() == EnvelopeStage::Release));
+    }
+
+    #[test]
+    fn test_latch() {
+        let mut manager = VoiceManager::new(2, 44100.0);
+        manager.set_latch(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        assert!(manager.voices[0].active);
+        assert!(!manager.voices[0].is_released());
+
+        // Pressing the latched note again releases it instead of retriggering
+        manager.note_on(60, 1.0);
+        assert!(manager.voices[0].is_released());
+        assert_eq!(manager.active_voice_count(), 1);
+
+        // Turning latch off releases whatever is still latched
+        manager.note_on(64, 1.0);
+        manager.set_latch(false);
+        assert!(manager.voices.iter().all(|v| v.is_released()));
+    }
+
+    #[test]
+    fn test_voice_states() {
+        let mut manager = VoiceManager::new(4, 44100.0);
+        assert!(manager.voice_states().is_empty());
+
+        manager.note_on(60, 1.0);
+        manager.voices[0].tick(5000.0);
+        let states = manager.voice_states();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].note, 60);
+        assert_eq!(states[0].stage, EnvelopeStage::Attack);
+
+        manager.note_off(60);
+        assert_eq!(manager.voice_states()[0].stage, EnvelopeStage::Release);
+    }
+
+    #[test]
+    fn test_filter_routing() {
+        // Sine through 1-pole filters at 1 kHz, so the responses are textbook
+        let voice_with = |routing: FilterRouting, type1: FilterType| {
+            let mut voice = Voice::new(44100.0);
+            voice.osc1.waveform = Waveform::Sine;
+            for filter in [&mut voice.filter, &mut voice.filter2] {
+                filter.set_slope(crate::filter::FilterSlope::Pole1);
+                filter.mode = LadderMode::Clean;
+            }
+            voice.filter.filter_type = type1;
+            voice.filter2_cutoff = 1000.0;
+            voice.filter_routing = routing;
+            voice.filter_env_amount = 0.0;
+            voice.amp_env.attack = 0.0;
+            voice.amp_env.sustain = 1.0;
+            voice
+        };
+        let render = |mut voice: Voice, note: u8| {
+            voice.note_on(note, 1.0);
+            (0..8820).map(|_| voice.tick(1000.0)).skip(4410).collect::<Vec<f32>>()
+        };
+        let rms = |buf: &[f32]| (buf.iter().map(|s| s * s).sum::<f32>() / buf.len() as f32).sqrt();
+
+        // Series LP -> HP passes around 1 kHz and cuts both ends
+        let band = |note| rms(&render(voice_with(FilterRouting::Series, FilterType::LowPass), note));
+        let lp = |note| rms(&render(voice_with(FilterRouting::Single, FilterType::LowPass), note));
+        assert!(band(33) < lp(33) * 0.3, "low end not cut");
+        assert!(band(84) > band(33) * 3.0 && band(84) > band(120) * 3.0);
+
+        // Parallel is the sum of the two filters on their own
+        let parallel = render(voice_with(FilterRouting::Parallel, FilterType::LowPass), 57);
+        let lp_only = render(voice_with(FilterRouting::Single, FilterType::LowPass), 57);
+        let hp_only = render(voice_with(FilterRouting::Single, FilterType::HighPass), 57);
+        for i in 0..parallel.len() {
+            assert!((parallel[i] - (lp_only[i] + hp_only[i])).abs() < 1e-5);
+        }
+
+        // The resonance knob drives both filters
+        let mut manager = VoiceManager::new(2, 44100.0);
+        manager.set_filter_resonance(0.8);
+        for voice in manager.voices_mut() {
+            assert!(voice.filter2.resonance > 0.0);
+            assert_eq!(voice.filter2.resonance, voice.filter.resonance);
+        }
+    }
+
+    #[test]
+    fn test_tick_with_cutoff_mod() {
+        let voice_at = || {
+            let mut voice = Voice::new(44100.0);
+            voice.osc1.waveform = Waveform::Saw;
+            voice.filter_env_amount = 0.0;
+            voice.amp_env.sustain = 1.0;
+            voice.note_on(45, 1.0);
+            voice
+        };
+
+        // Zero modulation is the plain tick
+        let (mut a, mut b) = (voice_at(), voice_at());
+        for _ in 0..2000 {
+            assert_eq!(a.tick(500.0), b.tick_with_cutoff_mod(500.0, 0.0));
+        }
+
+        // Modulation switched on mid-buffer opens the filter from that sample on
+        let (mut plain, mut modulated) = (voice_at(), voice_at());
+        let mut diverged_at = None;
+        let (mut plain_hf, mut mod_hf) = (0.0, 0.0);
+        let (mut plain_prev, mut mod_prev) = (0.0, 0.0);
+        for i in 0..8820 {
+            let cutoff_mod = if i < 4410 { 0.0 } else { 4000.0 };
+            let p = plain.tick(500.0);
+            let m = modulated.tick_with_cutoff_mod(500.0, cutoff_mod);
+            if diverged_at.is_none() && p != m {
+                diverged_at = Some(i);
+            }
+            if i >= 4410 + 441 {
+                // First difference as a crude high-frequency energy measure
+                plain_hf += (p - plain_prev) * (p - plain_prev);
+                mod_hf += (m - mod_prev) * (m - mod_prev);
+            }
+            plain_prev = p;
+            mod_prev = m;
+        }
+        assert_eq!(diverged_at, Some(4410));
+        assert!(mod_hf > plain_hf * 2.0, "{} {}", plain_hf, mod_hf);
+    }
+
+    #[test]
+    fn test_pedal_stacks_repeated_notes() {
+        let mut manager = VoiceManager::new(4, 44100.0);
+        let sounding = |m: &VoiceManager| m.voices.iter().filter(|v| v.active && v.note == 60).count();
+
+        manager.set_sustain_pedal(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        manager.note_on(60, 1.0);
+        assert_eq!(sounding(&manager), 2);
+        assert_eq!(manager.voice_states().iter().filter(|s| s.stage != EnvelopeStage::Release).count(), 2);
+
+        // Lifting the pedal releases only the key that is up
+        manager.set_sustain_pedal(false);
+        assert_eq!(manager.voice_states().iter().filter(|s| s.stage == EnvelopeStage::Release).count(), 1);
+
+        // With stacking off, the repeat retriggers the held voice
+        let mut manager = VoiceManager::new(4, 44100.0);
+        manager.set_pedal_stacking(false);
+        manager.set_sustain_pedal(true);
+        manager.note_on(60, 1.0);
+        manager.note_off(60);
+        manager.note_on(60, 1.0);
+        assert_eq!(sounding(&manager), 1);
+    }
+
+    #[test]
+    fn test_filter_env_ceiling_follows_sample_rate() {
+        let sample_rate = 22050.0;
+        let mut voice = Voice::new(sample_rate);
+        voice.filter_env_amount = 1.0;
+        voice.filter_env.attack = 0.1;
+        voice.filter_env.sustain = 1.0;
+        voice.note_on(48, 1.0);
+
+        let attack = (0.1 * sample_rate) as usize;
+        let cutoffs: Vec<f32> = (0..attack)
+            .map(|_| {
+                voice.tick(1000.0);
+                voice.filter.cutoff
+            })
+            .collect();
+        let top = cutoffs.iter().cloned().fold(0.0, f32::max);
+        assert!(top <= sample_rate * 0.5, "{}", top);
+        assert!(top > 8000.0, "{}", top);
+
+        // The sweep keeps rising through the attack instead of pinning at the clamp
+        let pinned = cutoffs.iter().filter(|&&c| c >= top * 0.99).count();
+        assert!(pinned < attack / 10, "{} of {}", pinned, attack);
+    }
+
+    #[test]
+    fn test_voice_ages_follow_note_on_order() {
+        let mut manager = VoiceManager::new(4, 44100.0);
+        for (note, velocity) in [(60, 0.5), (64, 0.7), (67, 0.9)] {
+            manager.note_on(note, velocity);
+            for _ in 0..100 {
+                for voice in &mut manager.voices {
+                    voice.tick(5000.0);
+                }
+            }
+        }
+
+        let mut states = manager.voice_states();
+        states.sort_by_key(|s| std::cmp::Reverse(s.age));
+        let order: Vec<(u8, f32, u64)> = states.iter().map(|s| (s.note, s.velocity, s.age)).collect();
+        assert_eq!(order, vec![(60, 0.5, 300), (64, 0.7, 200), (67, 0.9, 100)]);
+    }
+
+    #[test]
+    fn test_pwm_lfo() {
+        let render = |setup: &dyn Fn(&mut VoiceManager)| {
+            let mut manager = VoiceManager::new(1, 44100.0);
+            manager.set_osc1_waveform(Waveform::Square);
+            setup(&mut manager);
+            manager.note_on(48, 1.0);
+            let mut widths = Vec::new();
+            let output: Vec<f32> = (0..8820)
+                .map(|_| {
+                    let sample = manager.voices[0].tick(5000.0);
+                    widths.push(manager.voices[0].osc1.pulse_width);
+                    sample
+                })
+                .collect();
+            (output, widths)
+        };
+
+        // Zero depth renders exactly as before, even after PWM was used
+        let (plain, _) = render(&|_| {});
+        let (zeroed, _) = render(&|m| {
+            m.set_pwm_rate(3.0);
+            m.set_pwm_depth(0.5);
+            m.set_pwm_depth(0.0);
+        });
+        assert_eq!(plain, zeroed);
+
+        // Full depth sweeps the width across nearly the whole range
+        let (modulated, widths) = render(&|m| {
+            m.set_pwm_rate(5.0);
+            m.set_pwm_depth(1.0);
+        });
+        assert_ne!(modulated, plain);
+        let min = widths.iter().cloned().fold(f32::MAX, f32::min);
+        let max = widths.iter().cloned().fold(f32::MIN, f32::max);
+        assert!(min < 0.05 && max > 0.95, "width {} - {}", min, max);
+    }
+
+    #[test]
+    fn test_sub_octave() {
+        let mut manager = VoiceManager::new(1, 44100.0);
+        manager.note_on(60, 1.0);
+        let one_down = manager.voices[0].sub_osc.frequency;
+        assert!((one_down - midi_to_freq(60) * 0.5).abs() < 1e-3);
+
+        // Applies to the sounding note
+        manager.set_sub_octave(-2);
+        let two_down = manager.voices[0].sub_osc.frequency;
+        assert!((one_down / two_down - 2.0).abs() < 1e-5, "{} / {}", one_down, two_down);
+
+        manager.set_sub_octave(-5);
+        assert_eq!(manager.voices[0].sub_octave, -2);
+    }
+
+    #[test]
+    fn test_hpf_removes_dc() {
+        // A 1% pulse is almost pure DC
+        let mean = |cutoff: f32| {
+            let mut manager = VoiceManager::new(1, 44100.0);
+            manager.set_osc1_waveform(Waveform::Square);
+            manager.set_pulse_width(0.01);
+            manager.set_hpf_cutoff(cutoff);
+            manager.set_amp_envelope(0.001, 0.1, 1.0, 0.1);
+            manager.note_on(45, 1.0);
+            let voice = &mut manager.voices[0];
+            let output: Vec<f32> = (0..22050).map(|_| voice.tick(18000.0)).collect();
+            output[11025..].iter().sum::<f32>() / 11025.0
+        };
+
+        let open = mean(20.0);
+        let thinned = mean(2000.0);
+        assert!(open.abs() > 0.3, "bypassed mean {}", open);
+        assert!(thinned.abs() < open.abs() * 0.01, "filtered mean {}", thinned);
+    }
+
+    #[test]
+    fn test_filter_release_blip() {
+        let release_cutoffs = |blip: bool| {
+            let mut manager = VoiceManager::new(1, 44100.0);
+            manager.set_filter_env_amount(1.0);
+            manager.set_filter_envelope(0.001, 0.05, 0.2, 0.3);
+            manager.set_filter_release_blip(blip);
+            manager.note_on(48, 1.0);
+            for _ in 0..4410 {
+                manager.voices[0].tick(200.0);
+            }
+            manager.note_off(48);
+            (0..13230)
+                .map(|_| {
+                    manager.voices[0].tick(200.0);
+                    manager.voices[0].filter.cutoff
+                })
+                .collect::<Vec<f32>>()
+        };
+
+        let closing = release_cutoffs(false);
+        let blip = release_cutoffs(true);
+        // The standard release only closes; the blip first jumps well above it
+        assert!(closing[1] <= closing[0]);
+        assert!(blip[0] > closing[0] * 2.0, "{} vs {}", blip[0], closing[0]);
+        // ...and settles back onto the same trajectory
+        assert!((blip[13229] - closing[13229]).abs() < 1.0, "{} vs {}", blip[13229], closing[13229]);
+    }
+
+    #[test]
+    fn test_steal_order_protects_pedaled_notes() {
+        let mut vm = VoiceManager::new(4, 44100.0);
+        for voice in vm.voices_mut() {
+            voice.amp_env.release = 5.0;
+        }
+        let advance = |vm: &mut VoiceManager| {
+            for voice in vm.voices_mut() {
+                for _ in 0..100 {
+                    voice.tick(1000.0);
+                }
+            }
+        };
+        let notes = |vm: &VoiceManager| {
+            let mut notes: Vec<u8> = vm.voice_states().iter().map(|s| s.note).collect();
+            notes.sort();
+            notes
+        };
+
+        vm.set_sustain_pedal(true);
+        for note in [60, 62] {
+            vm.note_on(note, 0.8);
+            advance(&mut vm);
+            vm.note_off(note);
+        }
+        // Frequency notes ignore the pedal: this one is freely released (and the youngest)
+        let id = vm.note_on_freq(500.0, 0.8);
+        advance(&mut vm);
+        vm.note_off_id(id);
+        let released = vm.voice_states().iter().find(|s| s.note != 60 && s.note != 62).unwrap().note;
+
+        // A held note takes the free voice, then the released one
+        vm.note_on(70, 0.8);
+        advance(&mut vm);
+        assert_eq!(notes(&vm), {
+            let mut expected = vec![60, 62, 70, released];
+            expected.sort();
+            expected
+        });
+        vm.note_on(72, 0.8);
+        advance(&mut vm);
+        assert_eq!(notes(&vm), vec![60, 62, 70, 72]);
+
+        // Then pedaled notes, oldest first, and held keys last
+        vm.note_on(74, 0.8);
+        advance(&mut vm);
+        assert_eq!(notes(&vm), vec![62, 70, 72, 74]);
+        vm.note_on(76, 0.8);
+        advance(&mut vm);
+        assert_eq!(notes(&vm), vec![70, 72, 74, 76]);
+        vm.note_on(77, 0.8);
+        assert_eq!(notes(&vm), vec![72, 74, 76, 77]);
+    }
+
+    #[test]
+    fn test_note_on_returns_voice_index() {
+        let mut vm = VoiceManager::new(2, 44100.0);
+        assert_eq!(vm.note_on(60, 0.8), Some(0));
+        assert_eq!(vm.note_on(64, 0.8), Some(1));
+        assert_eq!(vm.voices_mut()[1].note, 64);
+
+        // A full pool steals: the index returned is the stolen voice, now playing the new note
+        for _ in 0..100 {
+            vm.voices_mut()[0].tick(1000.0);
+        }
+        let stolen = vm.note_on(67, 0.8).unwrap();
+        assert_eq!(stolen, 0);
+        assert_eq!(vm.voices_mut()[stolen].note, 67);
+
+        // Retriggering a sounding note reuses its voice; notes that start nothing give None
+        assert_eq!(vm.note_on(64, 0.8), Some(1));
+        assert_eq!(vm.note_on(64, 0.0), None);
+        vm.set_key_range(0, 100);
+        assert_eq!(vm.note_on(110, 0.8), None);
+    }
 }
@@ -13,6 +13,7 @@ extern "C" {
 /* Opaque handles */
 typedef void* SubSynthHandle;
 typedef void* FmSynthHandle;
+typedef void* Dx7CartridgeHandle;
 
 /* ============================================================================
    SUBTRACTIVE SYNTH
@@ -23,11 +24,19 @@ void sub_synth_destroy(SubSynthHandle handle);
 void sub_synth_set_sample_rate(SubSynthHandle handle, float sample_rate);
 void sub_synth_note_on(SubSynthHandle handle, uint8_t note, float velocity);
 void sub_synth_note_off(SubSynthHandle handle, uint8_t note);
+uint32_t sub_synth_note_on_freq(SubSynthHandle handle, float freq, float velocity);  /* returns an id for note_off_id */
+void sub_synth_note_off_id(SubSynthHandle handle, uint32_t id);
 void sub_synth_all_notes_off(SubSynthHandle handle);
+void sub_synth_reset(SubSynthHandle handle);  /* clear DSP state, keep parameters */
 void sub_synth_process(SubSynthHandle handle, float* left, float* right, size_t num_samples);
 
+/* Metering (last processed block; clip latches until read) */
+float sub_synth_get_peak(SubSynthHandle handle);
+float sub_synth_get_rms(SubSynthHandle handle);
+bool sub_synth_get_clip(SubSynthHandle handle);
+
 /* Oscillators */
-void sub_synth_set_osc1_waveform(SubSynthHandle handle, int32_t value);  /* 0=Saw, 1=Square, 2=Triangle, 3=Sine */
+void sub_synth_set_osc1_waveform(SubSynthHandle handle, int32_t value);  /* 0=Saw, 1=Square, 2=Triangle, 3=Sine, 4=BLIT */
 void sub_synth_set_osc1_level(SubSynthHandle handle, float value);
 void sub_synth_set_osc2_waveform(SubSynthHandle handle, int32_t value);
 void sub_synth_set_osc2_level(SubSynthHandle handle, float value);
@@ -52,7 +61,7 @@ void sub_synth_set_fm_ratio(SubSynthHandle handle, float value);
 
 /* Filter */
 void sub_synth_set_filter_cutoff(SubSynthHandle handle, float value);
-void sub_synth_set_filter_resonance(SubSynthHandle handle, float value);
+void sub_synth_set_filter_resonance(SubSynthHandle handle, float value);  /* knob 0-1; resonance = 1 - (1 - value)^2 */
 void sub_synth_set_filter_slope(SubSynthHandle handle, int32_t value);  /* 0=6dB, 1=12dB, 2=24dB */
 void sub_synth_set_filter_env_amount(SubSynthHandle handle, float value);
 void sub_synth_set_hpf_cutoff(SubSynthHandle handle, float value);
@@ -63,6 +72,10 @@ void sub_synth_set_filter_adsr(SubSynthHandle handle, float a, float d, float s,
 
 /* Master */
 void sub_synth_set_master_volume(SubSynthHandle handle, float value);
+void sub_synth_set_master_volume_db(SubSynthHandle handle, float db);  /* 0 dB = unity (max), ramped */
+void sub_synth_set_instance_seed(SubSynthHandle handle, uint32_t seed);  /* decorrelate layered instances */
+void sub_synth_set_master_pan(SubSynthHandle handle, float value);  /* -1=left, 0=centre, 1=right */
+void sub_synth_set_quality(SubSynthHandle handle, int32_t value);  /* 0=Draft, 1=Normal, 2=High, 3=Ultra */
 void sub_synth_set_pitch_bend(SubSynthHandle handle, float semitones);
 
 /* ============================================================================
@@ -73,16 +86,27 @@ FmSynthHandle fm_synth_create(float sample_rate);
 void fm_synth_destroy(FmSynthHandle handle);
 void fm_synth_note_on(FmSynthHandle handle, uint8_t note, float velocity);
 void fm_synth_note_off(FmSynthHandle handle, uint8_t note);
+uint32_t fm_synth_note_on_freq(FmSynthHandle handle, float freq, float velocity);  /* returns an id for note_off_id */
+void fm_synth_note_off_id(FmSynthHandle handle, uint32_t id);
 void fm_synth_all_notes_off(FmSynthHandle handle);
+void fm_synth_reset(FmSynthHandle handle);  /* clear DSP state, keep the patch */
 void fm_synth_process(FmSynthHandle handle, float* left, float* right, size_t num_samples);
 
+/* Metering (last processed block; clip latches until read) */
+float fm_synth_get_peak(FmSynthHandle handle);
+float fm_synth_get_rms(FmSynthHandle handle);
+bool fm_synth_get_clip(FmSynthHandle handle);
+
 /* Algorithm (0-31, 32 classic FM algorithms) */
 void fm_synth_set_algorithm(FmSynthHandle handle, int32_t value);
+uint8_t fm_synth_get_carrier_mask(FmSynthHandle handle);  /* bit 0 = OP1 */
 
 /* Per-operator parameters (op = 0-5) */
 void fm_synth_set_op_ratio(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_level(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_detune(FmSynthHandle handle, int32_t op, float value);
+void fm_synth_set_op_fixed_freq(FmSynthHandle handle, int32_t op, float value);  /* Hz, negative = track the note */
+void fm_synth_set_op_rate_scaling(FmSynthHandle handle, int32_t op, float value);  /* 0-1, shorter envelopes up the keyboard */
 void fm_synth_set_op_feedback(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_velocity_sens(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_attack(FmSynthHandle handle, int32_t op, float value);
@@ -90,6 +114,21 @@ void fm_synth_set_op_decay(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_sustain(FmSynthHandle handle, int32_t op, float value);
 void fm_synth_set_op_release(FmSynthHandle handle, int32_t op, float value);
 
+/* All nine operator settings in one call */
+typedef struct FmOperatorConfig {
+    float ratio;
+    float level;
+    float detune;
+    float feedback;
+    float velocity_sens;
+    float attack;
+    float decay;
+    float sustain;
+    float release;
+} FmOperatorConfig;
+
+void fm_synth_set_operator(FmSynthHandle handle, int32_t op, const FmOperatorConfig* config);
+
 /* Filter */
 void fm_synth_set_filter_enabled(FmSynthHandle handle, bool enabled);
 void fm_synth_set_filter_cutoff(FmSynthHandle handle, float value);
@@ -101,6 +140,38 @@ void fm_synth_set_vibrato_rate(FmSynthHandle handle, float value);
 
 /* Master */
 void fm_synth_set_master_volume(FmSynthHandle handle, float value);
+void fm_synth_set_master_volume_db(FmSynthHandle handle, float db);  /* 0 dB = unity (max), ramped */
+void fm_synth_set_instance_seed(FmSynthHandle handle, uint32_t seed);  /* decorrelate layered instances */
+void fm_synth_set_panic_fade_ms(FmSynthHandle handle, float ms);  /* fade before all_notes_off stops voices, 0 = instant */
+void fm_synth_set_master_pan(FmSynthHandle handle, float value);  /* -1=left, 0=centre, 1=right */
+void fm_synth_set_quality(FmSynthHandle handle, int32_t value);  /* 0=Draft, 1=Normal, 2=High, 3=Ultra */
+
+/* ============================================================================
+   DX7 CARTRIDGES
+   ============================================================================ */
+
+/* index: voice 0-31. Errors are negative: */
+#define DX7_ERROR_INVALID_DATA  (-1)
+#define DX7_ERROR_INVALID_INDEX (-2)
+#define DX7_ERROR_NULL_HANDLE   (-3)
+
+/* Parse a 32-voice bank once, as a .syx file (4104 bytes) or raw (4096 bytes),
+   into *out; returns 0 on success. Free it with dx7_cartridge_destroy. */
+int32_t dx7_cartridge_load(const uint8_t* data, size_t len, Dx7CartridgeHandle* out);
+void dx7_cartridge_destroy(Dx7CartridgeHandle cartridge);
+/* Voice name, NUL-terminated (same buffer rules as the schema functions) */
+int32_t dx7_cartridge_voice_name(Dx7CartridgeHandle cartridge, int32_t index, char* buffer, size_t capacity);
+/* Apply a voice's algorithm and operators; returns 0 on success */
+int32_t fm_synth_load_dx7_voice(FmSynthHandle handle, Dx7CartridgeHandle cartridge, int32_t index);
+
+/* ============================================================================
+   PARAMETER SCHEMA
+   ============================================================================ */
+
+/* JSON array of {id, name, min, max, default, unit}. Writes at most capacity-1
+   bytes plus a NUL and returns the full length (call with NULL, 0 to size). */
+size_t sub_synth_param_schema_json(char* buffer, size_t capacity);
+size_t fm_synth_param_schema_json(char* buffer, size_t capacity);
 
 #ifdef __cplusplus
 }
@@ -3,9 +3,13 @@
 
 use ossian19_core::synth::Synth;
 use ossian19_core::fm::Fm6OpVoiceManager;
-use ossian19_core::oscillator::{Waveform, SubWaveform};
+use ossian19_core::oscillator::{Quality, Waveform, SubWaveform};
 use ossian19_core::filter::FilterSlope;
 use ossian19_core::fm::Dx7Algorithm;
+use ossian19_core::fm::dx7_sysex::{parse_bank, Dx7VoiceData};
+use ossian19_core::schema::{describe, Engine};
+use ossian19_core::voice::next_instance_seed;
+use std::os::raw::c_char;
 use std::slice;
 
 // ============================================================================
@@ -15,7 +19,8 @@ use std::slice;
 /// Create a new subtractive synth instance
 #[no_mangle]
 pub extern "C" fn sub_synth_create(sample_rate: f32) -> *mut Synth {
-    let synth = Box::new(Synth::new(sample_rate, 8));
+    let mut synth = Box::new(Synth::new(sample_rate, 8));
+    synth.set_instance_seed(next_instance_seed());
     Box::into_raw(synth)
 }
 
@@ -51,6 +56,24 @@ pub extern "C" fn sub_synth_note_off(handle: *mut Synth, note: u8) {
     }
 }
 
+/// Start a note at any frequency in Hz (velocity 0.0-1.0); returns its id for
+/// `sub_synth_note_off_id` (0 for a null handle)
+#[no_mangle]
+pub extern "C" fn sub_synth_note_on_freq(handle: *mut Synth, freq: f32, velocity: f32) -> u32 {
+    match unsafe { handle.as_mut() } {
+        Some(s) => s.note_on_freq(freq, (velocity * 127.0) as u8),
+        None => 0,
+    }
+}
+
+/// Release a note started with `sub_synth_note_on_freq`
+#[no_mangle]
+pub extern "C" fn sub_synth_note_off_id(handle: *mut Synth, id: u32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.note_off_id(id);
+    }
+}
+
 /// All notes off
 #[no_mangle]
 pub extern "C" fn sub_synth_all_notes_off(handle: *mut Synth) {
@@ -59,6 +82,14 @@ pub extern "C" fn sub_synth_all_notes_off(handle: *mut Synth) {
     }
 }
 
+/// Clear all DSP state (voices, filters, envelopes, smoothers) but keep parameters
+#[no_mangle]
+pub extern "C" fn sub_synth_reset(handle: *mut Synth) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.reset();
+    }
+}
+
 /// Process audio block (stereo)
 #[no_mangle]
 pub extern "C" fn sub_synth_process(
@@ -78,6 +109,24 @@ pub extern "C" fn sub_synth_process(
     s.process_stereo(left_slice, right_slice);
 }
 
+/// Peak of the last processed block
+#[no_mangle]
+pub extern "C" fn sub_synth_get_peak(handle: *const Synth) -> f32 {
+    unsafe { handle.as_ref() }.map_or(0.0, |s| s.meter().peak())
+}
+
+/// RMS of the last processed block
+#[no_mangle]
+pub extern "C" fn sub_synth_get_rms(handle: *const Synth) -> f32 {
+    unsafe { handle.as_ref() }.map_or(0.0, |s| s.meter().rms())
+}
+
+/// True if the output exceeded ±1 since the last call (reading clears it)
+#[no_mangle]
+pub extern "C" fn sub_synth_get_clip(handle: *const Synth) -> bool {
+    unsafe { handle.as_ref() }.is_some_and(|s| s.meter().take_clip())
+}
+
 // --- Sub Synth Parameters ---
 
 #[no_mangle]
@@ -88,6 +137,7 @@ pub extern "C" fn sub_synth_set_osc1_waveform(handle: *mut Synth, value: i32) {
             1 => Waveform::Square,
             2 => Waveform::Triangle,
             3 => Waveform::Sine,
+            4 => Waveform::Blit,
             _ => Waveform::Saw,
         };
         s.set_osc1_waveform(wf);
@@ -109,6 +159,7 @@ pub extern "C" fn sub_synth_set_osc2_waveform(handle: *mut Synth, value: i32) {
             1 => Waveform::Square,
             2 => Waveform::Triangle,
             3 => Waveform::Sine,
+            4 => Waveform::Blit,
             _ => Waveform::Saw,
         };
         s.set_osc2_waveform(wf);
@@ -259,6 +310,37 @@ pub extern "C" fn sub_synth_set_master_volume(handle: *mut Synth, value: f32) {
     }
 }
 
+/// Master volume in dB (0 = unity and the maximum), ramped while notes sound
+#[no_mangle]
+pub extern "C" fn sub_synth_set_master_volume_db(handle: *mut Synth, db: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_master_volume_db(db);
+    }
+}
+
+/// Seed the instance's randomness (each instance gets its own at creation)
+#[no_mangle]
+pub extern "C" fn sub_synth_set_instance_seed(handle: *mut Synth, seed: u32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_instance_seed(seed);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sub_synth_set_master_pan(handle: *mut Synth, value: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_master_pan(value);
+    }
+}
+
+/// Anti-aliasing quality (0 = Draft, 1 = Normal, 2 = High, 3 = Ultra)
+#[no_mangle]
+pub extern "C" fn sub_synth_set_quality(handle: *mut Synth, value: i32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_quality(Quality::from_u8(value.clamp(0, 255) as u8));
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn sub_synth_set_pitch_bend(handle: *mut Synth, semitones: f32) {
     if let Some(s) = unsafe { handle.as_mut() } {
@@ -273,7 +355,8 @@ pub extern "C" fn sub_synth_set_pitch_bend(handle: *mut Synth, semitones: f32) {
 /// Create a new FM synth instance
 #[no_mangle]
 pub extern "C" fn fm_synth_create(sample_rate: f32) -> *mut Fm6OpVoiceManager {
-    let synth = Box::new(Fm6OpVoiceManager::new(8, sample_rate));
+    let mut synth = Box::new(Fm6OpVoiceManager::new(8, sample_rate));
+    synth.set_instance_seed(next_instance_seed());
     Box::into_raw(synth)
 }
 
@@ -301,6 +384,24 @@ pub extern "C" fn fm_synth_note_off(handle: *mut Fm6OpVoiceManager, note: u8) {
     }
 }
 
+/// Start a note at any frequency in Hz (velocity 0.0-1.0); returns its id for
+/// `fm_synth_note_off_id` (0 for a null handle)
+#[no_mangle]
+pub extern "C" fn fm_synth_note_on_freq(handle: *mut Fm6OpVoiceManager, freq: f32, velocity: f32) -> u32 {
+    match unsafe { handle.as_mut() } {
+        Some(s) => s.note_on_freq(freq, velocity),
+        None => 0,
+    }
+}
+
+/// Release a note started with `fm_synth_note_on_freq`
+#[no_mangle]
+pub extern "C" fn fm_synth_note_off_id(handle: *mut Fm6OpVoiceManager, id: u32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.note_off_id(id);
+    }
+}
+
 /// All notes off
 #[no_mangle]
 pub extern "C" fn fm_synth_all_notes_off(handle: *mut Fm6OpVoiceManager) {
@@ -309,6 +410,14 @@ pub extern "C" fn fm_synth_all_notes_off(handle: *mut Fm6OpVoiceManager) {
     }
 }
 
+/// Clear all DSP state (voices, LFO, smoothers, effects) but keep the patch
+#[no_mangle]
+pub extern "C" fn fm_synth_reset(handle: *mut Fm6OpVoiceManager) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.reset();
+    }
+}
+
 /// Process audio block (stereo, mono duplicated)
 #[no_mangle]
 pub extern "C" fn fm_synth_process(
@@ -325,11 +434,25 @@ pub extern "C" fn fm_synth_process(
     let left_slice = unsafe { slice::from_raw_parts_mut(left, num_samples) };
     let right_slice = unsafe { slice::from_raw_parts_mut(right, num_samples) };
 
-    for i in 0..num_samples {
-        let sample = s.tick();
-        left_slice[i] = sample;
-        right_slice[i] = sample;
-    }
+    s.process_stereo(left_slice, right_slice);
+}
+
+/// Peak of the last processed block
+#[no_mangle]
+pub extern "C" fn fm_synth_get_peak(handle: *const Fm6OpVoiceManager) -> f32 {
+    unsafe { handle.as_ref() }.map_or(0.0, |s| s.meter().peak())
+}
+
+/// RMS of the last processed block
+#[no_mangle]
+pub extern "C" fn fm_synth_get_rms(handle: *const Fm6OpVoiceManager) -> f32 {
+    unsafe { handle.as_ref() }.map_or(0.0, |s| s.meter().rms())
+}
+
+/// True if the output exceeded ±1 since the last call (reading clears it)
+#[no_mangle]
+pub extern "C" fn fm_synth_get_clip(handle: *const Fm6OpVoiceManager) -> bool {
+    unsafe { handle.as_ref() }.is_some_and(|s| s.meter().take_clip())
 }
 
 // --- FM Synth Parameters ---
@@ -341,6 +464,15 @@ pub extern "C" fn fm_synth_set_algorithm(handle: *mut Fm6OpVoiceManager, value:
     }
 }
 
+/// Carrier operators of the current algorithm as a bitmask (bit 0 = OP1)
+#[no_mangle]
+pub extern "C" fn fm_synth_get_carrier_mask(handle: *const Fm6OpVoiceManager) -> u8 {
+    match unsafe { handle.as_ref() } {
+        Some(s) => Dx7Algorithm::from_u8(s.get_algorithm()).carrier_mask(),
+        None => 0,
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn fm_synth_set_op_ratio(handle: *mut Fm6OpVoiceManager, op: i32, value: f32) {
     if let Some(s) = unsafe { handle.as_mut() } {
@@ -355,6 +487,22 @@ pub extern "C" fn fm_synth_set_op_level(handle: *mut Fm6OpVoiceManager, op: i32,
     }
 }
 
+/// Fixed operator frequency in Hz; a negative value tracks the note again
+#[no_mangle]
+pub extern "C" fn fm_synth_set_op_fixed_freq(handle: *mut Fm6OpVoiceManager, op: i32, value: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_op_fixed_freq(op as usize, (value >= 0.0).then_some(value));
+    }
+}
+
+/// Keyboard rate scaling (0-1): shorter operator envelopes on higher notes
+#[no_mangle]
+pub extern "C" fn fm_synth_set_op_rate_scaling(handle: *mut Fm6OpVoiceManager, op: i32, value: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_op_rate_scaling(op as usize, value);
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn fm_synth_set_op_detune(handle: *mut Fm6OpVoiceManager, op: i32, value: f32) {
     if let Some(s) = unsafe { handle.as_mut() } {
@@ -404,6 +552,39 @@ pub extern "C" fn fm_synth_set_op_release(handle: *mut Fm6OpVoiceManager, op: i3
     }
 }
 
+/// All settings of one operator, for `fm_synth_set_operator`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FmOperatorConfig {
+    pub ratio: f32,
+    pub level: f32,
+    pub detune: f32,
+    pub feedback: f32,
+    pub velocity_sens: f32,
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+/// Set every parameter of one operator at once
+#[no_mangle]
+pub extern "C" fn fm_synth_set_operator(handle: *mut Fm6OpVoiceManager, op: i32, config: *const FmOperatorConfig) {
+    let (Some(s), Some(c)) = (unsafe { handle.as_mut() }, unsafe { config.as_ref() }) else {
+        return;
+    };
+    let op = op as usize;
+    s.set_op_ratio(op, c.ratio);
+    s.set_op_level(op, c.level);
+    s.set_op_detune(op, c.detune);
+    s.set_op_feedback(op, c.feedback);
+    s.set_op_velocity_sens(op, c.velocity_sens);
+    s.set_op_attack(op, c.attack);
+    s.set_op_decay(op, c.decay);
+    s.set_op_sustain(op, c.sustain);
+    s.set_op_release(op, c.release);
+}
+
 #[no_mangle]
 pub extern "C" fn fm_synth_set_filter_enabled(handle: *mut Fm6OpVoiceManager, enabled: bool) {
     if let Some(s) = unsafe { handle.as_mut() } {
@@ -445,3 +626,324 @@ pub extern "C" fn fm_synth_set_master_volume(handle: *mut Fm6OpVoiceManager, val
         s.set_master_volume(value);
     }
 }
+
+/// Master volume in dB (0 = unity and the maximum), ramped while notes sound
+#[no_mangle]
+pub extern "C" fn fm_synth_set_master_volume_db(handle: *mut Fm6OpVoiceManager, db: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_master_volume_db(db);
+    }
+}
+
+/// Seed the instance's randomness (each instance gets its own at creation)
+#[no_mangle]
+pub extern "C" fn fm_synth_set_instance_seed(handle: *mut Fm6OpVoiceManager, seed: u32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_instance_seed(seed);
+    }
+}
+
+/// Fade length in ms `fm_synth_all_notes_off` applies before stopping the voices (0 = instant)
+#[no_mangle]
+pub extern "C" fn fm_synth_set_panic_fade_ms(handle: *mut Fm6OpVoiceManager, ms: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_panic_fade_ms(ms);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn fm_synth_set_master_pan(handle: *mut Fm6OpVoiceManager, value: f32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_master_pan(value);
+    }
+}
+
+/// Anti-aliasing quality (0 = Draft, 1 = Normal, 2 = High, 3 = Ultra)
+#[no_mangle]
+pub extern "C" fn fm_synth_set_quality(handle: *mut Fm6OpVoiceManager, value: i32) {
+    if let Some(s) = unsafe { handle.as_mut() } {
+        s.set_quality(Quality::from_u8(value.clamp(0, 255) as u8));
+    }
+}
+
+// ============================================================================
+// DX7 CARTRIDGES
+// ============================================================================
+
+/// Cartridge data is not a valid 32-voice DX7 bank
+pub const DX7_ERROR_INVALID_DATA: i32 = -1;
+/// Voice index outside 0-31
+pub const DX7_ERROR_INVALID_INDEX: i32 = -2;
+/// A synth, cartridge or output handle is null
+pub const DX7_ERROR_NULL_HANDLE: i32 = -3;
+
+/// A decoded 32-voice bank, parsed once by `dx7_cartridge_load`
+pub struct Dx7Cartridge {
+    voices: Vec<Dx7VoiceData>,
+}
+
+impl Dx7Cartridge {
+    fn voice(&self, index: i32) -> Result<&Dx7VoiceData, i32> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.voices.get(index))
+            .ok_or(DX7_ERROR_INVALID_INDEX)
+    }
+}
+
+/// Decode a 32-voice bank (.syx or raw) into `*out`, to be freed with `dx7_cartridge_destroy`.
+/// Returns 0 or a negative `DX7_ERROR_*`.
+#[no_mangle]
+pub extern "C" fn dx7_cartridge_load(data: *const u8, len: usize, out: *mut *mut Dx7Cartridge) -> i32 {
+    if out.is_null() {
+        return DX7_ERROR_NULL_HANDLE;
+    }
+    if data.is_null() {
+        return DX7_ERROR_INVALID_DATA;
+    }
+    let bytes = unsafe { slice::from_raw_parts(data, len) };
+    match parse_bank(bytes) {
+        Ok(voices) => {
+            unsafe { *out = Box::into_raw(Box::new(Dx7Cartridge { voices })) };
+            0
+        }
+        Err(_) => DX7_ERROR_INVALID_DATA,
+    }
+}
+
+/// Free a cartridge from `dx7_cartridge_load`
+#[no_mangle]
+pub extern "C" fn dx7_cartridge_destroy(cartridge: *mut Dx7Cartridge) {
+    if !cartridge.is_null() {
+        unsafe { drop(Box::from_raw(cartridge)); }
+    }
+}
+
+/// Copy the name of cartridge voice `index` (0-31) as a NUL-terminated string into `buffer`.
+/// Returns the name length (see `write_string`) or a negative `DX7_ERROR_*`.
+#[no_mangle]
+pub extern "C" fn dx7_cartridge_voice_name(
+    cartridge: *const Dx7Cartridge,
+    index: i32,
+    buffer: *mut c_char,
+    capacity: usize,
+) -> i32 {
+    let Some(cartridge) = (unsafe { cartridge.as_ref() }) else {
+        return DX7_ERROR_NULL_HANDLE;
+    };
+    match cartridge.voice(index) {
+        Ok(voice) => write_string(&voice.name, buffer, capacity) as i32,
+        Err(error) => error,
+    }
+}
+
+/// Load cartridge voice `index` (0-31) into the synth. Returns 0 or a negative `DX7_ERROR_*`.
+#[no_mangle]
+pub extern "C" fn fm_synth_load_dx7_voice(
+    handle: *mut Fm6OpVoiceManager,
+    cartridge: *const Dx7Cartridge,
+    index: i32,
+) -> i32 {
+    let (Some(s), Some(cartridge)) = (unsafe { handle.as_mut() }, unsafe { cartridge.as_ref() }) else {
+        return DX7_ERROR_NULL_HANDLE;
+    };
+    match cartridge.voice(index) {
+        Ok(voice) => {
+            s.load_dx7_voice(voice);
+            0
+        }
+        Err(error) => error,
+    }
+}
+
+// ============================================================================
+// PARAMETER SCHEMA
+// ============================================================================
+
+/// Copy `text` NUL-terminated into `buffer`.
+/// Returns the text length; if it is >= `capacity` the output was truncated.
+fn write_string(text: &str, buffer: *mut c_char, capacity: usize) -> usize {
+    if !buffer.is_null() && capacity > 0 {
+        let len = text.len().min(capacity - 1);
+        let out = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, capacity) };
+        out[..len].copy_from_slice(&text.as_bytes()[..len]);
+        out[len] = 0;
+    }
+    text.len()
+}
+
+/// Copy the engine's parameter schema as NUL-terminated JSON into `buffer` (see `write_string`)
+fn write_schema(engine: Engine, buffer: *mut c_char, capacity: usize) -> usize {
+    write_string(&serde_json::to_string(&describe(engine)).unwrap_or_default(), buffer, capacity)
+}
+
+/// Subtractive parameter schema as JSON (see `write_schema`)
+#[no_mangle]
+pub extern "C" fn sub_synth_param_schema_json(buffer: *mut c_char, capacity: usize) -> usize {
+    write_schema(Engine::Subtractive, buffer, capacity)
+}
+
+/// FM parameter schema as JSON (see `write_schema`)
+#[no_mangle]
+pub extern "C" fn fm_synth_param_schema_json(buffer: *mut c_char, capacity: usize) -> usize {
+    write_schema(Engine::Fm, buffer, capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_operator_applies_all_fields() {
+        let handle = fm_synth_create(44100.0);
+        let config = FmOperatorConfig {
+            ratio: 3.5,
+            level: 0.4,
+            detune: -7.0,
+            feedback: 0.3,
+            velocity_sens: 0.6,
+            attack: 0.02,
+            decay: 0.7,
+            sustain: 0.25,
+            release: 1.2,
+        };
+        fm_synth_set_operator(handle, 2, &config);
+
+        let op = unsafe { &*handle }.snapshot().operators[2];
+        assert_eq!(op.ratio, config.ratio);
+        assert_eq!(op.level, config.level);
+        assert_eq!(op.detune, config.detune);
+        assert_eq!(op.feedback, config.feedback);
+        assert_eq!(op.velocity_sens, config.velocity_sens);
+        assert_eq!(op.attack, config.attack);
+        assert_eq!(op.decay, config.decay);
+        assert_eq!(op.sustain, config.sustain);
+        assert_eq!(op.release, config.release);
+
+        fm_synth_destroy(handle);
+    }
+
+    #[test]
+    fn test_clip_flag() {
+        let render = |volume: f32| {
+            let handle = sub_synth_create(44100.0);
+            sub_synth_set_master_volume(handle, volume);
+            for note in [36, 43, 48, 52, 55, 60, 64, 67] {
+                sub_synth_note_on(handle, note, 1.0);
+            }
+            let mut left = vec![0.0; 4410];
+            let mut right = vec![0.0; 4410];
+            sub_synth_process(handle, left.as_mut_ptr(), right.as_mut_ptr(), left.len());
+            let result = (sub_synth_get_clip(handle), sub_synth_get_peak(handle), sub_synth_get_rms(handle));
+            // Reading resets the latch
+            assert!(!sub_synth_get_clip(handle));
+            sub_synth_destroy(handle);
+            result
+        };
+
+        let (clipped, peak, rms) = render(1.0);
+        assert!(clipped, "peak {}", peak);
+        assert!(rms > 0.0 && rms <= peak);
+
+        let (clipped, peak, _) = render(0.05);
+        assert!(!clipped);
+        assert!(peak > 0.0 && peak < 1.0);
+    }
+
+    #[test]
+    fn test_param_schema_json() {
+        let len = fm_synth_param_schema_json(std::ptr::null_mut(), 0);
+        let mut buffer = vec![0 as c_char; len + 1];
+        assert_eq!(fm_synth_param_schema_json(buffer.as_mut_ptr(), buffer.len()), len);
+        let json = unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap();
+        assert_eq!(json.len(), len);
+        assert!(json.contains("\"id\":\"op6_ratio\""));
+
+        // Truncated output stays NUL-terminated
+        let mut small = [1 as c_char; 8];
+        assert_eq!(sub_synth_param_schema_json(small.as_mut_ptr(), small.len()), sub_synth_param_schema_json(std::ptr::null_mut(), 0));
+        assert_eq!(small[7], 0);
+    }
+
+    #[test]
+    fn test_reset_keeps_params() {
+        let render = |handle: *mut Synth| {
+            sub_synth_note_on(handle, 48, 1.0);
+            let mut left = vec![0.0; 2048];
+            let mut right = vec![0.0; 2048];
+            sub_synth_process(handle, left.as_mut_ptr(), right.as_mut_ptr(), left.len());
+            left
+        };
+        let configure = |handle: *mut Synth| {
+            sub_synth_set_filter_cutoff(handle, 800.0);
+            sub_synth_set_filter_resonance(handle, 0.7);
+            // Let the cutoff glide settle before the first note
+            let mut silence = [0.0; 64];
+            let mut silence_right = [0.0; 64];
+            sub_synth_process(handle, silence.as_mut_ptr(), silence_right.as_mut_ptr(), 64);
+        };
+
+        let fresh = sub_synth_create(44100.0);
+        configure(fresh);
+        let expected = render(fresh);
+
+        // Reused instance: leave a note ringing through the resonant filter, then reset
+        let reused = sub_synth_create(44100.0);
+        configure(reused);
+        render(reused);
+        sub_synth_reset(reused);
+        assert_eq!(unsafe { &*reused }.active_voice_count(), 0);
+        assert_eq!(unsafe { &*reused }.params().filter_cutoff, 800.0);
+        // The resonance value is a knob position, eased towards self-oscillation
+        assert!((unsafe { &*reused }.params().filter_resonance - 0.91).abs() < 1e-6);
+        assert_eq!(render(reused), expected);
+
+        let fm = fm_synth_create(44100.0);
+        fm_synth_set_op_ratio(fm, 1, 3.0);
+        fm_synth_note_on(fm, 60, 1.0);
+        fm_synth_reset(fm);
+        let fm_ref = unsafe { &*fm };
+        assert_eq!(fm_ref.active_voice_count(), 0);
+        assert_eq!(fm_ref.snapshot().operators[1].ratio, 3.0);
+
+        sub_synth_destroy(fresh);
+        sub_synth_destroy(reused);
+        fm_synth_destroy(fm);
+    }
+
+    #[test]
+    fn test_dx7_cartridge() {
+        // Raw 32-voice bank: voice n is named "VOICE n" and OP1 plays at output level 3n
+        let mut bank = vec![0u8; 32 * 128];
+        for (n, voice) in bank.chunks_exact_mut(128).enumerate() {
+            voice[5 * 17 + 14] = 3 * n as u8;
+            let name = format!("{:<10}", format!("VOICE {}", n));
+            voice[118..128].copy_from_slice(name.as_bytes());
+        }
+
+        let mut cartridge = std::ptr::null_mut();
+        assert_eq!(dx7_cartridge_load(bank.as_ptr(), 100, &mut cartridge), DX7_ERROR_INVALID_DATA);
+        assert!(cartridge.is_null());
+        assert_eq!(dx7_cartridge_load(bank.as_ptr(), bank.len(), std::ptr::null_mut()), DX7_ERROR_NULL_HANDLE);
+        assert_eq!(dx7_cartridge_load(bank.as_ptr(), bank.len(), &mut cartridge), 0);
+
+        let mut buffer = [0 as c_char; 16];
+        for n in 0..32 {
+            let len = dx7_cartridge_voice_name(cartridge, n, buffer.as_mut_ptr(), buffer.len());
+            let name = unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap();
+            assert_eq!(name, format!("VOICE {}", n));
+            assert_eq!(len as usize, name.len());
+        }
+
+        let handle = fm_synth_create(44100.0);
+        assert_eq!(fm_synth_load_dx7_voice(handle, cartridge, 32), DX7_ERROR_INVALID_INDEX);
+        assert_eq!(fm_synth_load_dx7_voice(handle, cartridge, -1), DX7_ERROR_INVALID_INDEX);
+        assert_eq!(fm_synth_load_dx7_voice(std::ptr::null_mut(), cartridge, 0), DX7_ERROR_NULL_HANDLE);
+        assert_eq!(fm_synth_load_dx7_voice(handle, std::ptr::null(), 0), DX7_ERROR_NULL_HANDLE);
+        assert_eq!(fm_synth_load_dx7_voice(handle, cartridge, 11), 0);
+        assert_eq!(unsafe { &*handle }.snapshot().operators[0].level, 33.0 / 99.0);
+
+        fm_synth_destroy(handle);
+        dx7_cartridge_destroy(cartridge);
+    }
+}
@@ -4,15 +4,20 @@ use nih_plug::prelude::*;
 use nih_plug_egui::{create_egui_editor, egui, widgets, EguiState};
 use std::sync::Arc;
 
-use crate::{Ossian19FmParams, OperatorParams};
+use ossian19_core::{midi_to_freq, CcDestination, DetuneMode, Dx7Algorithm};
+use ossian19_plugin::editor::{compare_button, learn_row, restore_params, snapshot_params, DIM};
+
+use crate::{cc_param, Ossian19FmParams, OperatorParams};
 
 const WIDTH: u32 = 400;
 const HEIGHT: u32 = 750;
 
+/// Note the operator frequency readouts are computed for (A4)
+const REFERENCE_NOTE: u8 = 69;
+
 const BG: egui::Color32 = egui::Color32::from_rgb(26, 26, 26);
 const PANEL: egui::Color32 = egui::Color32::from_rgb(36, 36, 36);
 const ACCENT: egui::Color32 = egui::Color32::from_rgb(255, 140, 66);
-const DIM: egui::Color32 = egui::Color32::from_rgb(120, 120, 120);
 
 const OP_COLORS: [egui::Color32; 6] = [
     egui::Color32::from_rgb(100, 200, 255),
@@ -42,10 +47,29 @@ pub fn create(
                     ui.style_mut().spacing.item_spacing = egui::vec2(4.0, 4.0);
 
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.label(egui::RichText::new("OSSIAN-19 FM").color(ACCENT).strong());
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("OSSIAN-19 FM").color(ACCENT).strong());
+                            // Compare the patch-only settings (fixed frequency etc.) too
+                            compare_button(
+                                ui,
+                                &params.ab_compare,
+                                || {
+                                    let patch = params.patch.read().map(|p| p.clone()).unwrap_or_default();
+                                    (snapshot_params(params.as_ref()), patch)
+                                },
+                                |(values, patch)| {
+                                    if let Ok(mut pending) = params.pending_patch.lock() {
+                                        *pending = Some(patch.clone());
+                                    }
+                                    restore_params(params.as_ref(), setter, values);
+                                },
+                            );
+                        });
 
                         // Algorithm
                         row(ui, "Algorithm", &params.algorithm, setter);
+                        let algorithm: Dx7Algorithm = params.algorithm.value().into();
+                        ui.label(egui::RichText::new(algorithm.description()).size(9.0).color(DIM));
 
                         ui.separator();
 
@@ -76,11 +100,37 @@ pub fn create(
                         section(ui, "VIBRATO", |ui| {
                             row(ui, "Depth", &params.vibrato_depth, setter);
                             row(ui, "Rate", &params.vibrato_rate, setter);
+                            row(ui, "Wave", &params.vibrato_waveform, setter);
+                            row(ui, "Sync", &params.vibrato_sync, setter);
+                            row(ui, "Division", &params.vibrato_division, setter);
+                        });
+
+                        // Lo-fi
+                        section(ui, "LO-FI", |ui| {
+                            row(ui, "Bits", &params.lofi_bits, setter);
+                            row(ui, "Downsample", &params.lofi_downsample, setter);
+                            row(ui, "Mix", &params.lofi_mix, setter);
                         });
 
                         // Master
                         section(ui, "MASTER", |ui| {
                             row(ui, "Volume", &params.master_volume, setter);
+                            row(ui, "Pan", &params.master_pan, setter);
+                            row(ui, "Latch", &params.latch, setter);
+                            row(ui, "Key Low", &params.key_low, setter);
+                            row(ui, "Key High", &params.key_high, setter);
+                            row(ui, "Mono Sum", &params.mono_sum, setter);
+                            row(ui, "Quality", &params.quality, setter);
+                            row(ui, "Additive", &params.additive_mode, setter);
+                        });
+
+                        // MIDI learn
+                        section(ui, "MIDI LEARN", |ui| {
+                            for destination in CcDestination::ALL {
+                                if cc_param(&params, destination).is_some() {
+                                    learn_row(ui, &params.cc_map, &params.midi_learn, destination);
+                                }
+                            }
                         });
                     });
                 });
@@ -94,13 +144,20 @@ fn op(ui: &mut egui::Ui, name: &str, p: &OperatorParams, setter: &ParamSetter, c
         .corner_radius(3.0)
         .inner_margin(4.0)
         .show(ui, |ui| {
-            ui.label(egui::RichText::new(name).size(11.0).color(color).strong());
-
-            row(ui, "Ratio", &p.ratio, setter);
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(name).size(11.0).color(color).strong());
+                let mut solo = p.solo.value();
+                if ui.toggle_value(&mut solo, egui::RichText::new("Solo").size(9.0)).changed() {
+                    setter.set_parameter(&p.solo, solo);
+                }
+            });
+
+            ratio_row(ui, p, setter);
             row(ui, "Level", &p.level, setter);
             row(ui, "Detune", &p.detune, setter);
             row(ui, "Feedback", &p.feedback, setter);
             row(ui, "Vel Sens", &p.velocity_sens, setter);
+            row(ui, "Key Delay", &p.key_delay, setter);
             row(ui, "Attack", &p.attack, setter);
             row(ui, "Decay", &p.decay, setter);
             row(ui, "Sustain", &p.sustain, setter);
@@ -108,6 +165,16 @@ fn op(ui: &mut egui::Ui, name: &str, p: &OperatorParams, setter: &ParamSetter, c
         });
 }
 
+/// Ratio slider followed by the operator's frequency at the reference note
+fn ratio_row(ui: &mut egui::Ui, p: &OperatorParams, setter: &ParamSetter) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new("Ratio").size(9.0).color(DIM));
+        ui.add(widgets::ParamSlider::for_param(&p.ratio, setter));
+        let freq = DetuneMode::Absolute.frequency(midi_to_freq(REFERENCE_NOTE), p.ratio.value(), p.detune.value());
+        ui.label(egui::RichText::new(format!("{:.1} Hz", freq)).size(9.0).color(DIM));
+    });
+}
+
 fn section(ui: &mut egui::Ui, title: &str, content: impl FnOnce(&mut egui::Ui)) {
     egui::Frame::new().fill(PANEL).corner_radius(3.0).inner_margin(6.0).show(ui, |ui| {
         ui.label(egui::RichText::new(title).size(10.0).color(ACCENT));
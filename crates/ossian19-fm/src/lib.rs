@@ -4,8 +4,10 @@
 
 use nih_plug::prelude::*;
 use nih_plug_egui::EguiState;
-use ossian19_core::{Fm6OpVoiceManager, Dx7Algorithm};
-use std::sync::Arc;
+use ossian19_core::{AdditiveMode, Fm6OpParams, Fm6OpVoiceManager, Dx7Algorithm, LfoWaveform, Quality, next_instance_seed, pitch_bend_from_normalized};
+use ossian19_core::{AbCompare, CcDestination, CcMap, CcOverrides, ParamSnapshot};
+use ossian19_plugin::NoteDivisionParam;
+use std::sync::{Arc, Mutex, RwLock};
 
 mod editor;
 
@@ -14,6 +16,8 @@ struct Ossian19Fm {
     params: Arc<Ossian19FmParams>,
     voice_manager: Fm6OpVoiceManager,
     editor_state: Arc<EguiState>,
+    /// Values set by mapped CCs, held until the host parameter moves
+    cc_overrides: CcOverrides,
 }
 
 /// Operator parameters (repeated for 6 operators)
@@ -45,6 +49,13 @@ pub struct OperatorParams {
 
     #[id = "vel_sens"]
     pub velocity_sens: FloatParam,
+
+    #[id = "key_delay"]
+    pub key_delay: FloatParam,
+
+    /// Patch design aid: hear this operator alone (hidden from host automation)
+    #[id = "solo"]
+    pub solo: BoolParam,
 }
 
 impl OperatorParams {
@@ -108,76 +119,85 @@ impl OperatorParams {
                 0.5,
                 FloatRange::Linear { min: 0.0, max: 1.0 }
             ).with_unit(" %").with_value_to_string(formatters::v2s_f32_percentage(0)),
+
+            key_delay: FloatParam::new(
+                format!("{} Key Delay", prefix),
+                0.0,
+                FloatRange::Skewed { min: 0.0, max: 5.0, factor: FloatRange::skew_factor(-2.0) }
+            ).with_unit(" s"),
+
+            solo: BoolParam::new(format!("{} Solo", prefix), false).non_automatable(),
         }
     }
 }
 
-/// DX7 Algorithm parameter wrapper
+/// DX7 Algorithm parameter wrapper; names are just the number, the editor shows
+/// the topology from `Dx7Algorithm::description`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
 enum AlgorithmParam {
-    #[name = "1: 6→5→4→3→2→1"]
+    #[name = "1"]
     Algo1,
-    #[name = "2: 6→5→4→3→2, 1"]
+    #[name = "2"]
     Algo2,
-    #[name = "3: 6→5→4→3, 2→1"]
+    #[name = "3"]
     Algo3,
-    #[name = "4: 6→5→4, 3→2→1"]
+    #[name = "4"]
     Algo4,
-    #[name = "5: 6→5, 4→3→2→1"]
+    #[name = "5"]
     Algo5,
-    #[name = "6: 6→5+4→3→2→1"]
+    #[name = "6"]
     Algo6,
-    #[name = "7: 6→5, 4→3, 2→1"]
+    #[name = "7"]
     Algo7,
-    #[name = "8: 6+5→4, 3→2→1"]
+    #[name = "8"]
     Algo8,
-    #[name = "9: 6→5, 4→3→2, 1"]
+    #[name = "9"]
     Algo9,
-    #[name = "10: 6→5→4, 3→2, 1"]
+    #[name = "10"]
     Algo10,
-    #[name = "11: 6, 5→4→3→2→1"]
+    #[name = "11"]
     Algo11,
-    #[name = "12: 6→5, 4, 3→2→1"]
+    #[name = "12"]
     Algo12,
-    #[name = "13: 6, 5, 4→3→2→1"]
+    #[name = "13"]
     Algo13,
-    #[name = "14: 6→5, 4, 3, 2→1"]
+    #[name = "14"]
     Algo14,
-    #[name = "15: 6, 5, 4, 3→2→1"]
+    #[name = "15"]
     Algo15,
-    #[name = "16: 6, 5, 4→3, 2→1"]
+    #[name = "16"]
     Algo16,
-    #[name = "17: 6, 5→4, 3, 2→1"]
+    #[name = "17"]
     Algo17,
-    #[name = "18: 6→5, 4→3, 2, 1"]
+    #[name = "18"]
     Algo18,
-    #[name = "19: 6, 5→4→3, 2, 1"]
+    #[name = "19"]
     Algo19,
-    #[name = "20: 6→5, 4, 3, 2, 1"]
+    #[name = "20"]
     Algo20,
-    #[name = "21: 6, 5, 4→3, 2, 1"]
+    #[name = "21"]
     Algo21,
-    #[name = "22: 6, 5, 4, 3, 2→1"]
+    #[name = "22"]
     Algo22,
-    #[name = "23: 6, 5, 4, 3→2, 1"]
+    #[name = "23"]
     Algo23,
-    #[name = "24: 6, 5→4, 3, 2, 1"]
+    #[name = "24"]
     Algo24,
-    #[name = "25: 6, 5, 4, 3, 2, 1"]
+    #[name = "25"]
     Algo25,
-    #[name = "26: 6→5+4→3, 2, 1"]
+    #[name = "26"]
     Algo26,
-    #[name = "27: 6→5, 4→3, 2+1"]
+    #[name = "27"]
     Algo27,
-    #[name = "28: 6→5→4, 3, 2, 1"]
+    #[name = "28"]
     Algo28,
-    #[name = "29: 6, 5→4, 3→2, 1"]
+    #[name = "29"]
     Algo29,
-    #[name = "30: 6, 5→4→3, 2→1"]
+    #[name = "30"]
     Algo30,
-    #[name = "31: 6, 5, 4, 3, 2→1"]
+    #[name = "31"]
     Algo31,
-    #[name = "32: 6, 5, 4, 3, 2, 1"]
+    #[name = "32"]
     Algo32,
 }
 
@@ -220,6 +240,65 @@ impl From<AlgorithmParam> for Dx7Algorithm {
     }
 }
 
+/// Vibrato LFO waveform parameter wrapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum LfoWaveformParam {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+    #[name = "S&H"]
+    SampleAndHold,
+}
+
+impl From<LfoWaveformParam> for LfoWaveform {
+    fn from(w: LfoWaveformParam) -> Self {
+        match w {
+            LfoWaveformParam::Sine => LfoWaveform::Sine,
+            LfoWaveformParam::Triangle => LfoWaveform::Triangle,
+            LfoWaveformParam::Saw => LfoWaveform::Saw,
+            LfoWaveformParam::Square => LfoWaveform::Square,
+            LfoWaveformParam::SampleAndHold => LfoWaveform::SampleAndHold,
+        }
+    }
+}
+
+/// Additive mode parameter wrapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum AdditiveModeParam {
+    Normalize,
+    Clamp,
+}
+
+impl From<AdditiveModeParam> for AdditiveMode {
+    fn from(m: AdditiveModeParam) -> Self {
+        match m {
+            AdditiveModeParam::Normalize => AdditiveMode::Normalize,
+            AdditiveModeParam::Clamp => AdditiveMode::Clamp,
+        }
+    }
+}
+
+/// Anti-aliasing quality parameter wrapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum QualityParam {
+    Draft,
+    Normal,
+    High,
+    Ultra,
+}
+
+impl From<QualityParam> for Quality {
+    fn from(q: QualityParam) -> Self {
+        match q {
+            QualityParam::Draft => Quality::Draft,
+            QualityParam::Normal => Quality::Normal,
+            QualityParam::High => Quality::High,
+            QualityParam::Ultra => Quality::Ultra,
+        }
+    }
+}
+
 /// Plugin parameters
 #[derive(Params)]
 pub struct Ossian19FmParams {
@@ -257,9 +336,69 @@ pub struct Ossian19FmParams {
     #[id = "vib_rate"]
     pub vibrato_rate: FloatParam,
 
+    #[id = "vib_wave"]
+    pub vibrato_waveform: EnumParam<LfoWaveformParam>,
+
+    /// Clock the vibrato LFO to the host tempo instead of the rate
+    #[id = "vib_sync"]
+    pub vibrato_sync: BoolParam,
+
+    #[id = "vib_div"]
+    pub vibrato_division: EnumParam<NoteDivisionParam>,
+
     // Master
     #[id = "volume"]
     pub master_volume: FloatParam,
+
+    #[id = "pan"]
+    pub master_pan: FloatParam,
+
+    #[id = "latch"]
+    pub latch: BoolParam,
+
+    #[id = "key_low"]
+    pub key_low: IntParam,
+
+    #[id = "key_high"]
+    pub key_high: IntParam,
+
+    #[id = "mono_sum"]
+    pub mono_sum: BoolParam,
+
+    #[id = "quality"]
+    pub quality: EnumParam<QualityParam>,
+
+    // Lo-fi
+    #[id = "lofi_bits"]
+    pub lofi_bits: IntParam,
+
+    #[id = "lofi_down"]
+    pub lofi_downsample: IntParam,
+
+    #[id = "lofi_mix"]
+    pub lofi_mix: FloatParam,
+
+    #[id = "add_mode"]
+    pub additive_mode: EnumParam<AdditiveModeParam>,
+
+    /// Full patch snapshot saved with the host state, for settings that have no param
+    /// (fixed frequencies etc.). Restored in `initialize`.
+    #[persist = "patch"]
+    pub patch: Arc<RwLock<Fm6OpParams>>,
+
+    /// Patch the editor wants loaded (A/B compare), picked up by the next process()
+    pub pending_patch: Mutex<Option<Fm6OpParams>>,
+
+    /// CC assignments, saved with the plugin state
+    #[persist = "cc_map"]
+    pub cc_map: Arc<RwLock<CcMap>>,
+
+    /// Destination waiting for the next CC (armed by the editor's Learn buttons)
+    pub midi_learn: Mutex<Option<CcDestination>>,
+
+    /// A/B compare slots (parameters plus the full patch), saved with the plugin state
+    #[persist = "ab_compare"]
+    pub ab_compare: Arc<RwLock<AbCompare<(ParamSnapshot, Fm6OpParams)>>>,
 }
 
 impl Default for Ossian19FmParams {
@@ -288,22 +427,49 @@ impl Default for Ossian19FmParams {
             vibrato_rate: FloatParam::new("Vibrato Rate", 5.0, FloatRange::Skewed {
                 min: 0.1, max: 20.0, factor: FloatRange::skew_factor(-1.0)
             }).with_unit(" Hz"),
+            vibrato_waveform: EnumParam::new("Vibrato Wave", LfoWaveformParam::Sine),
+            vibrato_sync: BoolParam::new("Vibrato Sync", false),
+            vibrato_division: EnumParam::new("Vibrato Div", NoteDivisionParam::Eighth),
 
             master_volume: FloatParam::new("Volume", 0.7, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_smoother(SmoothingStyle::Logarithmic(10.0))
                 .with_unit(" dB")
                 .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
                 .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+            master_pan: FloatParam::new("Pan", 0.0, FloatRange::Linear { min: -1.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_value_to_string(formatters::v2s_f32_panning())
+                .with_string_to_value(formatters::s2v_f32_panning()),
+            latch: BoolParam::new("Latch", false),
+            key_low: IntParam::new("Key Low", 0, IntRange::Linear { min: 0, max: 127 }),
+            key_high: IntParam::new("Key High", 127, IntRange::Linear { min: 0, max: 127 }),
+            mono_sum: BoolParam::new("Mono Sum", false),
+            quality: EnumParam::new("Quality", QualityParam::Normal),
+            lofi_bits: IntParam::new("Lo-Fi Bits", 16, IntRange::Linear { min: 4, max: 16 })
+                .with_unit(" bit"),
+            lofi_downsample: IntParam::new("Lo-Fi Downsample", 1, IntRange::Linear { min: 1, max: 32 })
+                .with_unit("x"),
+            lofi_mix: FloatParam::new("Lo-Fi Mix", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_unit(" %").with_value_to_string(formatters::v2s_f32_percentage(0)),
+            additive_mode: EnumParam::new("Additive Mode", AdditiveModeParam::Normalize),
+            patch: Arc::new(RwLock::new(Fm6OpParams::default())),
+            pending_patch: Mutex::new(None),
+            cc_map: Arc::new(RwLock::new(CcMap::default())),
+            midi_learn: Mutex::new(None),
+            ab_compare: Arc::new(RwLock::new(AbCompare::default())),
         }
     }
 }
 
 impl Default for Ossian19Fm {
     fn default() -> Self {
+        let mut voice_manager = Fm6OpVoiceManager::new(8, 44100.0);
+        voice_manager.set_instance_seed(next_instance_seed());
         Self {
             params: Arc::new(Ossian19FmParams::default()),
-            voice_manager: Fm6OpVoiceManager::new(8, 44100.0),
+            voice_manager,
             editor_state: editor::default_state(),
+            cc_overrides: CcOverrides::default(),
         }
     }
 }
@@ -322,9 +488,14 @@ impl Plugin for Ossian19Fm {
             main_output_channels: NonZeroU32::new(2),
             ..AudioIOLayout::const_default()
         },
+        AudioIOLayout {
+            main_input_channels: None,
+            main_output_channels: NonZeroU32::new(1),
+            ..AudioIOLayout::const_default()
+        },
     ];
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
@@ -344,12 +515,16 @@ impl Plugin for Ossian19Fm {
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.voice_manager = Fm6OpVoiceManager::new(8, buffer_config.sample_rate);
+        self.voice_manager.set_sample_rate(buffer_config.sample_rate);
+        // Restore the non-param settings; automatable params are re-applied in process()
+        if let Ok(patch) = self.params.patch.read() {
+            self.voice_manager.apply_params(&patch);
+        }
         true
     }
 
     fn reset(&mut self) {
-        self.voice_manager.panic();
+        self.voice_manager.panic_now();
     }
 
     fn process(
@@ -358,8 +533,15 @@ impl Plugin for Ossian19Fm {
         _aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        // Load a patch from A/B compare; the host parameters below override its automatable part
+        if let Some(patch) = self.params.pending_patch.try_lock().ok().and_then(|mut pending| pending.take()) {
+            self.voice_manager.apply_params(&patch);
+        }
+
         // Apply parameter changes
-        self.apply_params();
+        let tempo = context.transport().tempo;
+        self.apply_params(tempo);
+        self.apply_cc_overrides();
 
         // Process MIDI events
         let mut next_event = context.next_event();
@@ -372,12 +554,25 @@ impl Plugin for Ossian19Fm {
                 }
 
                 match event {
+                    // Velocity 0 note-on means note-off
+                    NoteEvent::NoteOn { note, velocity, .. } if velocity == 0.0 => {
+                        self.voice_manager.note_off(note);
+                    }
                     NoteEvent::NoteOn { note, velocity, .. } => {
                         self.voice_manager.note_on(note, velocity);
                     }
                     NoteEvent::NoteOff { note, .. } => {
                         self.voice_manager.note_off(note);
                     }
+                    NoteEvent::MidiPitchBend { value, .. } => {
+                        self.voice_manager.set_pitch_bend(pitch_bend_from_normalized(value));
+                    }
+                    NoteEvent::MidiCC { cc: 64, value, .. } => {
+                        self.voice_manager.set_sustain_pedal(value >= 0.5);
+                    }
+                    NoteEvent::MidiCC { cc, value, .. } if CcMap::is_assignable(cc) => {
+                        self.handle_cc(cc, value);
+                    }
                     _ => {}
                 }
 
@@ -385,21 +580,71 @@ impl Plugin for Ossian19Fm {
             }
 
             // Generate audio sample
-            let sample = self.voice_manager.tick();
+            let (left, right) = self.voice_manager.tick_stereo();
 
-            // Output to all channels (stereo)
-            for channel_sample in channel_samples {
-                *channel_sample = sample;
+            let num_channels = channel_samples.len();
+            for (channel_idx, channel_sample) in channel_samples.into_iter().enumerate() {
+                *channel_sample = channel_output(left, right, num_channels, channel_idx);
             }
         }
 
+        // Keep the persisted snapshot current (skipped if the host is reading it)
+        if let Ok(mut patch) = self.params.patch.try_write() {
+            *patch = self.voice_manager.snapshot();
+        }
+
         ProcessStatus::Normal
     }
 }
 
 impl Ossian19Fm {
+    /// Learn the CC if a destination is armed, then let a mapped CC drive its parameter
+    fn handle_cc(&mut self, cc: u8, value: f32) {
+        let params = self.params.clone();
+        if let Ok(mut map) = params.cc_map.try_write() {
+            if let Some(destination) = params.midi_learn.try_lock().ok().and_then(|mut learn| learn.take()) {
+                map.learn(cc, destination);
+            }
+        }
+
+        let destination = params.cc_map.try_read().ok().and_then(|map| map.get(cc));
+        if let Some(destination) = destination {
+            if let Some(param) = cc_param(&params, destination) {
+                let plain = param.preview_plain(value);
+                self.cc_overrides.capture(destination, plain, param.value());
+                self.set_cc_destination(destination, plain);
+            }
+        }
+    }
+
+    /// Re-apply CC values on top of the host parameters written by `apply_params`
+    fn apply_cc_overrides(&mut self) {
+        let params = self.params.clone();
+        for destination in CcDestination::ALL {
+            if let Some(param) = cc_param(&params, destination) {
+                let host = param.value();
+                let value = self.cc_overrides.resolve(destination, host);
+                if value != host {
+                    self.set_cc_destination(destination, value);
+                }
+            }
+        }
+    }
+
+    fn set_cc_destination(&mut self, destination: CcDestination, value: f32) {
+        match destination {
+            CcDestination::FilterCutoff => self.voice_manager.set_filter_cutoff(value),
+            CcDestination::FilterResonance => self.voice_manager.set_filter_resonance(value),
+            CcDestination::VibratoDepth => self.voice_manager.set_vibrato_depth(value),
+            CcDestination::VibratoRate => self.voice_manager.set_vibrato_rate(value),
+            CcDestination::MasterVolume => self.voice_manager.set_master_volume(value),
+            CcDestination::MasterPan => self.voice_manager.set_master_pan(value),
+            _ => {}
+        }
+    }
+
     /// Apply parameter values from nih-plug to the voice manager
-    fn apply_params(&mut self) {
+    fn apply_params(&mut self, tempo: Option<f64>) {
         // Algorithm
         self.voice_manager.set_algorithm(self.params.algorithm.value().into());
 
@@ -414,6 +659,7 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(0, self.params.op1.release.value());
         self.voice_manager.set_op_feedback(0, self.params.op1.feedback.value());
         self.voice_manager.set_op_velocity_sens(0, self.params.op1.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(0, self.params.op1.key_delay.value());
 
         // OP2
         self.voice_manager.set_op_ratio(1, self.params.op2.ratio.value());
@@ -425,6 +671,7 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(1, self.params.op2.release.value());
         self.voice_manager.set_op_feedback(1, self.params.op2.feedback.value());
         self.voice_manager.set_op_velocity_sens(1, self.params.op2.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(1, self.params.op2.key_delay.value());
 
         // OP3
         self.voice_manager.set_op_ratio(2, self.params.op3.ratio.value());
@@ -436,6 +683,7 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(2, self.params.op3.release.value());
         self.voice_manager.set_op_feedback(2, self.params.op3.feedback.value());
         self.voice_manager.set_op_velocity_sens(2, self.params.op3.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(2, self.params.op3.key_delay.value());
 
         // OP4
         self.voice_manager.set_op_ratio(3, self.params.op4.ratio.value());
@@ -447,6 +695,7 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(3, self.params.op4.release.value());
         self.voice_manager.set_op_feedback(3, self.params.op4.feedback.value());
         self.voice_manager.set_op_velocity_sens(3, self.params.op4.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(3, self.params.op4.key_delay.value());
 
         // OP5
         self.voice_manager.set_op_ratio(4, self.params.op5.ratio.value());
@@ -458,6 +707,7 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(4, self.params.op5.release.value());
         self.voice_manager.set_op_feedback(4, self.params.op5.feedback.value());
         self.voice_manager.set_op_velocity_sens(4, self.params.op5.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(4, self.params.op5.key_delay.value());
 
         // OP6
         self.voice_manager.set_op_ratio(5, self.params.op6.ratio.value());
@@ -469,6 +719,11 @@ impl Ossian19Fm {
         self.voice_manager.set_op_release(5, self.params.op6.release.value());
         self.voice_manager.set_op_feedback(5, self.params.op6.feedback.value());
         self.voice_manager.set_op_velocity_sens(5, self.params.op6.velocity_sens.value());
+        self.voice_manager.set_op_key_delay(5, self.params.op6.key_delay.value());
+
+        // Solo (first soloed operator wins)
+        let ops = [&self.params.op1, &self.params.op2, &self.params.op3, &self.params.op4, &self.params.op5, &self.params.op6];
+        self.voice_manager.set_solo_operator(ops.iter().position(|op| op.solo.value()));
 
         // Filter
         self.voice_manager.set_filter_enabled(self.params.filter_enabled.value());
@@ -478,9 +733,48 @@ impl Ossian19Fm {
         // Vibrato
         self.voice_manager.set_vibrato_depth(self.params.vibrato_depth.value());
         self.voice_manager.set_vibrato_rate(self.params.vibrato_rate.value());
+        self.voice_manager.set_vibrato_waveform(self.params.vibrato_waveform.value().into());
+        // Synced without a host tempo falls back to the rate
+        let division = self.params.vibrato_sync.value().then(|| self.params.vibrato_division.value().into());
+        self.voice_manager.set_vibrato_sync(division, tempo.map(|bpm| bpm as f32));
 
         // Master
         self.voice_manager.set_master_volume(self.params.master_volume.value());
+        self.voice_manager.set_master_pan(self.params.master_pan.value());
+        self.voice_manager.set_latch(self.params.latch.value());
+        self.voice_manager.set_key_range(self.params.key_low.value() as u8, self.params.key_high.value() as u8);
+        self.voice_manager.set_mono_sum(self.params.mono_sum.value());
+        self.voice_manager.set_quality(self.params.quality.value().into());
+
+        // Lo-fi
+        self.voice_manager.set_lofi_bit_depth(self.params.lofi_bits.value() as u32);
+        self.voice_manager.set_lofi_downsample(self.params.lofi_downsample.value() as usize);
+        self.voice_manager.set_lofi_mix(self.params.lofi_mix.value());
+        self.voice_manager.set_additive_mode(self.params.additive_mode.value().into());
+    }
+}
+
+/// Host parameter a CC destination drives (None if this plugin has no such parameter)
+fn cc_param(params: &Ossian19FmParams, destination: CcDestination) -> Option<&FloatParam> {
+    match destination {
+        CcDestination::FilterCutoff => Some(&params.filter_cutoff),
+        CcDestination::FilterResonance => Some(&params.filter_resonance),
+        CcDestination::VibratoDepth => Some(&params.vibrato_depth),
+        CcDestination::VibratoRate => Some(&params.vibrato_rate),
+        CcDestination::MasterVolume => Some(&params.master_volume),
+        CcDestination::MasterPan => Some(&params.master_pan),
+        _ => None,
+    }
+}
+
+/// Sample for one output channel: L/R on stereo, the centre mix on mono
+fn channel_output(left: f32, right: f32, num_channels: usize, channel_idx: usize) -> f32 {
+    if num_channels == 1 {
+        (left + right) * 0.5
+    } else if channel_idx == 0 {
+        left
+    } else {
+        right
     }
 }
 
@@ -507,3 +801,23 @@ impl Vst3Plugin for Ossian19Fm {
 
 nih_export_clap!(Ossian19Fm);
 nih_export_vst3!(Ossian19Fm);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mono_and_stereo_layouts() {
+        let channels: Vec<u32> = Ossian19Fm::AUDIO_IO_LAYOUTS
+            .iter()
+            .filter_map(|layout| layout.main_output_channels.map(|c| c.get()))
+            .collect();
+        assert!(channels.contains(&1));
+        assert!(channels.contains(&2));
+
+        // A centred stereo frame renders unchanged into a single channel
+        assert_eq!(channel_output(0.5, 0.5, 1, 0), 0.5);
+        assert_eq!(channel_output(0.2, 0.8, 2, 0), 0.2);
+        assert_eq!(channel_output(0.2, 0.8, 2, 1), 0.8);
+    }
+}
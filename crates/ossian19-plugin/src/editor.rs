@@ -0,0 +1,82 @@
+//! egui widgets both plugin editors use
+
+use nih_plug::prelude::*;
+use nih_plug_egui::egui;
+use std::sync::{Mutex, RwLock};
+
+use ossian19_core::{AbCompare, CcDestination, CcMap, ParamSnapshot};
+
+/// Label colour for parameter names
+pub const DIM: egui::Color32 = egui::Color32::from_rgb(120, 120, 120);
+
+/// Current CC for a destination, with Learn (arm / cancel) and Clear buttons
+pub fn learn_row(
+    ui: &mut egui::Ui,
+    cc_map: &RwLock<CcMap>,
+    midi_learn: &Mutex<Option<CcDestination>>,
+    destination: CcDestination,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new(destination.name()).size(9.0).color(DIM));
+
+        let cc = cc_map.read().ok().and_then(|map| map.cc_for(destination));
+        let Ok(mut learn) = midi_learn.lock() else { return };
+        let learning = *learn == Some(destination);
+        let status = match (learning, cc) {
+            (true, _) => "move a control...".to_string(),
+            (false, Some(cc)) => format!("CC {}", cc),
+            (false, None) => "-".to_string(),
+        };
+        ui.label(egui::RichText::new(status).size(9.0));
+
+        if ui.selectable_label(learning, "Learn").clicked() {
+            *learn = if learning { None } else { Some(destination) };
+        }
+        drop(learn);
+        if cc.is_some() && ui.small_button("Clear").clicked() {
+            if let Ok(mut map) = cc_map.write() {
+                map.clear_destination(destination);
+            }
+        }
+    });
+}
+
+/// A/B button: stores the live state (from `capture`) in the current slot and
+/// hands the other slot to `apply`
+pub fn compare_button<T: Clone>(
+    ui: &mut egui::Ui,
+    compare: &RwLock<AbCompare<T>>,
+    capture: impl FnOnce() -> T,
+    apply: impl FnOnce(&T),
+) {
+    let Ok(mut compare) = compare.write() else { return };
+    let label = format!("A/B: {}", compare.active().name());
+    if ui.button(egui::RichText::new(label).size(9.0)).clicked() {
+        let target = compare.toggle(capture());
+        apply(&target);
+    }
+}
+
+/// Normalized values of every parameter
+pub fn snapshot_params(params: &impl Params) -> ParamSnapshot {
+    params
+        .param_map()
+        .into_iter()
+        // SAFETY: the pointers come from `params`, which outlives this call
+        .map(|(id, ptr, _)| (id, unsafe { ptr.unmodulated_normalized_value() }))
+        .collect()
+}
+
+/// Set every parameter found in `snapshot` through the host
+pub fn restore_params(params: &impl Params, setter: &ParamSetter, snapshot: &ParamSnapshot) {
+    for (id, ptr, _) in params.param_map() {
+        if let Some(&value) = snapshot.get(&id) {
+            // SAFETY: as above; the begin/set/end calls form one host gesture
+            unsafe {
+                setter.raw_context.raw_begin_set_parameter(ptr);
+                setter.raw_context.raw_set_parameter_normalized(ptr, value);
+                setter.raw_context.raw_end_set_parameter(ptr);
+            }
+        }
+    }
+}
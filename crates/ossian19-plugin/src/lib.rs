@@ -0,0 +1,50 @@
+//! OSSIAN-19 plugin helpers
+//!
+//! Parameter types and editor widgets shared by the Sub and FM plugins.
+
+use nih_plug::prelude::*;
+use ossian19_core::NoteDivision;
+
+pub mod editor;
+
+/// Tempo division parameter wrapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum NoteDivisionParam {
+    #[name = "1/1"]
+    Whole,
+    #[name = "1/2"]
+    Half,
+    #[name = "1/4"]
+    Quarter,
+    #[name = "1/8"]
+    Eighth,
+    #[name = "1/16"]
+    Sixteenth,
+    #[name = "1/32"]
+    ThirtySecond,
+    #[name = "1/4 dotted"]
+    DottedQuarter,
+    #[name = "1/8 dotted"]
+    DottedEighth,
+    #[name = "1/4 triplet"]
+    TripletQuarter,
+    #[name = "1/8 triplet"]
+    TripletEighth,
+}
+
+impl From<NoteDivisionParam> for NoteDivision {
+    fn from(d: NoteDivisionParam) -> Self {
+        match d {
+            NoteDivisionParam::Whole => NoteDivision::Whole,
+            NoteDivisionParam::Half => NoteDivision::Half,
+            NoteDivisionParam::Quarter => NoteDivision::Quarter,
+            NoteDivisionParam::Eighth => NoteDivision::Eighth,
+            NoteDivisionParam::Sixteenth => NoteDivision::Sixteenth,
+            NoteDivisionParam::ThirtySecond => NoteDivision::ThirtySecond,
+            NoteDivisionParam::DottedQuarter => NoteDivision::DottedQuarter,
+            NoteDivisionParam::DottedEighth => NoteDivision::DottedEighth,
+            NoteDivisionParam::TripletQuarter => NoteDivision::TripletQuarter,
+            NoteDivisionParam::TripletEighth => NoteDivision::TripletEighth,
+        }
+    }
+}
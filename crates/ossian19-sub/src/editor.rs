@@ -4,7 +4,10 @@ use nih_plug::prelude::*;
 use nih_plug_egui::{create_egui_editor, egui, widgets, EguiState};
 use std::sync::Arc;
 
-use crate::Ossian19SubParams;
+use ossian19_core::CcDestination;
+use ossian19_plugin::editor::{compare_button, learn_row, restore_params, snapshot_params, DIM};
+
+use crate::{cc_param, Ossian19SubParams};
 
 const WIDTH: u32 = 380;
 const HEIGHT: u32 = 700;
@@ -13,7 +16,6 @@ const BG: egui::Color32 = egui::Color32::from_rgb(26, 26, 26);
 const PANEL: egui::Color32 = egui::Color32::from_rgb(36, 36, 36);
 const ACCENT1: egui::Color32 = egui::Color32::from_rgb(100, 200, 255);
 const ACCENT2: egui::Color32 = egui::Color32::from_rgb(255, 140, 66);
-const DIM: egui::Color32 = egui::Color32::from_rgb(120, 120, 120);
 
 pub fn default_state() -> Arc<EguiState> {
     EguiState::from_size(WIDTH, HEIGHT)
@@ -34,7 +36,15 @@ pub fn create(
                     ui.style_mut().spacing.item_spacing = egui::vec2(4.0, 4.0);
 
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.label(egui::RichText::new("OSSIAN-19 Sub").color(ACCENT1).strong());
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("OSSIAN-19 Sub").color(ACCENT1).strong());
+                            compare_button(
+                                ui,
+                                &params.ab_compare,
+                                || snapshot_params(params.as_ref()),
+                                |target| restore_params(params.as_ref(), setter, target),
+                            );
+                        });
                         ui.separator();
 
                         // === OSCILLATORS ===
@@ -51,11 +61,14 @@ pub fn create(
                             row(ui, "Sub Wave", &params.sub_waveform, setter);
                             row(ui, "Sub Level", &params.sub_level, setter);
                             row(ui, "Sub Octave", &params.sub_octave, setter);
+                            row(ui, "Free Phase", &params.sub_free_phase, setter);
+                            row(ui, "Thru Filter", &params.sub_pre_filter, setter);
                         });
 
                         // === NOISE ===
                         section(ui, "NOISE", |ui| {
                             row(ui, "Noise Level", &params.noise_level, setter);
+                            row(ui, "Thru Filter", &params.noise_pre_filter, setter);
                         });
 
                         // === PWM ===
@@ -78,6 +91,9 @@ pub fn create(
                             row(ui, "Slope", &params.filter_slope, setter);
                             row(ui, "Env Amount", &params.filter_env_amount, setter);
                             row(ui, "HPF", &params.hpf_cutoff, setter);
+                            row(ui, "Routing", &params.filter_routing, setter);
+                            row(ui, "F2 Cutoff", &params.filter2_cutoff, setter);
+                            row(ui, "F2 Type", &params.filter2_type, setter);
                         });
 
                         // === AMP ENVELOPE ===
@@ -86,6 +102,9 @@ pub fn create(
                             row(ui, "Decay", &params.amp_decay, setter);
                             row(ui, "Sustain", &params.amp_sustain, setter);
                             row(ui, "Release", &params.amp_release, setter);
+                            row(ui, "Attack Div", &params.amp_attack_div, setter);
+                            row(ui, "Decay Div", &params.amp_decay_div, setter);
+                            row(ui, "Release Div", &params.amp_release_div, setter);
                         });
 
                         // === FILTER ENVELOPE ===
@@ -94,11 +113,41 @@ pub fn create(
                             row(ui, "Decay", &params.filter_decay, setter);
                             row(ui, "Sustain", &params.filter_sustain, setter);
                             row(ui, "Release", &params.filter_release, setter);
+                            row(ui, "Attack Div", &params.filter_attack_div, setter);
+                            row(ui, "Decay Div", &params.filter_decay_div, setter);
+                            row(ui, "Release Div", &params.filter_release_div, setter);
+                        });
+
+                        // === ENVELOPE SYNC ===
+                        section(ui, "ENVELOPE SYNC", |ui| {
+                            row(ui, "Tempo Sync", &params.env_sync, setter);
+                        });
+
+                        // === LO-FI ===
+                        section(ui, "LO-FI", |ui| {
+                            row(ui, "Bits", &params.lofi_bits, setter);
+                            row(ui, "Downsample", &params.lofi_downsample, setter);
+                            row(ui, "Mix", &params.lofi_mix, setter);
                         });
 
                         // === MASTER ===
                         section(ui, "MASTER", |ui| {
                             row(ui, "Volume", &params.master_volume, setter);
+                            row(ui, "Pan", &params.master_pan, setter);
+                            row(ui, "Latch", &params.latch, setter);
+                            row(ui, "Key Low", &params.key_low, setter);
+                            row(ui, "Key High", &params.key_high, setter);
+                            row(ui, "Mono Sum", &params.mono_sum, setter);
+                            row(ui, "Quality", &params.quality, setter);
+                        });
+
+                        // === MIDI LEARN ===
+                        section(ui, "MIDI LEARN", |ui| {
+                            for destination in CcDestination::ALL {
+                                if cc_param(&params, destination).is_some() {
+                                    learn_row(ui, &params.cc_map, &params.midi_learn, destination);
+                                }
+                            }
                         });
                     });
                 });
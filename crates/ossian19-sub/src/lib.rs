@@ -4,8 +4,10 @@
 
 use nih_plug::prelude::*;
 use nih_plug_egui::EguiState;
-use ossian19_core::{Synth, Waveform, SubWaveform, FilterSlope};
-use std::sync::Arc;
+use ossian19_core::{resonance_knob, Quality, Synth, SynthParams, Waveform, SubWaveform, FilterSlope, FilterRouting, FilterType, NoteDivision, next_instance_seed, pitch_bend_from_normalized};
+use ossian19_core::{AbCompare, CcDestination, CcMap, CcOverrides};
+use ossian19_plugin::NoteDivisionParam;
+use std::sync::{Arc, Mutex, RwLock};
 
 mod editor;
 
@@ -14,6 +16,8 @@ struct Ossian19Sub {
     params: Arc<Ossian19SubParams>,
     synth: Synth,
     editor_state: Arc<EguiState>,
+    /// Values set by mapped CCs, held until the host parameter moves
+    cc_overrides: CcOverrides,
 }
 
 /// Plugin parameters - mapped to nih-plug's parameter system
@@ -45,10 +49,19 @@ pub struct Ossian19SubParams {
     #[id = "sub_oct"]
     pub sub_octave: IntParam,
 
+    #[id = "sub_free"]
+    pub sub_free_phase: BoolParam,
+
+    #[id = "sub_pre"]
+    pub sub_pre_filter: BoolParam,
+
     // === Noise ===
     #[id = "noise"]
     pub noise_level: FloatParam,
 
+    #[id = "noise_pre"]
+    pub noise_pre_filter: BoolParam,
+
     // === PWM ===
     #[id = "pw"]
     pub pulse_width: FloatParam,
@@ -82,6 +95,15 @@ pub struct Ossian19SubParams {
     #[id = "hpf"]
     pub hpf_cutoff: FloatParam,
 
+    #[id = "flt_route"]
+    pub filter_routing: EnumParam<FilterRoutingParam>,
+
+    #[id = "flt2_cutoff"]
+    pub filter2_cutoff: FloatParam,
+
+    #[id = "flt2_type"]
+    pub filter2_type: EnumParam<FilterTypeParam>,
+
     // === Amp Envelope ===
     #[id = "amp_a"]
     pub amp_attack: FloatParam,
@@ -108,9 +130,71 @@ pub struct Ossian19SubParams {
     #[id = "flt_r"]
     pub filter_release: FloatParam,
 
+    // === Envelope Tempo Sync ===
+    #[id = "env_sync"]
+    pub env_sync: BoolParam,
+
+    #[id = "amp_a_div"]
+    pub amp_attack_div: EnumParam<NoteDivisionParam>,
+
+    #[id = "amp_d_div"]
+    pub amp_decay_div: EnumParam<NoteDivisionParam>,
+
+    #[id = "amp_r_div"]
+    pub amp_release_div: EnumParam<NoteDivisionParam>,
+
+    #[id = "flt_a_div"]
+    pub filter_attack_div: EnumParam<NoteDivisionParam>,
+
+    #[id = "flt_d_div"]
+    pub filter_decay_div: EnumParam<NoteDivisionParam>,
+
+    #[id = "flt_r_div"]
+    pub filter_release_div: EnumParam<NoteDivisionParam>,
+
     // === Master ===
     #[id = "volume"]
     pub master_volume: FloatParam,
+
+    #[id = "pan"]
+    pub master_pan: FloatParam,
+
+    #[id = "latch"]
+    pub latch: BoolParam,
+
+    #[id = "key_low"]
+    pub key_low: IntParam,
+
+    #[id = "key_high"]
+    pub key_high: IntParam,
+
+    #[id = "mono_sum"]
+    pub mono_sum: BoolParam,
+
+    #[id = "quality"]
+    pub quality: EnumParam<QualityParam>,
+
+    // Lo-fi
+    #[id = "lofi_bits"]
+    pub lofi_bits: IntParam,
+
+    #[id = "lofi_down"]
+    pub lofi_downsample: IntParam,
+
+    #[id = "lofi_mix"]
+    pub lofi_mix: FloatParam,
+
+    // === MIDI ===
+    /// CC assignments, saved with the plugin state
+    #[persist = "cc_map"]
+    pub cc_map: Arc<RwLock<CcMap>>,
+
+    /// Destination waiting for the next CC (armed by the editor's Learn buttons)
+    pub midi_learn: Mutex<Option<CcDestination>>,
+
+    /// A/B compare slots, saved with the plugin state
+    #[persist = "ab_compare"]
+    pub ab_compare: Arc<RwLock<AbCompare>>,
 }
 
 // Enum wrapper for nih-plug
@@ -120,6 +204,8 @@ enum WaveformParam {
     Saw,
     Square,
     Triangle,
+    #[name = "BLIT"]
+    Blit,
 }
 
 impl From<WaveformParam> for Waveform {
@@ -129,6 +215,7 @@ impl From<WaveformParam> for Waveform {
             WaveformParam::Saw => Waveform::Saw,
             WaveformParam::Square => Waveform::Square,
             WaveformParam::Triangle => Waveform::Triangle,
+            WaveformParam::Blit => Waveform::Blit,
         }
     }
 }
@@ -148,6 +235,43 @@ impl From<SubWaveformParam> for SubWaveform {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum FilterRoutingParam {
+    Single,
+    Series,
+    Parallel,
+}
+
+impl From<FilterRoutingParam> for FilterRouting {
+    fn from(r: FilterRoutingParam) -> Self {
+        match r {
+            FilterRoutingParam::Single => FilterRouting::Single,
+            FilterRoutingParam::Series => FilterRouting::Series,
+            FilterRoutingParam::Parallel => FilterRouting::Parallel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum FilterTypeParam {
+    #[name = "Low Pass"]
+    LowPass,
+    #[name = "High Pass"]
+    HighPass,
+    #[name = "Band Pass"]
+    BandPass,
+}
+
+impl From<FilterTypeParam> for FilterType {
+    fn from(t: FilterTypeParam) -> Self {
+        match t {
+            FilterTypeParam::LowPass => FilterType::LowPass,
+            FilterTypeParam::HighPass => FilterType::HighPass,
+            FilterTypeParam::BandPass => FilterType::BandPass,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
 enum FilterSlopeParam {
     #[name = "6 dB/oct"]
@@ -168,6 +292,25 @@ impl From<FilterSlopeParam> for FilterSlope {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum QualityParam {
+    Draft,
+    Normal,
+    High,
+    Ultra,
+}
+
+impl From<QualityParam> for Quality {
+    fn from(q: QualityParam) -> Self {
+        match q {
+            QualityParam::Draft => Quality::Draft,
+            QualityParam::Normal => Quality::Normal,
+            QualityParam::High => Quality::High,
+            QualityParam::Ultra => Quality::Ultra,
+        }
+    }
+}
+
 impl Default for Ossian19SubParams {
     fn default() -> Self {
         Self {
@@ -190,11 +333,14 @@ impl Default for Ossian19SubParams {
                 .with_value_to_string(formatters::v2s_f32_percentage(0)),
             sub_waveform: EnumParam::new("Sub Wave", SubWaveformParam::Square),
             sub_octave: IntParam::new("Sub Octave", -1, IntRange::Linear { min: -2, max: -1 }),
+            sub_free_phase: BoolParam::new("Sub Free Phase", false),
+            sub_pre_filter: BoolParam::new("Sub Through Filter", true),
 
             // Noise
             noise_level: FloatParam::new("Noise", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_unit(" %")
                 .with_value_to_string(formatters::v2s_f32_percentage(0)),
+            noise_pre_filter: BoolParam::new("Noise Through Filter", true),
 
             // PWM
             pulse_width: FloatParam::new("Pulse Width", 0.5, FloatRange::Linear { min: 0.01, max: 0.99 })
@@ -219,7 +365,8 @@ impl Default for Ossian19SubParams {
             filter_cutoff: FloatParam::new("Cutoff", 5000.0, FloatRange::Skewed {
                 min: 20.0, max: 20000.0, factor: FloatRange::skew_factor(-2.0)
             }).with_unit(" Hz"),
-            filter_resonance: FloatParam::new("Resonance", 0.3, FloatRange::Linear { min: 0.0, max: 1.0 })
+            // Knob position; the engine skews it towards self-oscillation
+            filter_resonance: FloatParam::new("Resonance", resonance_knob(SynthParams::default().filter_resonance), FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_unit(" %")
                 .with_value_to_string(formatters::v2s_f32_percentage(0)),
             filter_slope: EnumParam::new("Filter Slope", FilterSlopeParam::Pole4),
@@ -229,6 +376,11 @@ impl Default for Ossian19SubParams {
             hpf_cutoff: FloatParam::new("HPF", 20.0, FloatRange::Skewed {
                 min: 20.0, max: 2000.0, factor: FloatRange::skew_factor(-2.0)
             }).with_unit(" Hz"),
+            filter_routing: EnumParam::new("Filter Routing", FilterRoutingParam::Single),
+            filter2_cutoff: FloatParam::new("Filter 2 Cutoff", 200.0, FloatRange::Skewed {
+                min: 20.0, max: 20000.0, factor: FloatRange::skew_factor(-2.0)
+            }).with_unit(" Hz"),
+            filter2_type: EnumParam::new("Filter 2 Type", FilterTypeParam::HighPass),
 
             // Amp envelope
             amp_attack: FloatParam::new("Amp Attack", 0.01, FloatRange::Skewed {
@@ -258,22 +410,53 @@ impl Default for Ossian19SubParams {
                 min: 0.001, max: 10.0, factor: FloatRange::skew_factor(-2.0)
             }).with_unit(" s"),
 
+            // Envelope tempo sync
+            env_sync: BoolParam::new("Env Sync", false),
+            amp_attack_div: EnumParam::new("Amp Attack Div", NoteDivisionParam::ThirtySecond),
+            amp_decay_div: EnumParam::new("Amp Decay Div", NoteDivisionParam::Sixteenth),
+            amp_release_div: EnumParam::new("Amp Release Div", NoteDivisionParam::Eighth),
+            filter_attack_div: EnumParam::new("Filter Attack Div", NoteDivisionParam::ThirtySecond),
+            filter_decay_div: EnumParam::new("Filter Decay Div", NoteDivisionParam::Eighth),
+            filter_release_div: EnumParam::new("Filter Release Div", NoteDivisionParam::Eighth),
+
             // Master
             master_volume: FloatParam::new("Volume", 0.7, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_smoother(SmoothingStyle::Logarithmic(10.0))
                 .with_unit(" dB")
                 .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
                 .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+            master_pan: FloatParam::new("Pan", 0.0, FloatRange::Linear { min: -1.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_value_to_string(formatters::v2s_f32_panning())
+                .with_string_to_value(formatters::s2v_f32_panning()),
+            latch: BoolParam::new("Latch", false),
+            key_low: IntParam::new("Key Low", 0, IntRange::Linear { min: 0, max: 127 }),
+            key_high: IntParam::new("Key High", 127, IntRange::Linear { min: 0, max: 127 }),
+            mono_sum: BoolParam::new("Mono Sum", false),
+            quality: EnumParam::new("Quality", QualityParam::Normal),
+            lofi_bits: IntParam::new("Lo-Fi Bits", 16, IntRange::Linear { min: 4, max: 16 })
+                .with_unit(" bit"),
+            lofi_downsample: IntParam::new("Lo-Fi Downsample", 1, IntRange::Linear { min: 1, max: 32 })
+                .with_unit("x"),
+            lofi_mix: FloatParam::new("Lo-Fi Mix", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_unit(" %").with_value_to_string(formatters::v2s_f32_percentage(0)),
+
+            cc_map: Arc::new(RwLock::new(CcMap::default())),
+            midi_learn: Mutex::new(None),
+            ab_compare: Arc::new(RwLock::new(AbCompare::default())),
         }
     }
 }
 
 impl Default for Ossian19Sub {
     fn default() -> Self {
+        let mut synth = Synth::new(44100.0, 8);
+        synth.set_instance_seed(next_instance_seed());
         Self {
             params: Arc::new(Ossian19SubParams::default()),
-            synth: Synth::new(44100.0, 8),
+            synth,
             editor_state: editor::default_state(),
+            cc_overrides: CcOverrides::default(),
         }
     }
 }
@@ -292,9 +475,14 @@ impl Plugin for Ossian19Sub {
             main_output_channels: NonZeroU32::new(2),
             ..AudioIOLayout::const_default()
         },
+        AudioIOLayout {
+            main_input_channels: None,
+            main_output_channels: NonZeroU32::new(1),
+            ..AudioIOLayout::const_default()
+        },
     ];
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
@@ -319,7 +507,7 @@ impl Plugin for Ossian19Sub {
     }
 
     fn reset(&mut self) {
-        self.synth.panic();
+        self.synth.panic_now();
     }
 
     fn process(
@@ -329,7 +517,10 @@ impl Plugin for Ossian19Sub {
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
         // Apply parameter changes to synth
-        self.apply_params();
+        let tempo = context.transport().tempo;
+        self.apply_params(tempo);
+        self.sync_cc_map();
+        self.apply_cc_overrides();
 
         // Process MIDI events
         let mut next_event = context.next_event();
@@ -342,6 +533,10 @@ impl Plugin for Ossian19Sub {
                 }
 
                 match event {
+                    // Velocity 0 note-on means note-off
+                    NoteEvent::NoteOn { note, velocity, .. } if velocity == 0.0 => {
+                        self.synth.note_off(note);
+                    }
                     NoteEvent::NoteOn { note, velocity, .. } => {
                         self.synth.note_on(note, (velocity * 127.0) as u8);
                     }
@@ -349,10 +544,14 @@ impl Plugin for Ossian19Sub {
                         self.synth.note_off(note);
                     }
                     NoteEvent::MidiPitchBend { value, .. } => {
-                        // value is 0..1, convert to -1..1
-                        self.synth.set_pitch_bend(value * 2.0 - 1.0);
+                        // value is 0..1 (14-bit), center 8192 maps to exactly 0
+                        self.synth.set_pitch_bend(pitch_bend_from_normalized(value));
+                    }
+                    NoteEvent::MidiCC { cc, value, .. } if CcMap::is_assignable(cc) => {
+                        self.handle_cc(cc, value);
                     }
                     NoteEvent::MidiCC { cc, value, .. } => {
+                        // Sustain pedal and channel mode messages
                         self.synth.control_change(cc, (value * 127.0) as u8);
                     }
                     _ => {}
@@ -362,11 +561,11 @@ impl Plugin for Ossian19Sub {
             }
 
             // Generate audio sample
-            let sample = self.synth.tick();
+            let (left, right) = self.synth.tick_stereo();
 
-            // Output to all channels (stereo)
-            for channel_sample in channel_samples {
-                *channel_sample = sample;
+            let num_channels = channel_samples.len();
+            for (channel_idx, channel_sample) in channel_samples.into_iter().enumerate() {
+                *channel_sample = channel_output(left, right, num_channels, channel_idx);
             }
         }
 
@@ -375,8 +574,50 @@ impl Plugin for Ossian19Sub {
 }
 
 impl Ossian19Sub {
+    /// Learn the CC if a destination is armed, then let a mapped CC drive its parameter
+    fn handle_cc(&mut self, cc: u8, value: f32) {
+        let params = self.params.clone();
+        if let Ok(mut map) = params.cc_map.try_write() {
+            if let Some(destination) = params.midi_learn.try_lock().ok().and_then(|mut learn| learn.take()) {
+                map.learn(cc, destination);
+            }
+        }
+        self.sync_cc_map();
+
+        if let Some(destination) = self.synth.cc_map().get(cc) {
+            if let Some(param) = cc_param(&params, destination) {
+                let plain = param.preview_plain(value);
+                self.cc_overrides.capture(destination, plain, param.value());
+                self.synth.set_cc_destination(destination, plain);
+            }
+        }
+    }
+
+    /// Load the persisted map (edited by MIDI learn, the editor and state restore)
+    /// into the synth, which resolves every CC
+    fn sync_cc_map(&mut self) {
+        if let Ok(map) = self.params.cc_map.try_read() {
+            if *map != *self.synth.cc_map() {
+                self.synth.set_cc_map(map.clone());
+            }
+        }
+    }
+
+    /// Re-apply CC values on top of the host parameters written by `apply_params`
+    fn apply_cc_overrides(&mut self) {
+        for destination in CcDestination::ALL {
+            if let Some(param) = cc_param(&self.params, destination) {
+                let host = param.value();
+                let value = self.cc_overrides.resolve(destination, host);
+                if value != host {
+                    self.synth.set_cc_destination(destination, value);
+                }
+            }
+        }
+    }
+
     /// Apply parameter values from nih-plug to the synth core
-    fn apply_params(&mut self) {
+    fn apply_params(&mut self, tempo: Option<f64>) {
         // Oscillators
         self.synth.set_osc1_waveform(self.params.osc1_waveform.value().into());
         self.synth.set_osc1_level(self.params.osc1_level.value());
@@ -388,9 +629,12 @@ impl Ossian19Sub {
         self.synth.set_sub_level(self.params.sub_level.value());
         self.synth.set_sub_waveform(self.params.sub_waveform.value().into());
         self.synth.set_sub_octave(self.params.sub_octave.value() as i8);
+        self.synth.set_sub_free_phase(self.params.sub_free_phase.value());
+        self.synth.set_sub_pre_filter(self.params.sub_pre_filter.value());
 
         // Noise
         self.synth.set_noise_level(self.params.noise_level.value());
+        self.synth.set_noise_pre_filter(self.params.noise_pre_filter.value());
 
         // PWM
         self.synth.set_pulse_width(self.params.pulse_width.value());
@@ -407,23 +651,74 @@ impl Ossian19Sub {
         self.synth.set_filter_slope(self.params.filter_slope.value().into());
         self.synth.set_filter_env_amount(self.params.filter_env_amount.value());
         self.synth.set_hpf_cutoff(self.params.hpf_cutoff.value());
-
-        // Envelopes
+        self.synth.set_filter_routing(self.params.filter_routing.value().into());
+        self.synth.set_filter2_cutoff(self.params.filter2_cutoff.value());
+        self.synth.set_filter2_type(self.params.filter2_type.value().into());
+
+        // Envelopes (tempo divisions replace the times when synced)
+        let sync = self.params.env_sync.value();
+        let time = |seconds: &FloatParam, division: &EnumParam<NoteDivisionParam>| {
+            envelope_time(seconds.value(), division.value(), sync, tempo)
+        };
         self.synth.set_amp_adsr(
-            self.params.amp_attack.value(),
-            self.params.amp_decay.value(),
+            time(&self.params.amp_attack, &self.params.amp_attack_div),
+            time(&self.params.amp_decay, &self.params.amp_decay_div),
             self.params.amp_sustain.value(),
-            self.params.amp_release.value(),
+            time(&self.params.amp_release, &self.params.amp_release_div),
         );
         self.synth.set_filter_adsr(
-            self.params.filter_attack.value(),
-            self.params.filter_decay.value(),
+            time(&self.params.filter_attack, &self.params.filter_attack_div),
+            time(&self.params.filter_decay, &self.params.filter_decay_div),
             self.params.filter_sustain.value(),
-            self.params.filter_release.value(),
+            time(&self.params.filter_release, &self.params.filter_release_div),
         );
 
         // Master
         self.synth.set_master_volume(self.params.master_volume.value());
+        self.synth.set_master_pan(self.params.master_pan.value());
+        self.synth.set_latch(self.params.latch.value());
+        self.synth.set_key_range(self.params.key_low.value() as u8, self.params.key_high.value() as u8);
+        self.synth.set_mono_sum(self.params.mono_sum.value());
+        self.synth.set_quality(self.params.quality.value().into());
+
+        // Lo-fi
+        self.synth.set_lofi_bit_depth(self.params.lofi_bits.value() as u32);
+        self.synth.set_lofi_downsample(self.params.lofi_downsample.value() as usize);
+        self.synth.set_lofi_mix(self.params.lofi_mix.value());
+    }
+}
+
+/// Host parameter a CC destination drives (None if this plugin has no such parameter)
+fn cc_param(params: &Ossian19SubParams, destination: CcDestination) -> Option<&FloatParam> {
+    match destination {
+        CcDestination::FilterCutoff => Some(&params.filter_cutoff),
+        CcDestination::FilterResonance => Some(&params.filter_resonance),
+        CcDestination::FilterEnvAmount => Some(&params.filter_env_amount),
+        CcDestination::AmpAttack => Some(&params.amp_attack),
+        CcDestination::AmpDecay => Some(&params.amp_decay),
+        CcDestination::AmpRelease => Some(&params.amp_release),
+        CcDestination::MasterVolume => Some(&params.master_volume),
+        CcDestination::MasterPan => Some(&params.master_pan),
+        CcDestination::VibratoDepth | CcDestination::VibratoRate => None,
+    }
+}
+
+/// Envelope time in seconds: the tempo division when synced and the host reports a tempo
+fn envelope_time(seconds: f32, division: NoteDivisionParam, sync: bool, tempo: Option<f64>) -> f32 {
+    match tempo {
+        Some(bpm) if sync && bpm > 0.0 => NoteDivision::from(division).to_seconds(bpm as f32),
+        _ => seconds,
+    }
+}
+
+/// Sample for one output channel: L/R on stereo, the centre mix on mono
+fn channel_output(left: f32, right: f32, num_channels: usize, channel_idx: usize) -> f32 {
+    if num_channels == 1 {
+        (left + right) * 0.5
+    } else if channel_idx == 0 {
+        left
+    } else {
+        right
     }
 }
 
@@ -450,3 +745,33 @@ impl Vst3Plugin for Ossian19Sub {
 
 nih_export_clap!(Ossian19Sub);
 nih_export_vst3!(Ossian19Sub);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mono_and_stereo_layouts() {
+        let channels: Vec<u32> = Ossian19Sub::AUDIO_IO_LAYOUTS
+            .iter()
+            .filter_map(|layout| layout.main_output_channels.map(|c| c.get()))
+            .collect();
+        assert!(channels.contains(&1));
+        assert!(channels.contains(&2));
+
+        // A centred stereo frame renders unchanged into a single channel
+        assert_eq!(channel_output(0.5, 0.5, 1, 0), 0.5);
+        assert_eq!(channel_output(0.2, 0.8, 2, 0), 0.2);
+        assert_eq!(channel_output(0.2, 0.8, 2, 1), 0.8);
+    }
+
+    #[test]
+    fn test_synced_envelope_time() {
+        let release = envelope_time(0.3, NoteDivisionParam::Quarter, true, Some(120.0));
+        assert!((release - 0.5).abs() < 1e-6);
+
+        // Seconds are used when sync is off or the host has no tempo
+        assert_eq!(envelope_time(0.3, NoteDivisionParam::Quarter, false, Some(120.0)), 0.3);
+        assert_eq!(envelope_time(0.3, NoteDivisionParam::Quarter, true, None), 0.3);
+    }
+}
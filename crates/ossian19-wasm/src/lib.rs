@@ -4,10 +4,11 @@
 //! to be used with Web Audio API's AudioWorklet.
 
 use ossian19_core::{
-    LfoWaveform, Synth, SynthParams, Waveform,
+    describe, next_instance_seed, Engine, FilterSlope, FilterType, LfoWaveform, Quality, Synth, SynthParams, Waveform,
     Fm4OpVoiceManager, FmAlgorithm,
     Fm6OpVoiceManager, Dx7Algorithm,
 };
+use ossian19_core::fm::dx7_sysex::{parse_bank, Dx7VoiceData};
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 
@@ -29,9 +30,15 @@ impl Ossian19Synth {
     /// Create a new synthesizer instance
     #[wasm_bindgen(constructor)]
     pub fn new(sample_rate: f32, num_voices: u32) -> Self {
-        Self {
-            synth: Synth::new(sample_rate, num_voices as usize),
-        }
+        let mut synth = Synth::new(sample_rate, num_voices as usize);
+        synth.set_instance_seed(next_instance_seed());
+        Self { synth }
+    }
+
+    /// Seed this instance's randomness (each instance gets its own by default)
+    #[wasm_bindgen(js_name = setInstanceSeed)]
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.synth.set_instance_seed(seed);
     }
 
     /// Set the sample rate (call if AudioContext sample rate changes)
@@ -64,6 +71,18 @@ impl Ossian19Synth {
         self.synth.note_off(note);
     }
 
+    /// Start a note at any frequency in Hz; returns its id for `noteOffId`
+    #[wasm_bindgen(js_name = noteOnFreq)]
+    pub fn note_on_freq(&mut self, freq: f32, velocity: u8) -> u32 {
+        self.synth.note_on_freq(freq, velocity)
+    }
+
+    /// Release a note started with `noteOnFreq`
+    #[wasm_bindgen(js_name = noteOffId)]
+    pub fn note_off_id(&mut self, id: u32) {
+        self.synth.note_off_id(id);
+    }
+
     /// Handle MIDI CC
     #[wasm_bindgen(js_name = controlChange)]
     pub fn control_change(&mut self, cc: u8, value: u8) {
@@ -76,12 +95,30 @@ impl Ossian19Synth {
         self.synth.all_notes_off();
     }
 
-    /// Panic - immediately stop all sound
+    /// Panic - fade all sound out over the panic fade, then stop every voice
     #[wasm_bindgen]
     pub fn panic(&mut self) {
         self.synth.panic();
     }
 
+    /// Stop every voice immediately, without the panic fade
+    #[wasm_bindgen(js_name = panicNow)]
+    pub fn panic_now(&mut self) {
+        self.synth.panic_now();
+    }
+
+    /// Panic fade length in ms (0 = instant)
+    #[wasm_bindgen(js_name = setPanicFadeMs)]
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.synth.set_panic_fade_ms(ms);
+    }
+
+    /// Clear all DSP state (filters, envelopes, LFOs) while keeping the parameters
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.synth.reset();
+    }
+
     /// Get number of active voices
     #[wasm_bindgen(js_name = activeVoiceCount)]
     pub fn active_voice_count(&self) -> usize {
@@ -148,9 +185,11 @@ impl Ossian19Synth {
         self.synth.set_filter_cutoff(cutoff);
     }
 
+    /// Set filter resonance from a knob position (0-1), eased towards
+    /// self-oscillation: resonance = 1 - (1 - knob)²
     #[wasm_bindgen(js_name = setFilterResonance)]
-    pub fn set_filter_resonance(&mut self, resonance: f32) {
-        self.synth.set_filter_resonance(resonance);
+    pub fn set_filter_resonance(&mut self, knob: f32) {
+        self.synth.set_filter_resonance(knob);
     }
 
     /// Set filter slope (0 = 6dB/oct, 1 = 12dB/oct, 2 = 24dB/oct)
@@ -183,6 +222,23 @@ impl Ossian19Synth {
         self.synth.set_master_volume(volume);
     }
 
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    #[wasm_bindgen(js_name = setMasterVolumeDb)]
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.synth.set_master_volume_db(db);
+    }
+
+    #[wasm_bindgen(js_name = setMasterPan)]
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.synth.set_master_pan(pan);
+    }
+
+    /// Set anti-aliasing quality (0 = Draft, 1 = Normal, 2 = High, 3 = Ultra)
+    #[wasm_bindgen(js_name = setQuality)]
+    pub fn set_quality(&mut self, quality: u8) {
+        self.synth.set_quality(Quality::from_u8(quality));
+    }
+
     // === Pitch Bend ===
 
     /// Set pitch bend value (-1 to 1)
@@ -215,6 +271,12 @@ impl Ossian19Synth {
             false
         }
     }
+
+    /// Describe all parameters (id, name, min, max, default, unit) as JSON
+    #[wasm_bindgen(js_name = getParamSchema)]
+    pub fn get_param_schema(&self) -> String {
+        serde_json::to_string(&describe(Engine::Subtractive)).unwrap_or_default()
+    }
 }
 
 fn parse_waveform(s: &str) -> Option<Waveform> {
@@ -223,6 +285,7 @@ fn parse_waveform(s: &str) -> Option<Waveform> {
         "saw" | "sawtooth" => Some(Waveform::Saw),
         "square" => Some(Waveform::Square),
         "triangle" => Some(Waveform::Triangle),
+        "blit" | "impulse" => Some(Waveform::Blit),
         _ => None,
     }
 }
@@ -265,9 +328,15 @@ impl Ossian19Fm4Op {
     /// Create a new 4-op FM synthesizer
     #[wasm_bindgen(constructor)]
     pub fn new(sample_rate: f32, num_voices: u32) -> Self {
-        Self {
-            voice_manager: Fm4OpVoiceManager::new(num_voices as usize, sample_rate),
-        }
+        let mut voice_manager = Fm4OpVoiceManager::new(num_voices as usize, sample_rate);
+        voice_manager.set_instance_seed(next_instance_seed());
+        Self { voice_manager }
+    }
+
+    /// Seed this instance's randomness (each instance gets its own by default)
+    #[wasm_bindgen(js_name = setInstanceSeed)]
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.voice_manager.set_instance_seed(seed);
     }
 
     /// Set sample rate
@@ -279,19 +348,13 @@ impl Ossian19Fm4Op {
     /// Process mono audio
     #[wasm_bindgen]
     pub fn process(&mut self, buffer: &mut [f32]) {
-        for sample in buffer.iter_mut() {
-            *sample = self.voice_manager.tick();
-        }
+        self.voice_manager.process(buffer);
     }
 
     /// Process stereo audio (simple mono->stereo for now)
     #[wasm_bindgen(js_name = processStereo)]
     pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
-        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
-            let sample = self.voice_manager.tick();
-            *l = sample;
-            *r = sample;
-        }
+        self.voice_manager.process_stereo(left, right);
     }
 
     /// Note on
@@ -306,12 +369,42 @@ impl Ossian19Fm4Op {
         self.voice_manager.note_off(note);
     }
 
-    /// Panic - stop all voices
+    /// Start a note at any frequency in Hz; returns its id for `noteOffId`
+    #[wasm_bindgen(js_name = noteOnFreq)]
+    pub fn note_on_freq(&mut self, freq: f32, velocity: u8) -> u32 {
+        self.voice_manager.note_on_freq(freq, velocity as f32 / 127.0)
+    }
+
+    /// Release a note started with `noteOnFreq`
+    #[wasm_bindgen(js_name = noteOffId)]
+    pub fn note_off_id(&mut self, id: u32) {
+        self.voice_manager.note_off_id(id);
+    }
+
+    /// Panic - fade all sound out over the panic fade, then stop every voice
     #[wasm_bindgen]
     pub fn panic(&mut self) {
         self.voice_manager.panic();
     }
 
+    /// Stop every voice immediately, without the panic fade
+    #[wasm_bindgen(js_name = panicNow)]
+    pub fn panic_now(&mut self) {
+        self.voice_manager.panic_now();
+    }
+
+    /// Panic fade length in ms (0 = instant)
+    #[wasm_bindgen(js_name = setPanicFadeMs)]
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.voice_manager.set_panic_fade_ms(ms);
+    }
+
+    /// Clear all DSP state (filters, envelopes, LFOs) while keeping the parameters
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.voice_manager.reset();
+    }
+
     /// Get active voice count
     #[wasm_bindgen(js_name = activeVoiceCount)]
     pub fn active_voice_count(&self) -> usize {
@@ -364,6 +457,12 @@ impl Ossian19Fm4Op {
         self.voice_manager.get_algorithm()
     }
 
+    /// Get carrier operators of the current algorithm as a bitmask (bit 0 = OP1)
+    #[wasm_bindgen(js_name = getCarriers)]
+    pub fn get_carriers(&self) -> u8 {
+        FmAlgorithm::from_u8(self.voice_manager.get_algorithm()).carrier_mask()
+    }
+
     /// Dump all operator levels (for debugging)
     #[wasm_bindgen(js_name = debugDump)]
     pub fn debug_dump(&self) -> String {
@@ -477,6 +576,28 @@ impl Ossian19Fm4Op {
         self.voice_manager.set_filter_resonance(resonance);
     }
 
+    /// Set filter type (0 = low-pass, 1 = high-pass, 2 = band-pass)
+    #[wasm_bindgen(js_name = setFilterType)]
+    pub fn set_filter_type(&mut self, filter_type: u8) {
+        self.voice_manager.set_filter_type(match filter_type {
+            1 => FilterType::HighPass,
+            2 => FilterType::BandPass,
+            _ => FilterType::LowPass,
+        });
+    }
+
+    /// Set filter slope (0 = 6dB/oct, 1 = 12dB/oct, 2 = 24dB/oct)
+    #[wasm_bindgen(js_name = setFilterSlope)]
+    pub fn set_filter_slope(&mut self, slope: u8) {
+        self.voice_manager.set_filter_slope(FilterSlope::from_u8(slope));
+    }
+
+    /// Set filter input drive (1.0 = unity)
+    #[wasm_bindgen(js_name = setFilterDrive)]
+    pub fn set_filter_drive(&mut self, drive: f32) {
+        self.voice_manager.set_filter_drive(drive);
+    }
+
     // === Master Volume ===
 
     #[wasm_bindgen(js_name = setMasterVolume)]
@@ -484,6 +605,23 @@ impl Ossian19Fm4Op {
         self.voice_manager.set_master_volume(volume);
     }
 
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    #[wasm_bindgen(js_name = setMasterVolumeDb)]
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.voice_manager.set_master_volume_db(db);
+    }
+
+    #[wasm_bindgen(js_name = setMasterPan)]
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.voice_manager.set_master_pan(pan);
+    }
+
+    /// Set anti-aliasing quality (0 = Draft, 1 = Normal, 2 = High, 3 = Ultra)
+    #[wasm_bindgen(js_name = setQuality)]
+    pub fn set_quality(&mut self, quality: u8) {
+        self.voice_manager.set_quality(Quality::from_u8(quality));
+    }
+
     // === Vibrato Controls ===
 
     /// Set vibrato depth in cents (0-100, typical range 0-50)
@@ -534,6 +672,8 @@ impl Ossian19Fm4Op {
 #[wasm_bindgen]
 pub struct Ossian19Fm6Op {
     voice_manager: Fm6OpVoiceManager,
+    /// Voices of the last loaded DX7 cartridge
+    cartridge: Vec<Dx7VoiceData>,
 }
 
 #[wasm_bindgen]
@@ -541,27 +681,30 @@ impl Ossian19Fm6Op {
     /// Create a new 6-op FM synthesizer
     #[wasm_bindgen(constructor)]
     pub fn new(sample_rate: f32, num_voices: u32) -> Self {
+        let mut voice_manager = Fm6OpVoiceManager::new(num_voices as usize, sample_rate);
+        voice_manager.set_instance_seed(next_instance_seed());
         Self {
-            voice_manager: Fm6OpVoiceManager::new(num_voices as usize, sample_rate),
+            voice_manager,
+            cartridge: Vec::new(),
         }
     }
 
+    /// Seed this instance's randomness (each instance gets its own by default)
+    #[wasm_bindgen(js_name = setInstanceSeed)]
+    pub fn set_instance_seed(&mut self, seed: u32) {
+        self.voice_manager.set_instance_seed(seed);
+    }
+
     /// Process mono audio
     #[wasm_bindgen]
     pub fn process(&mut self, buffer: &mut [f32]) {
-        for sample in buffer.iter_mut() {
-            *sample = self.voice_manager.tick();
-        }
+        self.voice_manager.process(buffer);
     }
 
     /// Process stereo audio (mono->stereo)
     #[wasm_bindgen(js_name = processStereo)]
     pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
-        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
-            let sample = self.voice_manager.tick();
-            *l = sample;
-            *r = sample;
-        }
+        self.voice_manager.process_stereo(left, right);
     }
 
     /// Note on
@@ -576,12 +719,42 @@ impl Ossian19Fm6Op {
         self.voice_manager.note_off(note);
     }
 
-    /// Panic - stop all voices
+    /// Start a note at any frequency in Hz; returns its id for `noteOffId`
+    #[wasm_bindgen(js_name = noteOnFreq)]
+    pub fn note_on_freq(&mut self, freq: f32, velocity: u8) -> u32 {
+        self.voice_manager.note_on_freq(freq, velocity as f32 / 127.0)
+    }
+
+    /// Release a note started with `noteOnFreq`
+    #[wasm_bindgen(js_name = noteOffId)]
+    pub fn note_off_id(&mut self, id: u32) {
+        self.voice_manager.note_off_id(id);
+    }
+
+    /// Panic - fade all sound out over the panic fade, then stop every voice
     #[wasm_bindgen]
     pub fn panic(&mut self) {
         self.voice_manager.panic();
     }
 
+    /// Stop every voice immediately, without the panic fade
+    #[wasm_bindgen(js_name = panicNow)]
+    pub fn panic_now(&mut self) {
+        self.voice_manager.panic_now();
+    }
+
+    /// Panic fade length in ms (0 = instant)
+    #[wasm_bindgen(js_name = setPanicFadeMs)]
+    pub fn set_panic_fade_ms(&mut self, ms: f32) {
+        self.voice_manager.set_panic_fade_ms(ms);
+    }
+
+    /// Clear all DSP state (filters, envelopes, LFOs) while keeping the parameters
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.voice_manager.reset();
+    }
+
     /// Get active voice count
     #[wasm_bindgen(js_name = activeVoiceCount)]
     pub fn active_voice_count(&self) -> usize {
@@ -602,6 +775,12 @@ impl Ossian19Fm6Op {
         self.voice_manager.get_algorithm()
     }
 
+    /// Get carrier operators of the current algorithm as a bitmask (bit 0 = OP1)
+    #[wasm_bindgen(js_name = getCarriers)]
+    pub fn get_carriers(&self) -> u8 {
+        Dx7Algorithm::from_u8(self.voice_manager.get_algorithm()).carrier_mask()
+    }
+
     // === Operator Controls (0-5 for OP1-OP6) ===
 
     /// Set operator ratio (frequency multiplier)
@@ -628,12 +807,24 @@ impl Ossian19Fm6Op {
         self.voice_manager.get_op_ratio(op as usize)
     }
 
+    /// Run an operator at a fixed frequency in Hz (a negative value tracks the note again)
+    #[wasm_bindgen(js_name = setOpFixedFreq)]
+    pub fn set_op_fixed_freq(&mut self, op: u8, freq: f32) {
+        self.voice_manager.set_op_fixed_freq(op as usize, (freq >= 0.0).then_some(freq));
+    }
+
     /// Set operator detune in cents (-100 to +100)
     #[wasm_bindgen(js_name = setOpDetune)]
     pub fn set_op_detune(&mut self, op: u8, detune: f32) {
         self.voice_manager.set_op_detune(op as usize, detune);
     }
 
+    /// Keyboard rate scaling (0-1): shorter operator envelopes on higher notes
+    #[wasm_bindgen(js_name = setOpRateScaling)]
+    pub fn set_op_rate_scaling(&mut self, op: u8, amount: f32) {
+        self.voice_manager.set_op_rate_scaling(op as usize, amount);
+    }
+
     /// Set operator envelope attack
     #[wasm_bindgen(js_name = setOpAttack)]
     pub fn set_op_attack(&mut self, op: u8, attack: f32) {
@@ -690,6 +881,28 @@ impl Ossian19Fm6Op {
         self.voice_manager.set_filter_resonance(resonance);
     }
 
+    /// Set filter type (0 = low-pass, 1 = high-pass, 2 = band-pass)
+    #[wasm_bindgen(js_name = setFilterType)]
+    pub fn set_filter_type(&mut self, filter_type: u8) {
+        self.voice_manager.set_filter_type(match filter_type {
+            1 => FilterType::HighPass,
+            2 => FilterType::BandPass,
+            _ => FilterType::LowPass,
+        });
+    }
+
+    /// Set filter slope (0 = 6dB/oct, 1 = 12dB/oct, 2 = 24dB/oct)
+    #[wasm_bindgen(js_name = setFilterSlope)]
+    pub fn set_filter_slope(&mut self, slope: u8) {
+        self.voice_manager.set_filter_slope(FilterSlope::from_u8(slope));
+    }
+
+    /// Set filter input drive (1.0 = unity)
+    #[wasm_bindgen(js_name = setFilterDrive)]
+    pub fn set_filter_drive(&mut self, drive: f32) {
+        self.voice_manager.set_filter_drive(drive);
+    }
+
     // === Vibrato Controls ===
 
     /// Set vibrato depth in cents (0-100)
@@ -711,6 +924,23 @@ impl Ossian19Fm6Op {
         self.voice_manager.set_master_volume(volume);
     }
 
+    /// Set master volume in dB (0 dB = unity and the maximum), ramped while notes sound
+    #[wasm_bindgen(js_name = setMasterVolumeDb)]
+    pub fn set_master_volume_db(&mut self, db: f32) {
+        self.voice_manager.set_master_volume_db(db);
+    }
+
+    #[wasm_bindgen(js_name = setMasterPan)]
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.voice_manager.set_master_pan(pan);
+    }
+
+    /// Set anti-aliasing quality (0 = Draft, 1 = Normal, 2 = High, 3 = Ultra)
+    #[wasm_bindgen(js_name = setQuality)]
+    pub fn set_quality(&mut self, quality: u8) {
+        self.voice_manager.set_quality(Quality::from_u8(quality));
+    }
+
     /// Set all parameters for an operator at once
     #[wasm_bindgen(js_name = setOperator)]
     pub fn set_operator(
@@ -756,4 +986,32 @@ impl Ossian19Fm6Op {
             self.voice_manager.get_op_ratio(5),
         )
     }
+
+    /// Describe all parameters (id, name, min, max, default, unit) as JSON
+    #[wasm_bindgen(js_name = getParamSchema)]
+    pub fn get_param_schema(&self) -> String {
+        serde_json::to_string(&describe(Engine::Fm)).unwrap_or_default()
+    }
+
+    // === DX7 Cartridges ===
+
+    /// Load a 32-voice DX7 bank (.syx or raw) and return its voice names
+    #[wasm_bindgen(js_name = loadDx7Cartridge)]
+    pub fn load_dx7_cartridge(&mut self, bytes: &[u8]) -> Result<Vec<String>, JsValue> {
+        let voices = parse_bank(bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let names = voices.iter().map(|voice| voice.name.clone()).collect();
+        self.cartridge = voices;
+        Ok(names)
+    }
+
+    /// Apply a voice (0-31) of the loaded cartridge
+    #[wasm_bindgen(js_name = selectDx7Voice)]
+    pub fn select_dx7_voice(&mut self, index: u32) -> Result<(), JsValue> {
+        let voice = self
+            .cartridge
+            .get(index as usize)
+            .ok_or_else(|| JsValue::from_str("no cartridge voice at that index"))?;
+        self.voice_manager.load_dx7_voice(voice);
+        Ok(())
+    }
 }
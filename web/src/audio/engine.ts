@@ -49,7 +49,7 @@ export const defaultParams: SynthParams = {
   fmAmount: 0.0,   // FM pois (subtractive mode)
   fmRatio: 2.0,    // Klassinen 2:1 ratio
   filterCutoff: 5000,
-  filterResonance: 0.3,
+  filterResonance: 0.16, // Nupin asento (moottorissa ≈ 0.3)
   filterSlope: 2,  // 24dB/oct (Moog-style)
   filterEnvAmount: 0.5,
   ampAttack: 0.01,